@@ -58,7 +58,7 @@ pub mod tests {
     use cas::memory::MemoryStorage;
     use holochain_core_types::cas::{
         content::{ExampleAddressableContent, OtherExampleAddressableContent},
-        storage::StorageTestSuite,
+        storage::{DynContentAddressableStorage, StorageTestSuite},
     };
 
     pub fn test_memory_storage() -> MemoryStorage {
@@ -74,4 +74,29 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// show that a memory storage still round trips behind a trait object
+    fn dyn_memory_round_trip() {
+        let test_suite =
+            StorageTestSuite::new(DynContentAddressableStorage::new(test_memory_storage()));
+        test_suite.round_trip_test::<ExampleAddressableContent, OtherExampleAddressableContent>(
+            String::from("foo"),
+            String::from("bar"),
+        );
+    }
+
+    #[test]
+    /// show that wrapped storages compare like the storages they wrap
+    fn dyn_memory_eq() {
+        let storage = test_memory_storage();
+        let dyn_storage = DynContentAddressableStorage::new(storage.clone());
+
+        assert_eq!(dyn_storage, dyn_storage.clone());
+        assert_eq!(dyn_storage, DynContentAddressableStorage::new(storage));
+        assert_ne!(
+            dyn_storage,
+            DynContentAddressableStorage::new(test_memory_storage())
+        );
+    }
+
 }
@@ -42,9 +42,12 @@ impl EntityAttributeValueStorage for EavMemoryStorage {
 #[cfg(test)]
 pub mod tests {
     use eav::memory::EavMemoryStorage;
-    use holochain_core_types::cas::{
-        content::{AddressableContent, ExampleAddressableContent},
-        storage::EavTestSuite,
+    use holochain_core_types::{
+        cas::{
+            content::{AddressableContent, ExampleAddressableContent},
+            storage::EavTestSuite,
+        },
+        eav::DynEntityAttributeValueStorage,
     };
 
     #[test]
@@ -74,4 +77,19 @@ pub mod tests {
         EavTestSuite::test_many_to_one::<ExampleAddressableContent, EavMemoryStorage>(eav_storage)
     }
 
+    #[test]
+    fn dyn_memory_eav_round_trip() {
+        let entity_content = ExampleAddressableContent::from_content(&"foo".to_string());
+        let attribute = "favourite-color".to_string();
+        let value_content = ExampleAddressableContent::from_content(&"blue".to_string());
+        EavTestSuite::test_round_trip(
+            DynEntityAttributeValueStorage::new(
+                EavMemoryStorage::new().expect("could not construct new eav memory storage"),
+            ),
+            entity_content,
+            attribute,
+            value_content,
+        )
+    }
+
 }
@@ -0,0 +1,392 @@
+//! Admin interfaces (`admin = true` in their configuration) expose the container's admin API
+//! next to the zome functions, so clients can install DNAs, manage agents and manage instances
+//! at runtime:
+//! * `admin/dna/install` `{"id", "path", "expected_hash"}`, returns the hash of the DNA
+//! * `admin/dna/uninstall` `{"id"}`
+//! * `admin/agent/add` `{"id", "name"}`
+//! * `admin/instance/create` `{"id", "dna", "agent", "storage", "interfaces"}`, storage
+//!   defaults to memory, the interfaces with the given ids expose the new instance
+//! * `admin/instance/remove`, `admin/instance/start`, `admin/instance/stop` `{"id"}`
+//!
+//! Interfaces that start or stop exposing an instance restart right after the call returned.
+//! * `admin/instance/list`, returns the id, DNA, agent, activity and status of every instance
+//!
+//! Changes get saved to the configuration file of the container.
+//! @see doc/architecture/decisions/0015-container-api.md
+
+use config::{InstanceConfiguration, StorageConfiguration};
+use container::Container;
+use holochain_core::nucleus::ribosome::metering::ExecutionLimits;
+use holochain_core_api::error::HolochainResult;
+use interface::rpc_error;
+use jsonrpc_core::{Error as RpcError, MetaIoHandler, Metadata, Params, Value};
+use serde::de::DeserializeOwned;
+use serde_json;
+use std::sync::{Mutex, Weak};
+
+#[derive(Deserialize)]
+struct IdParams {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct InstallDnaParams {
+    id: String,
+    path: String,
+    #[serde(default)]
+    expected_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AddAgentParams {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CreateInstanceParams {
+    id: String,
+    dna: String,
+    agent: String,
+    #[serde(default)]
+    storage: Option<StorageConfiguration>,
+    #[serde(default)]
+    limits: ExecutionLimits,
+    #[serde(default)]
+    interfaces: Vec<String>,
+}
+
+/// Adds the admin methods, which manage the container behind the given handle for as long as
+/// it exists.
+pub fn add_admin_functions<M: Metadata>(
+    io: &mut MetaIoHandler<M>,
+    container: &Weak<Mutex<Container>>,
+) {
+    add_admin_method(io, "admin/dna/install", container, |container, params| {
+        let params: InstallDnaParams = params.parse()?;
+        container
+            .install_dna_from_file(&params.id, &params.path, params.expected_hash)
+            .map(Value::String)
+            .map_err(rpc_error)
+    });
+    add_admin_method(io, "admin/dna/uninstall", container, |container, params| {
+        let params: IdParams = params.parse()?;
+        respond(container.uninstall_dna(&params.id))
+    });
+    add_admin_method(io, "admin/agent/add", container, |container, params| {
+        let params: AddAgentParams = params.parse()?;
+        respond(container.add_agent(&params.id, &params.name))
+    });
+    add_admin_method(
+        io,
+        "admin/instance/create",
+        container,
+        |container, params| {
+            let params: CreateInstanceParams = params.parse()?;
+            let instance_config = InstanceConfiguration {
+                id: params.id,
+                dna: params.dna,
+                agent: params.agent,
+                storage: params.storage.unwrap_or(StorageConfiguration::Memory),
+                limits: params.limits,
+            };
+            // the call might have come in through one of the interfaces
+            container
+                .add_instance(instance_config, &params.interfaces)
+                .map_err(rpc_error)?;
+            container.restart_interfaces_later(params.interfaces);
+            Ok(Value::Bool(true))
+        },
+    );
+    add_admin_method(
+        io,
+        "admin/instance/remove",
+        container,
+        |container, params| {
+            let params: IdParams = params.parse()?;
+            let interface_ids = container.drop_instance(&params.id).map_err(rpc_error)?;
+            container.restart_interfaces_later(interface_ids);
+            Ok(Value::Bool(true))
+        },
+    );
+    add_admin_method(
+        io,
+        "admin/instance/start",
+        container,
+        |container, params| {
+            let params: IdParams = params.parse()?;
+            respond(container.start_instance(&params.id))
+        },
+    );
+    add_admin_method(io, "admin/instance/stop", container, |container, params| {
+        let params: IdParams = params.parse()?;
+        respond(container.stop_instance(&params.id))
+    });
+    add_admin_method(io, "admin/instance/list", container, |container, _| {
+        let instances = container.list_instances().map_err(rpc_error)?;
+        serde_json::to_value(instances).map_err(|_| RpcError::internal_error())
+    });
+}
+
+fn add_admin_method<M, F>(
+    io: &mut MetaIoHandler<M>,
+    name: &str,
+    container: &Weak<Mutex<Container>>,
+    method: F,
+) where
+    M: Metadata,
+    F: Fn(&mut Container, Params) -> Result<Value, RpcError> + Send + Sync + 'static,
+{
+    let container = container.clone();
+    io.add_method(name, move |params: Params| {
+        // the container has shut down
+        let container = container.upgrade().ok_or_else(RpcError::internal_error)?;
+        let mut container = container.lock().map_err(|_| RpcError::internal_error())?;
+        method(&mut container, params)
+    });
+}
+
+fn respond(result: HolochainResult<()>) -> Result<Value, RpcError> {
+    result.map(|_| Value::Bool(true)).map_err(rpc_error)
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+
+    use self::tempfile::tempdir;
+    use super::*;
+    use config::{load_configuration, InterfaceConfiguration, InterfaceDriver};
+    use container::{dna_hash, tests::test_config, ContainerHandle, InstanceInfo};
+    use holochain_core::nucleus::state::NucleusStatus;
+    use holochain_dna::Dna;
+    use jsonrpc_core::IoHandler;
+    use std::{
+        fs,
+        io::{self, Read, Write},
+        net::{SocketAddr, TcpStream},
+        path::Path,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    fn admin_handler(container: &ContainerHandle) -> IoHandler {
+        let mut io = IoHandler::new();
+        add_admin_functions(&mut *io, &Arc::downgrade(container));
+        io
+    }
+
+    fn call(io: &IoHandler, method: &str, params: Value) -> Value {
+        let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        let response = io
+            .handle_request_sync(&request.to_string())
+            .expect("a call should get a response");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    /// a container without instances, saving its configuration to container.toml in dir
+    fn empty_container(dir: &Path) -> ContainerHandle {
+        let mut config = test_config(dir, Vec::new());
+        config.dnas.clear();
+        config.agents.clear();
+        let config_file = dir.join("container.toml");
+        fs::write(&config_file, config.to_toml_string().unwrap()).unwrap();
+        Container::from_config_file(&config_file)
+            .unwrap()
+            .into_handle()
+    }
+
+    #[test]
+    fn manages_instances_through_rpc() {
+        let dir = tempdir().unwrap();
+        let container = empty_container(dir.path());
+        let io = admin_handler(&container);
+        let dna_file = dir
+            .path()
+            .join("app.dna.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let dna = Dna::from_json_str(&fs::read_to_string(&dna_file).unwrap()).unwrap();
+
+        let response = call(
+            &io,
+            "admin/dna/install",
+            json!({"id": "app", "path": dna_file, "expected_hash": "QmWrong"}),
+        );
+        assert_eq!(response["error"]["code"], json!(-32602));
+        let response = call(
+            &io,
+            "admin/dna/install",
+            json!({"id": "app", "path": dna_file, "expected_hash": dna_hash(&dna)}),
+        );
+        assert_eq!(response["result"], json!(dna_hash(&dna)));
+
+        let response = call(&io, "admin/agent/add", json!({"id": "bob", "name": "Bob"}));
+        assert_eq!(response["result"], json!(true));
+        let response = call(
+            &io,
+            "admin/instance/create",
+            json!({"id": "app_bob", "dna": "app", "agent": "bob"}),
+        );
+        assert_eq!(response["result"], json!(true));
+        let response = call(&io, "admin/instance/start", json!({"id": "app_bob"}));
+        assert_eq!(response["result"], json!(true));
+
+        let response = call(&io, "admin/instance/list", json!({}));
+        let instances: Vec<InstanceInfo> =
+            serde_json::from_value(response["result"].clone()).unwrap();
+        assert_eq!(
+            instances,
+            vec![InstanceInfo {
+                id: "app_bob".to_string(),
+                dna: "app".to_string(),
+                agent: "bob".to_string(),
+                active: true,
+                status: NucleusStatus::Initialized,
+            }]
+        );
+
+        // the DNA can't go while an instance runs it
+        let response = call(&io, "admin/dna/uninstall", json!({"id": "app"}));
+        assert_eq!(response["error"]["code"], json!(-32602));
+        let response = call(&io, "admin/instance/remove", json!({"id": "app_bob"}));
+        assert_eq!(response["result"], json!(true));
+        let response = call(&io, "admin/dna/uninstall", json!({"id": "app"}));
+        assert_eq!(response["result"], json!(true));
+        let response = call(&io, "admin/instance/list", json!({}));
+        assert_eq!(response["result"], json!([]));
+    }
+
+    /// posts a call to an HTTP interface and returns the raw response
+    fn post(address: SocketAddr, method: &str, params: Value) -> io::Result<String> {
+        let body = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1});
+        let body = body.to_string();
+        let mut stream = TcpStream::connect(address)?;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    }
+
+    /// calls main of the instance "app_bob" through the admin interface until the response
+    /// contains the expected part, as the interface restarts after admin calls
+    fn wait_for_main(container: &ContainerHandle, expected: &str) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let address = container
+                .lock()
+                .unwrap()
+                .interface("admin")
+                .and_then(|server| server.address());
+            let response = address
+                .and_then(|address| {
+                    post(address, "app_bob/test_zome/test_cap/main", json!({})).ok()
+                })
+                .unwrap_or_default();
+            if response.contains(expected) {
+                return;
+            }
+            assert!(
+                Instant::now() < deadline,
+                "unexpected response: {}",
+                response
+            );
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    /// test that an admin interface serves the instances created through it right away
+    fn serves_created_instances() {
+        let dir = tempdir().unwrap();
+        let mut config = test_config(dir.path(), Vec::new());
+        config.interfaces.push(InterfaceConfiguration {
+            id: "admin".to_string(),
+            driver: InterfaceDriver::Http { port: 0 },
+            admin: true,
+            instances: Vec::new(),
+        });
+        let container = Container::from_config(config).into_handle();
+        container.lock().unwrap().start_all_interfaces().unwrap();
+        let address = container
+            .lock()
+            .unwrap()
+            .interface("admin")
+            .and_then(|server| server.address())
+            .unwrap();
+
+        let response = post(
+            address,
+            "admin/instance/create",
+            json!({"id": "app_bob", "dna": "app", "agent": "bob", "interfaces": ["admin"]}),
+        ).unwrap();
+        assert!(response.contains(r#""result":true"#), "{}", response);
+        container.lock().unwrap().start_instance("app_bob").unwrap();
+        wait_for_main(&container, r#""result":1337"#);
+
+        let address = container
+            .lock()
+            .unwrap()
+            .interface("admin")
+            .and_then(|server| server.address())
+            .unwrap();
+        let response = post(address, "admin/instance/remove", json!({"id": "app_bob"})).unwrap();
+        assert!(response.contains(r#""result":true"#), "{}", response);
+        // unknown method
+        wait_for_main(&container, r#""code":-32601"#);
+    }
+
+    #[test]
+    fn saves_changes_to_the_config_file() {
+        let dir = tempdir().unwrap();
+        let container = empty_container(dir.path());
+        let dna_file = dir
+            .path()
+            .join("app.dna.json")
+            .to_str()
+            .unwrap()
+            .to_string();
+        {
+            let mut container = container.lock().unwrap();
+            container
+                .install_dna_from_file("app", &dna_file, None)
+                .unwrap();
+            container.add_agent("bob", "Bob").unwrap();
+            container
+                .create_instance(
+                    InstanceConfiguration {
+                        id: "app_bob".to_string(),
+                        dna: "app".to_string(),
+                        agent: "bob".to_string(),
+                        storage: StorageConfiguration::Memory,
+                        limits: ExecutionLimits::default(),
+                    },
+                    &[],
+                ).unwrap();
+        }
+
+        let saved = load_configuration(dir.path().join("container.toml")).unwrap();
+        assert_eq!(&saved, container.lock().unwrap().config());
+        assert!(saved.dna_by_id("app").unwrap().hash.is_some());
+        assert!(saved.instance_by_id("app_bob").is_some());
+    }
+
+    #[test]
+    fn fails_once_the_container_is_gone() {
+        let dir = tempdir().unwrap();
+        let container = empty_container(dir.path());
+        let io = admin_handler(&container);
+        drop(container);
+
+        let response = call(&io, "admin/instance/list", json!({}));
+        assert_eq!(response["error"]["code"], json!(-32603));
+    }
+}
@@ -0,0 +1,374 @@
+//! Declarative description of everything a container runs: agents, DNAs, instances
+//! (an agent running a DNA on some storage) and the interfaces clients reach them through.
+//! Configurations get loaded from TOML or JSON files, e.g.:
+//!
+//! ```toml
+//! [[agents]]
+//! id = "bob"
+//! name = "Bob"
+//!
+//! [[dnas]]
+//! id = "app"
+//! file = "app.dna.json"
+//!
+//! [[instances]]
+//! id = "app_bob"
+//! dna = "app"
+//! agent = "bob"
+//! [instances.storage]
+//! type = "file"
+//! path = "/var/lib/holochain/app_bob"
+//! [instances.limits]
+//! max_instructions = 100000000
+//!
+//! [[interfaces]]
+//! id = "http"
+//! [interfaces.driver]
+//! type = "http"
+//! port = 4000
+//! [[interfaces.instances]]
+//! id = "app_bob"
+//! ```
+
+use holochain_core::nucleus::ribosome::metering::ExecutionLimits;
+use holochain_core_types::error::HolochainError;
+use serde_json;
+use std::{collections::HashSet, fs, path::Path};
+use toml;
+
+/// Everything a container runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Configuration {
+    #[serde(default)]
+    pub agents: Vec<AgentConfiguration>,
+    #[serde(default)]
+    pub dnas: Vec<DnaConfiguration>,
+    #[serde(default)]
+    pub instances: Vec<InstanceConfiguration>,
+    #[serde(default)]
+    pub interfaces: Vec<InterfaceConfiguration>,
+}
+
+/// An agent identity instances can run as.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AgentConfiguration {
+    pub id: String,
+    pub name: String,
+}
+
+/// A DNA file instances can run.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DnaConfiguration {
+    pub id: String,
+    pub file: String,
+    /// hash of the DNA, checked whenever the file gets loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// An agent running a DNA, referencing both by their ids.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceConfiguration {
+    pub id: String,
+    pub dna: String,
+    pub agent: String,
+    pub storage: StorageConfiguration,
+    /// resources each zome call of the instance may use, defaults for those not given
+    #[serde(default)]
+    pub limits: ExecutionLimits,
+}
+
+/// Where an instance keeps its source chain, DHT shard and state.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StorageConfiguration {
+    /// nothing survives the container
+    Memory,
+    /// everything gets stored below the given directory, so the instance can be loaded again
+    File { path: String },
+}
+
+/// A way for clients to reach some of the instances.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceConfiguration {
+    pub id: String,
+    pub driver: InterfaceDriver,
+    /// admin interfaces can manage the container itself
+    #[serde(default)]
+    pub admin: bool,
+    /// the instances this interface exposes
+    #[serde(default)]
+    pub instances: Vec<InstanceReferenceConfiguration>,
+}
+
+/// Protocol and address an interface gets served on.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum InterfaceDriver {
+    Http {
+        port: u16,
+    },
+    Websocket {
+        port: u16,
+    },
+    /// unix domain socket at the given path
+    Socket {
+        file: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceReferenceConfiguration {
+    pub id: String,
+}
+
+impl Configuration {
+    pub fn from_toml_str(toml_str: &str) -> Result<Self, HolochainError> {
+        let config: Configuration = toml::from_str(toml_str).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not parse TOML configuration: {}", e))
+        })?;
+        config.check_consistency()?;
+        Ok(config)
+    }
+
+    pub fn from_json_str(json_str: &str) -> Result<Self, HolochainError> {
+        let config: Configuration = serde_json::from_str(json_str).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not parse JSON configuration: {}", e))
+        })?;
+        config.check_consistency()?;
+        Ok(config)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, HolochainError> {
+        // going through toml::Value puts plain values before tables, as TOML requires
+        toml::Value::try_from(self)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .map_err(|e| {
+                HolochainError::ConfigError(format!("Could not serialize configuration: {}", e))
+            })
+    }
+
+    pub fn to_json_string(&self) -> Result<String, HolochainError> {
+        serde_json::to_string_pretty(self).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not serialize configuration: {}", e))
+        })
+    }
+
+    /// Makes sure ids are unique and every reference points to something that is configured.
+    pub fn check_consistency(&self) -> Result<(), HolochainError> {
+        check_unique_ids("agent", self.agents.iter().map(|agent| &agent.id))?;
+        check_unique_ids("DNA", self.dnas.iter().map(|dna| &dna.id))?;
+        check_unique_ids(
+            "instance",
+            self.instances.iter().map(|instance| &instance.id),
+        )?;
+        check_unique_ids(
+            "interface",
+            self.interfaces.iter().map(|interface| &interface.id),
+        )?;
+
+        for instance in self.instances.iter() {
+            self.agent_by_id(&instance.agent).ok_or_else(|| {
+                HolochainError::ConfigError(format!(
+                    "Agent '{}' of instance '{}' is not configured",
+                    instance.agent, instance.id
+                ))
+            })?;
+            self.dna_by_id(&instance.dna).ok_or_else(|| {
+                HolochainError::ConfigError(format!(
+                    "DNA '{}' of instance '{}' is not configured",
+                    instance.dna, instance.id
+                ))
+            })?;
+        }
+        for interface in self.interfaces.iter() {
+            for reference in interface.instances.iter() {
+                self.instance_by_id(&reference.id).ok_or_else(|| {
+                    HolochainError::ConfigError(format!(
+                        "Instance '{}' of interface '{}' is not configured",
+                        reference.id, interface.id
+                    ))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn agent_by_id(&self, id: &str) -> Option<&AgentConfiguration> {
+        self.agents.iter().find(|agent| agent.id == id)
+    }
+
+    pub fn dna_by_id(&self, id: &str) -> Option<&DnaConfiguration> {
+        self.dnas.iter().find(|dna| dna.id == id)
+    }
+
+    pub fn instance_by_id(&self, id: &str) -> Option<&InstanceConfiguration> {
+        self.instances.iter().find(|instance| instance.id == id)
+    }
+
+    pub fn interface_by_id(&self, id: &str) -> Option<&InterfaceConfiguration> {
+        self.interfaces.iter().find(|interface| interface.id == id)
+    }
+
+    /// The ids of the instances running the given DNA.
+    pub fn instances_of_dna(&self, dna_id: &str) -> Vec<String> {
+        self.instances
+            .iter()
+            .filter(|instance| instance.dna == dna_id)
+            .map(|instance| instance.id.clone())
+            .collect()
+    }
+}
+
+fn check_unique_ids<'a, I>(kind: &str, ids: I) -> Result<(), HolochainError>
+where
+    I: Iterator<Item = &'a String>,
+{
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) {
+            return Err(HolochainError::ConfigError(format!(
+                "Duplicate {} id '{}'",
+                kind, id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Loads a configuration from a file, parsed as JSON if the file name ends with .json
+/// and as TOML otherwise.
+pub fn load_configuration<P: AsRef<Path>>(path: P) -> Result<Configuration, HolochainError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => Configuration::from_json_str(&contents),
+        _ => Configuration::from_toml_str(&contents),
+    }
+}
+
+/// Writes a configuration to a file, as JSON if the file name ends with .json
+/// and as TOML otherwise, so load_configuration() reads it back.
+pub fn save_configuration<P: AsRef<Path>>(
+    config: &Configuration,
+    path: P,
+) -> Result<(), HolochainError> {
+    let path = path.as_ref();
+    let contents = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => config.to_json_string()?,
+        _ => config.to_toml_string()?,
+    };
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+
+    use self::tempfile::tempdir;
+    use super::*;
+
+    pub fn test_toml() -> &'static str {
+        r#"
+[[agents]]
+id = "bob"
+name = "Bob"
+
+[[dnas]]
+id = "app"
+file = "app.dna.json"
+
+[[instances]]
+id = "app_bob"
+dna = "app"
+agent = "bob"
+[instances.storage]
+type = "memory"
+[instances.limits]
+max_call_depth = 256
+
+[[interfaces]]
+id = "http"
+[interfaces.driver]
+type = "http"
+port = 4000
+[[interfaces.instances]]
+id = "app_bob"
+"#
+    }
+
+    #[test]
+    fn parses_toml_and_json() {
+        let config = Configuration::from_toml_str(test_toml()).unwrap();
+        assert_eq!(config.agent_by_id("bob").unwrap().name, "Bob");
+        assert_eq!(config.dna_by_id("app").unwrap().file, "app.dna.json");
+        assert_eq!(
+            config.instance_by_id("app_bob").unwrap().storage,
+            StorageConfiguration::Memory
+        );
+        assert_eq!(
+            config.instance_by_id("app_bob").unwrap().limits,
+            ExecutionLimits {
+                max_call_depth: 256,
+                ..Default::default()
+            }
+        );
+        let interface = config.interface_by_id("http").unwrap();
+        assert_eq!(interface.driver, InterfaceDriver::Http { port: 4000 });
+        assert!(!interface.admin);
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(Configuration::from_json_str(&json), Ok(config));
+    }
+
+    #[test]
+    fn saves_what_it_loads() {
+        let dir = tempdir().unwrap();
+        let mut config = Configuration::from_toml_str(test_toml()).unwrap();
+        config.dnas[0].hash = Some("QmHash".to_string());
+        config.interfaces[0].admin = true;
+
+        for file in vec!["container.toml", "container.json"] {
+            let path = dir.path().join(file);
+            save_configuration(&config, &path).unwrap();
+            assert_eq!(load_configuration(&path), Ok(config.clone()));
+        }
+    }
+
+    #[test]
+    fn rejects_dangling_references() {
+        let toml = test_toml().replace("agent = \"bob\"", "agent = \"alice\"");
+        assert_eq!(
+            Configuration::from_toml_str(&toml),
+            Err(HolochainError::ConfigError(
+                "Agent 'alice' of instance 'app_bob' is not configured".to_string()
+            ))
+        );
+
+        let toml = test_toml().replace(
+            "[[interfaces.instances]]\nid = \"app_bob\"",
+            "[[interfaces.instances]]\nid = \"app_alice\"",
+        );
+        assert_eq!(
+            Configuration::from_toml_str(&toml),
+            Err(HolochainError::ConfigError(
+                "Instance 'app_alice' of interface 'http' is not configured".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let toml = format!(
+            "[[agents]]\nid = \"bob\"\nname = \"Bobby\"\n{}",
+            test_toml()
+        );
+        assert_eq!(
+            Configuration::from_toml_str(&toml),
+            Err(HolochainError::ConfigError(
+                "Duplicate agent id 'bob'".to_string()
+            ))
+        );
+    }
+}
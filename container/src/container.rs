@@ -0,0 +1,710 @@
+//! The Container owns the Holochain instances of a Configuration and manages their lifecycle.
+//! @see doc/architecture/decisions/0015-container-api.md
+
+use config::{
+    load_configuration, save_configuration, AgentConfiguration, Configuration, DnaConfiguration,
+    InstanceConfiguration, InstanceReferenceConfiguration, StorageConfiguration,
+};
+use holochain_agent::Agent;
+use holochain_cas_implementations::{
+    cas::{file::FilesystemStorage, memory::MemoryStorage},
+    eav::{file::EavFileStorage, memory::EavMemoryStorage},
+};
+use holochain_core::{
+    context::Context,
+    logger::{Logger, SimpleLogger},
+    nucleus::state::NucleusStatus,
+    persister::{FilePersister, SimplePersister},
+};
+use holochain_core_api::{error::HolochainResult, Holochain};
+use holochain_core_types::error::HolochainError;
+use holochain_dna::{package::PACKAGE_EXTENSION, Dna};
+use interface::{start_interface, InterfaceServer};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Shared handle on an instance, so interfaces can call it from their own threads.
+pub type InstanceHandle = Arc<Mutex<Holochain>>;
+
+/// Shared handle on a container, so admin interfaces can manage it from their own threads.
+pub type ContainerHandle = Arc<Mutex<Container>>;
+
+/// How long a restarting interface waits for its closed server to free its address.
+const INTERFACE_RESTART_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Runs the instances of a configuration.
+pub struct Container {
+    config: Configuration,
+    /// where admin changes to the configuration get saved
+    config_path: Option<PathBuf>,
+    instances: HashMap<String, InstanceHandle>,
+    interfaces: HashMap<String, InterfaceServer>,
+    logger: Arc<Mutex<Logger>>,
+    /// set by into_handle(), admin interfaces can only be served with it
+    handle: Option<Weak<Mutex<Container>>>,
+}
+
+/// What list_instances() reports about an instance.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub id: String,
+    pub dna: String,
+    pub agent: String,
+    pub active: bool,
+    pub status: NucleusStatus,
+}
+
+impl Container {
+    /// Creates a container for the given configuration, without instantiating anything yet.
+    pub fn from_config(config: Configuration) -> Self {
+        Container {
+            config,
+            config_path: None,
+            instances: HashMap::new(),
+            interfaces: HashMap::new(),
+            logger: Arc::new(Mutex::new(SimpleLogger {})),
+            handle: None,
+        }
+    }
+
+    /// Creates a container for the configuration in the given file.
+    /// Changes made through the admin API get saved back to that file.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> HolochainResult<Self> {
+        let config = load_configuration(&path)?;
+        let mut container = Container::from_config(config);
+        container.config_path = Some(path.as_ref().to_path_buf());
+        Ok(container)
+    }
+
+    /// Moves the container behind a shared handle, which admin interfaces need to manage it.
+    pub fn into_handle(self) -> ContainerHandle {
+        let handle = Arc::new(Mutex::new(self));
+        {
+            let mut container = handle.lock().expect("nobody else can hold the lock yet");
+            container.handle = Some(Arc::downgrade(&handle));
+        }
+        handle
+    }
+
+    /// Sets the logger instances created from now on log to.
+    pub fn with_logger(mut self, logger: Arc<Mutex<Logger>>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    pub fn config(&self) -> &Configuration {
+        &self.config
+    }
+
+    /// Instantiates every configured instance that doesn't exist in this container yet.
+    /// Instances run genesis, or load their state if their storage already holds one,
+    /// but don't get started.
+    pub fn load_config(&mut self) -> HolochainResult<()> {
+        for instance_config in self.config.instances.clone() {
+            if self.instances.contains_key(&instance_config.id) {
+                continue;
+            }
+            let holochain = instantiate_from_config(&instance_config, &self.config, &self.logger)?;
+            self.instances
+                .insert(instance_config.id.clone(), Arc::new(Mutex::new(holochain)));
+        }
+        Ok(())
+    }
+
+    /// Starts every instance that is not active.
+    pub fn start_all_instances(&mut self) -> HolochainResult<()> {
+        for holochain in self.instances.values() {
+            let mut holochain = lock_instance(holochain)?;
+            if !holochain.active() {
+                holochain.start()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stops every active instance.
+    pub fn stop_all_instances(&mut self) -> HolochainResult<()> {
+        for holochain in self.instances.values() {
+            let mut holochain = lock_instance(holochain)?;
+            if holochain.active() {
+                holochain.stop()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Starts serving every configured interface that is not running yet.
+    /// Instances have to be loaded before the interfaces exposing them.
+    pub fn start_all_interfaces(&mut self) -> HolochainResult<()> {
+        for interface_config in self.config.interfaces.iter() {
+            if self.interfaces.contains_key(&interface_config.id) {
+                continue;
+            }
+            let server = start_interface(interface_config, &self.instances, self.handle.clone())?;
+            self.interfaces.insert(interface_config.id.clone(), server);
+        }
+        Ok(())
+    }
+
+    /// Closes all running interfaces.
+    pub fn stop_all_interfaces(&mut self) {
+        for (_, server) in self.interfaces.drain() {
+            server.close();
+        }
+    }
+
+    pub fn interface(&self, id: &str) -> Option<&InterfaceServer> {
+        self.interfaces.get(id)
+    }
+
+    /// Restarts those of the given interfaces that are running,
+    /// so they serve the instances their configuration lists now.
+    pub fn restart_interfaces(&mut self, ids: &[String]) -> HolochainResult<()> {
+        let closing = self.take_interfaces(ids);
+        let ids: Vec<String> = closing.iter().map(|(id, _)| id.clone()).collect();
+        for (_, server) in closing {
+            server.close();
+        }
+        self.start_interfaces(&ids)
+    }
+
+    /// Restarts the given interfaces on another thread once the admin call that changed them
+    /// released the container, since an interface can't close itself while serving a call.
+    pub(crate) fn restart_interfaces_later(&self, ids: Vec<String>) {
+        let handle = match self.handle {
+            Some(ref handle) => handle.clone(),
+            None => return,
+        };
+        let logger = self.logger.clone();
+        thread::spawn(move || {
+            // the container has shut down
+            let container = match handle.upgrade() {
+                Some(container) => container,
+                None => return,
+            };
+            let closing = match container.lock() {
+                Ok(mut container) => container.take_interfaces(&ids),
+                Err(_) => return,
+            };
+            // close the servers without holding the container, calls they wait for might need it
+            let ids: Vec<String> = closing.iter().map(|(id, _)| id.clone()).collect();
+            for (_, server) in closing {
+                server.close();
+            }
+            let result = match container.lock() {
+                Ok(mut container) => container.start_interfaces(&ids),
+                Err(_) => return,
+            };
+            if let Err(err) = result {
+                if let Ok(mut logger) = logger.lock() {
+                    logger.log(format!(
+                        "err/container: could not restart interfaces: {}",
+                        err
+                    ));
+                }
+            }
+        });
+    }
+
+    /// Removes the running ones of the given interfaces, for them to be closed.
+    fn take_interfaces(&mut self, ids: &[String]) -> Vec<(String, InterfaceServer)> {
+        ids.iter()
+            .filter_map(|id| {
+                self.interfaces
+                    .remove(id)
+                    .map(|server| (id.clone(), server))
+            })
+            .collect()
+    }
+
+    /// Starts the given interfaces after they got closed.
+    fn start_interfaces(&mut self, ids: &[String]) -> HolochainResult<()> {
+        for id in ids {
+            let interface_config = self.config.interface_by_id(id).cloned().ok_or_else(|| {
+                HolochainError::ConfigError(format!("Interface '{}' is not configured", id))
+            })?;
+            // closed servers free their address on their own threads, so give them a moment
+            let deadline = Instant::now() + INTERFACE_RESTART_TIMEOUT;
+            let server = loop {
+                match start_interface(&interface_config, &self.instances, self.handle.clone()) {
+                    Ok(server) => break server,
+                    Err(_) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                    Err(err) => return Err(err),
+                }
+            };
+            self.interfaces.insert(id.clone(), server);
+        }
+        Ok(())
+    }
+
+    /// Closes all interfaces, then stops and drops all instances.
+    /// Their storages and state stay where they are, so load_config() can bring them back.
+    pub fn shutdown(&mut self) -> HolochainResult<()> {
+        self.stop_all_interfaces();
+        self.stop_all_instances()?;
+        self.instances.clear();
+        Ok(())
+    }
+
+    pub fn instances(&self) -> &HashMap<String, InstanceHandle> {
+        &self.instances
+    }
+
+    pub fn instance(&self, id: &str) -> Option<InstanceHandle> {
+        self.instances.get(id).cloned()
+    }
+
+    /// Adds the DNA in the given file to the configuration, so instances can run it.
+    /// Fails if an expected hash is given and the DNA has a different one.
+    /// Returns the hash of the DNA.
+    pub fn install_dna_from_file(
+        &mut self,
+        id: &str,
+        file: &str,
+        expected_hash: Option<String>,
+    ) -> HolochainResult<String> {
+        let hash = dna_hash(&load_dna(file, &expected_hash)?);
+        self.change_config(|config| {
+            config.dnas.push(DnaConfiguration {
+                id: id.to_string(),
+                file: file.to_string(),
+                hash: Some(hash.clone()),
+            })
+        })?;
+        Ok(hash)
+    }
+
+    /// Removes a DNA from the configuration. Fails while instances run it.
+    pub fn uninstall_dna(&mut self, id: &str) -> HolochainResult<()> {
+        if self.config.dna_by_id(id).is_none() {
+            return Err(
+                HolochainError::ConfigError(format!("DNA '{}' is not configured", id)).into(),
+            );
+        }
+        let instances = self.config.instances_of_dna(id);
+        if !instances.is_empty() {
+            return Err(HolochainError::ConfigError(format!(
+                "DNA '{}' is still used by the instances {:?}",
+                id, instances
+            )).into());
+        }
+        self.change_config(|config| config.dnas.retain(|dna| dna.id != id))
+    }
+
+    pub fn add_agent(&mut self, id: &str, name: &str) -> HolochainResult<()> {
+        self.change_config(|config| {
+            config.agents.push(AgentConfiguration {
+                id: id.to_string(),
+                name: name.to_string(),
+            })
+        })
+    }
+
+    /// Adds an instance to the configuration and instantiates it, without starting it.
+    /// The given interfaces expose it, running ones get restarted to serve it right away.
+    pub fn create_instance(
+        &mut self,
+        instance_config: InstanceConfiguration,
+        interface_ids: &[String],
+    ) -> HolochainResult<()> {
+        self.add_instance(instance_config, interface_ids)?;
+        self.restart_interfaces(interface_ids)
+    }
+
+    /// create_instance() without restarting the interfaces.
+    pub(crate) fn add_instance(
+        &mut self,
+        instance_config: InstanceConfiguration,
+        interface_ids: &[String],
+    ) -> HolochainResult<()> {
+        for id in interface_ids {
+            if self.config.interface_by_id(id).is_none() {
+                return Err(HolochainError::ConfigError(format!(
+                    "Interface '{}' is not configured",
+                    id
+                )).into());
+            }
+        }
+        let mut config = self.config.clone();
+        config.instances.push(instance_config.clone());
+        config.check_consistency()?;
+        let holochain = instantiate_from_config(&instance_config, &config, &self.logger)?;
+        self.change_config(|config| {
+            config.instances.push(instance_config.clone());
+            for interface in config.interfaces.iter_mut() {
+                if interface_ids.contains(&interface.id) {
+                    interface.instances.push(InstanceReferenceConfiguration {
+                        id: instance_config.id.clone(),
+                    });
+                }
+            }
+        })?;
+        self.instances
+            .insert(instance_config.id, Arc::new(Mutex::new(holochain)));
+        Ok(())
+    }
+
+    /// Stops an instance and removes it from the configuration and the interfaces' instance
+    /// lists. Its storage stays where it is.
+    /// Running interfaces that exposed it get restarted, so they stop serving it.
+    pub fn remove_instance(&mut self, id: &str) -> HolochainResult<()> {
+        let interface_ids = self.drop_instance(id)?;
+        self.restart_interfaces(&interface_ids)
+    }
+
+    /// remove_instance() without restarting the interfaces.
+    /// Returns the ids of the interfaces that exposed the instance.
+    pub(crate) fn drop_instance(&mut self, id: &str) -> HolochainResult<Vec<String>> {
+        if self.config.instance_by_id(id).is_none() {
+            return Err(HolochainError::ConfigError(format!(
+                "Instance '{}' is not configured",
+                id
+            )).into());
+        }
+        if let Some(holochain) = self.instances.remove(id) {
+            let mut holochain = lock_instance(&holochain)?;
+            if holochain.active() {
+                holochain.stop()?;
+            }
+        }
+        let interface_ids: Vec<String> = self
+            .config
+            .interfaces
+            .iter()
+            .filter(|interface| interface.instances.iter().any(|instance| instance.id == id))
+            .map(|interface| interface.id.clone())
+            .collect();
+        self.change_config(|config| {
+            config.instances.retain(|instance| instance.id != id);
+            for interface in config.interfaces.iter_mut() {
+                interface.instances.retain(|instance| instance.id != id);
+            }
+        })?;
+        Ok(interface_ids)
+    }
+
+    pub fn start_instance(&mut self, id: &str) -> HolochainResult<()> {
+        let holochain = self.instance_or_error(id)?;
+        let mut holochain = lock_instance(&holochain)?;
+        holochain.start()
+    }
+
+    pub fn stop_instance(&mut self, id: &str) -> HolochainResult<()> {
+        let holochain = self.instance_or_error(id)?;
+        let mut holochain = lock_instance(&holochain)?;
+        holochain.stop()
+    }
+
+    /// Reports the instances of this container, ordered by their ids.
+    pub fn list_instances(&self) -> HolochainResult<Vec<InstanceInfo>> {
+        let mut infos = Vec::new();
+        for instance_config in self.config.instances.iter() {
+            if let Some(holochain) = self.instances.get(&instance_config.id) {
+                let mut holochain = lock_instance(holochain)?;
+                infos.push(InstanceInfo {
+                    id: instance_config.id.clone(),
+                    dna: instance_config.dna.clone(),
+                    agent: instance_config.agent.clone(),
+                    active: holochain.active(),
+                    status: holochain.state()?.nucleus().status(),
+                });
+            }
+        }
+        infos.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(infos)
+    }
+
+    fn instance_or_error(&self, id: &str) -> HolochainResult<InstanceHandle> {
+        self.instance(id).ok_or_else(|| {
+            HolochainError::ConfigError(format!("Instance '{}' does not exist", id)).into()
+        })
+    }
+
+    /// Applies a change to the configuration if the result is consistent,
+    /// and saves it if the container was created from a file.
+    fn change_config<F>(&mut self, change: F) -> HolochainResult<()>
+    where
+        F: FnOnce(&mut Configuration),
+    {
+        let mut config = self.config.clone();
+        change(&mut config);
+        config.check_consistency()?;
+        if let Some(ref path) = self.config_path {
+            save_configuration(&config, path)?;
+        }
+        self.config = config;
+        Ok(())
+    }
+}
+
+impl Drop for Container {
+    fn drop(&mut self) {
+        // there is nobody left to report errors to
+        self.stop_all_interfaces();
+        let _ = self.stop_all_instances();
+    }
+}
+
+pub(crate) fn lock_instance(
+    holochain: &InstanceHandle,
+) -> Result<MutexGuard<Holochain>, HolochainError> {
+    holochain
+        .lock()
+        .map_err(|_| HolochainError::new("instance lock is poisoned"))
+}
+
+/// Creates the instance described by the given instance configuration.
+/// Instances with file storage keep their source chain, DHT and state in the
+/// `cas`, `eav` and `state` directories below the configured path,
+/// and get loaded from there if a state has been persisted before.
+pub fn instantiate_from_config(
+    instance_config: &InstanceConfiguration,
+    config: &Configuration,
+    logger: &Arc<Mutex<Logger>>,
+) -> HolochainResult<Holochain> {
+    let agent_config = config.agent_by_id(&instance_config.agent).ok_or_else(|| {
+        HolochainError::ConfigError(format!(
+            "Agent '{}' of instance '{}' is not configured",
+            instance_config.agent, instance_config.id
+        ))
+    })?;
+    let dna_config = config.dna_by_id(&instance_config.dna).ok_or_else(|| {
+        HolochainError::ConfigError(format!(
+            "DNA '{}' of instance '{}' is not configured",
+            instance_config.dna, instance_config.id
+        ))
+    })?;
+    let agent = Agent::from(agent_config.name.clone());
+
+    match instance_config.storage {
+        StorageConfiguration::Memory => {
+            let mut context = Context::new(
+                agent,
+                logger.clone(),
+                Arc::new(Mutex::new(SimplePersister::new())),
+                MemoryStorage::new()?,
+                EavMemoryStorage::new()?,
+            )?;
+            context.execution_limits = instance_config.limits.clone();
+            Holochain::new(
+                load_dna(&dna_config.file, &dna_config.hash)?,
+                Arc::new(context),
+            )
+        }
+        StorageConfiguration::File { ref path } => {
+            let cas_path = Path::new(path).join("cas");
+            let eav_path = Path::new(path).join("eav");
+            let state_path = Path::new(path).join("state");
+            for dir in vec![&cas_path, &eav_path, &state_path] {
+                fs::create_dir_all(dir).map_err(HolochainError::from)?;
+            }
+            let persister = FilePersister::new(&state_path);
+            let has_state = persister.file_path().exists();
+            let mut context = Context::new(
+                agent,
+                logger.clone(),
+                Arc::new(Mutex::new(persister)),
+                FilesystemStorage::new(&path_to_string(&cas_path)?)?,
+                EavFileStorage::new(path_to_string(&eav_path)?)?,
+            )?;
+            context.execution_limits = instance_config.limits.clone();
+            if has_state {
+                Ok(Holochain::load(
+                    path_to_string(&state_path)?,
+                    Arc::new(context),
+                )?)
+            } else {
+                Holochain::new(
+                    load_dna(&dna_config.file, &dna_config.hash)?,
+                    Arc::new(context),
+                )
+            }
+        }
+    }
+}
+
+/// The hash a DNA gets identified and checked by.
+pub fn dna_hash(dna: &Dna) -> String {
+    dna.content_hash().to_string()
+}
+
+/// Loads a DNA file or `.hcpkg` package, checking its hash if one is expected.
+fn load_dna(file: &str, expected_hash: &Option<String>) -> Result<Dna, HolochainError> {
+    let dna = if file.ends_with(&format!(".{}", PACKAGE_EXTENSION)) {
+        Dna::from_package_file(file).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not load DNA package '{}': {}", file, e))
+        })?
+    } else {
+        let contents = fs::read_to_string(file).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not read DNA file '{}': {}", file, e))
+        })?;
+        Dna::from_json_str(&contents).map_err(|e| {
+            HolochainError::ConfigError(format!("Could not parse DNA file '{}': {}", file, e))
+        })?
+    };
+    if let Some(ref expected_hash) = expected_hash {
+        let hash = dna_hash(&dna);
+        if hash != *expected_hash {
+            return Err(HolochainError::ConfigError(format!(
+                "DNA file '{}' has the hash {} instead of the expected {}",
+                file, hash, expected_hash
+            )));
+        }
+    }
+    Ok(dna)
+}
+
+fn path_to_string(path: &Path) -> Result<String, HolochainError> {
+    path.to_str()
+        .map(String::from)
+        .ok_or_else(|| HolochainError::ConfigError(format!("Invalid path {:?}", path)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+    extern crate test_utils;
+
+    use self::tempfile::tempdir;
+    use super::*;
+    use config::{
+        AgentConfiguration, DnaConfiguration, InstanceConfiguration, StorageConfiguration,
+    };
+    use holochain_core::nucleus::ribosome::metering::ExecutionLimits;
+    use holochain_core_types::chain_header::ChainHeader;
+    use holochain_dna::package::DnaPackage;
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    /// writes the test DNA to the given directory and returns a configuration with an
+    /// instance of it for each of the given storages
+    pub fn test_config(dir: &Path, storages: Vec<StorageConfiguration>) -> Configuration {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let dna_file: PathBuf = dir.join("app.dna.json");
+        fs::write(&dna_file, dna.to_json()).unwrap();
+
+        Configuration {
+            agents: vec![AgentConfiguration {
+                id: "bob".to_string(),
+                name: "Bob".to_string(),
+            }],
+            dnas: vec![DnaConfiguration {
+                id: "app".to_string(),
+                file: dna_file.to_str().unwrap().to_string(),
+                hash: None,
+            }],
+            instances: storages
+                .into_iter()
+                .enumerate()
+                .map(|(index, storage)| InstanceConfiguration {
+                    id: format!("app_bob_{}", index),
+                    dna: "app".to_string(),
+                    agent: "bob".to_string(),
+                    storage,
+                    limits: ExecutionLimits::default(),
+                }).collect(),
+            interfaces: Vec::new(),
+        }
+    }
+
+    fn call_main(container: &Container, id: &str) -> HolochainResult<String> {
+        container
+            .instance(id)
+            .expect("instance should exist")
+            .lock()
+            .unwrap()
+            .call("test_zome", "test_cap", "main", "")
+    }
+
+    fn top_chain_header(container: &Container) -> Option<ChainHeader> {
+        let holochain = container
+            .instance("app_bob_0")
+            .expect("instance should exist");
+        let mut holochain = holochain.lock().unwrap();
+        holochain.state().unwrap().agent().top_chain_header()
+    }
+
+    #[test]
+    fn runs_configured_instances() {
+        let dir = tempdir().unwrap();
+        let storage_path = dir.path().join("storage").to_str().unwrap().to_string();
+        let config = test_config(
+            dir.path(),
+            vec![
+                StorageConfiguration::Memory,
+                StorageConfiguration::File { path: storage_path },
+            ],
+        );
+        let mut container = Container::from_config(config);
+
+        container
+            .load_config()
+            .expect("instances should get created");
+        assert_eq!(container.instances().len(), 2);
+        container
+            .start_all_instances()
+            .expect("instances should start");
+
+        assert_eq!(call_main(&container, "app_bob_0"), Ok("1337".to_string()));
+        assert_eq!(call_main(&container, "app_bob_1"), Ok("1337".to_string()));
+
+        container.shutdown().expect("instances should stop");
+        assert!(container.instances().is_empty());
+    }
+
+    #[test]
+    fn loads_persisted_instances() {
+        let dir = tempdir().unwrap();
+        let storage_path = dir.path().join("storage").to_str().unwrap().to_string();
+        let config = test_config(
+            dir.path(),
+            vec![StorageConfiguration::File { path: storage_path }],
+        );
+
+        let mut container = Container::from_config(config.clone());
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+        let top_header = top_chain_header(&container);
+        container.shutdown().unwrap();
+
+        // genesis must not run again, so the source chain stays the same
+        let mut container = Container::from_config(config);
+        container.load_config().unwrap();
+        assert_eq!(top_chain_header(&container), top_header);
+    }
+
+    #[test]
+    fn runs_instances_of_dna_packages() {
+        let dir = tempdir().unwrap();
+        let mut config = test_config(dir.path(), vec![StorageConfiguration::Memory]);
+        let dna = load_dna(&config.dnas[0].file, &None).unwrap();
+        let package_file = dir.path().join("app.hcpkg");
+        DnaPackage::from_dna(&dna, BTreeMap::new())
+            .save(&package_file)
+            .unwrap();
+        config.dnas[0].file = package_file.to_str().unwrap().to_string();
+
+        let mut container = Container::from_config(config);
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+        assert_eq!(call_main(&container, "app_bob_0"), Ok("1337".to_string()));
+    }
+
+    #[test]
+    fn fails_on_missing_dna_file() {
+        let dir = tempdir().unwrap();
+        let mut config = test_config(dir.path(), vec![StorageConfiguration::Memory]);
+        config.dnas[0].file = "does/not/exist.dna.json".to_string();
+
+        let result = Container::from_config(config).load_config();
+        match result {
+            Err(err) => assert!(err.to_string().contains("Could not read DNA file")),
+            Ok(_) => panic!("loading should fail"),
+        }
+    }
+}
@@ -0,0 +1,537 @@
+//! Interfaces let clients outside of the container, like web UIs, call the zome functions of
+//! its instances through JSON-RPC 2.0, over HTTP, a local socket or a WebSocket.
+//! Methods are named `<instance>/<zome>/<cap>/<fn>`, their params get handed to the zome
+//! function as its json encoded parameters and its result is returned as json if it is json.
+
+use admin::add_admin_functions;
+use config::{InterfaceConfiguration, InterfaceDriver};
+use container::{lock_instance, Container, InstanceHandle};
+use futures::{
+    self,
+    executor::{Executor, ThreadPool, ThreadPoolBuilder},
+    never::Never,
+    task,
+};
+use holochain_core::nucleus::actions::call_zome_function::ZomeCallFuture;
+use holochain_core_api::error::{HolochainInstanceError, HolochainResult};
+use holochain_core_types::error::{DnaError, HolochainError};
+use jsonrpc_core::{
+    futures::{future, sync::oneshot, Future},
+    Error as RpcError, ErrorCode, IoHandler, MetaIoHandler, Metadata, Params, Value,
+};
+use jsonrpc_http_server;
+use jsonrpc_ipc_server;
+use jsonrpc_ws_server;
+use serde_json;
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::SocketAddr,
+    sync::{Mutex, Weak},
+};
+use websocket::{start_websocket_server, websocket_handler};
+
+// JSON-RPC error codes of Holochain errors,
+// taken from the range the spec reserves for implementation-defined server errors
+pub const INSTANCE_NOT_ACTIVE: i64 = -32000;
+pub const INSTANCE_ALREADY_ACTIVE: i64 = -32001;
+pub const TIMEOUT: i64 = -32002;
+pub const CAPABILITY_TOKEN_MISSING: i64 = -32003;
+pub const VALIDATION_FAILED: i64 = -32004;
+pub const ZOME_FUNCTION_FAILED: i64 = -32005;
+pub const EXECUTION_LIMIT_EXCEEDED: i64 = -32006;
+
+/// Threads of an interface that wait for its zome calls to return.
+/// Waiting doesn't block them, so a few of them serve any number of concurrent calls.
+const ZOME_CALL_THREADS: usize = 2;
+
+pub fn rpc_method_name(instance_id: &str, zome: &str, cap: &str, function: &str) -> String {
+    format!("{}/{}/{}/{}", instance_id, zome, cap, function)
+}
+
+/// Maps errors of zome and admin calls to JSON-RPC error objects.
+/// Calls to zomes, capabilities or functions that don't exist are unknown methods,
+/// admin calls the configuration can't take have invalid params,
+/// errors Holochain reports get their own server error codes.
+pub fn rpc_error(error: HolochainInstanceError) -> RpcError {
+    let code = match error {
+        HolochainInstanceError::InstanceNotActiveYet => ErrorCode::ServerError(INSTANCE_NOT_ACTIVE),
+        HolochainInstanceError::InstanceAlreadyActive => {
+            ErrorCode::ServerError(INSTANCE_ALREADY_ACTIVE)
+        }
+        HolochainInstanceError::Timeout(_) => ErrorCode::ServerError(TIMEOUT),
+        HolochainInstanceError::InvalidDna(_) => ErrorCode::InvalidParams,
+        HolochainInstanceError::InternalFailure(ref err) => match err {
+            HolochainError::Dna(DnaError::ZomeNotFound(_))
+            | HolochainError::Dna(DnaError::CapabilityNotFound(_))
+            | HolochainError::Dna(DnaError::ZomeFunctionNotFound(_)) => ErrorCode::MethodNotFound,
+            HolochainError::DoesNotHaveCapabilityToken => {
+                ErrorCode::ServerError(CAPABILITY_TOKEN_MISSING)
+            }
+            HolochainError::ValidationFailed(_) => ErrorCode::ServerError(VALIDATION_FAILED),
+            HolochainError::ErrorGeneric(_)
+            | HolochainError::RibosomeFailed(_)
+            | HolochainError::ZomeAborted(_) => ErrorCode::ServerError(ZOME_FUNCTION_FAILED),
+            HolochainError::ExecutionLimitExceeded(_) => {
+                ErrorCode::ServerError(EXECUTION_LIMIT_EXCEEDED)
+            }
+            HolochainError::ConfigError(_) => ErrorCode::InvalidParams,
+            _ => ErrorCode::InternalError,
+        },
+    };
+    // clients get to know everything that is wrong with a DNA, and where a zome aborted
+    let data = match error {
+        HolochainInstanceError::InvalidDna(ref diagnostics) => Some(Value::Array(
+            diagnostics
+                .iter()
+                .map(|diagnostic| Value::String(diagnostic.to_string()))
+                .collect(),
+        )),
+        HolochainInstanceError::InternalFailure(HolochainError::ZomeAborted(ref report)) => {
+            serde_json::to_value(report).ok()
+        }
+        _ => None,
+    };
+    RpcError {
+        code,
+        message: error.description().to_string(),
+        data,
+    }
+}
+
+pub type RpcFuture = Box<Future<Item = Value, Error = RpcError> + Send>;
+
+/// Responds to a JSON-RPC request with the result of its zome call once that resolved.
+struct ZomeCallResponder {
+    zome_call: ZomeCallFuture,
+    sender: Option<oneshot::Sender<Result<Value, RpcError>>>,
+}
+
+impl futures::Future for ZomeCallResponder {
+    type Item = ();
+    type Error = Never;
+
+    fn poll(&mut self, cx: &mut task::Context) -> Result<futures::Async<()>, Never> {
+        // both versions of Future are around here, this is the one of the zome call
+        let result = match futures::Future::poll(&mut self.zome_call, cx) {
+            Ok(futures::Async::Pending) => return Ok(futures::Async::Pending),
+            Ok(futures::Async::Ready(result)) => Ok(
+                serde_json::from_str::<Value>(&result).unwrap_or_else(|_| Value::String(result))
+            ),
+            Err(err) => Err(rpc_error(err.into())),
+        };
+        if let Some(sender) = self.sender.take() {
+            // the client is gone if nobody receives the result
+            let _ = sender.send(result);
+        }
+        Ok(futures::Async::Ready(()))
+    }
+}
+
+/// Calls a zome function with the given JSON-RPC params.
+/// The result gets awaited on the given pool, so the servers' event loops can take
+/// further calls in the meantime without a thread per call.
+fn call_zome_function(
+    instance: &InstanceHandle,
+    pool: &ThreadPool,
+    zome: &str,
+    cap: &str,
+    function: &str,
+    params: Params,
+) -> RpcFuture {
+    let parameters = match params {
+        Params::None => String::from("{}"),
+        Params::Map(map) => Value::Object(map).to_string(),
+        Params::Array(array) => Value::Array(array).to_string(),
+    };
+    // only hold the lock while dispatching the call, so calls to one instance can run concurrently
+    let dispatched = match instance.lock() {
+        Ok(mut holochain) => holochain
+            .call_async(zome, cap, function, &parameters)
+            .map_err(rpc_error),
+        Err(_) => Err(RpcError::internal_error()),
+    };
+    let zome_call = match dispatched {
+        Ok(zome_call) => zome_call,
+        Err(error) => return Box::new(future::err(error)),
+    };
+
+    let (sender, receiver) = oneshot::channel();
+    let responder = ZomeCallResponder {
+        zome_call,
+        sender: Some(sender),
+    };
+    if pool.clone().spawn(Box::new(responder)).is_err() {
+        return Box::new(future::err(RpcError::internal_error()));
+    }
+    Box::new(
+        receiver
+            .map_err(|_| RpcError::internal_error())
+            .and_then(|result| result),
+    )
+}
+
+/// Builds a JSON-RPC handler with a method for every function that the capabilities of the
+/// given instances' DNAs declare.
+pub fn rpc_handler(instances: &HashMap<String, InstanceHandle>) -> HolochainResult<IoHandler> {
+    let mut io = IoHandler::new();
+    add_zome_functions(&mut *io, instances)?;
+    Ok(io)
+}
+
+/// Adds the methods of rpc_handler() to a handler with metadata.
+pub fn add_zome_functions<M: Metadata>(
+    io: &mut MetaIoHandler<M>,
+    instances: &HashMap<String, InstanceHandle>,
+) -> HolochainResult<()> {
+    // its threads stop once the handler holding the methods gets dropped
+    let pool = ThreadPoolBuilder::new()
+        .pool_size(ZOME_CALL_THREADS)
+        .name_prefix("zome-call-")
+        .create();
+    for (instance_id, instance) in instances.iter() {
+        let dna = lock_instance(instance)?
+            .state()?
+            .nucleus()
+            .dna()
+            .ok_or(HolochainError::DnaMissing)?;
+        for (zome_name, zome) in dna.zomes.iter() {
+            for (cap_name, capability) in zome.capabilities.iter() {
+                for function in capability.functions.iter() {
+                    let instance = instance.clone();
+                    let pool = pool.clone();
+                    let zome_name = zome_name.clone();
+                    let cap_name = cap_name.clone();
+                    let fn_name = function.name.clone();
+                    io.add_method(
+                        &rpc_method_name(instance_id, &zome_name, &cap_name, &fn_name),
+                        move |params: Params| {
+                            call_zome_function(
+                                &instance, &pool, &zome_name, &cap_name, &fn_name, params,
+                            )
+                        },
+                    );
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A running interface. It serves requests until it gets closed.
+pub enum InterfaceServer {
+    Http(jsonrpc_http_server::Server),
+    Socket(jsonrpc_ipc_server::Server),
+    Websocket(jsonrpc_ws_server::Server),
+}
+
+impl InterfaceServer {
+    /// the address an HTTP or WebSocket interface listens on
+    pub fn address(&self) -> Option<SocketAddr> {
+        match self {
+            InterfaceServer::Http(server) => Some(*server.address()),
+            InterfaceServer::Socket(_) => None,
+            InterfaceServer::Websocket(server) => Some(*server.addr()),
+        }
+    }
+
+    pub fn close(self) {
+        match self {
+            InterfaceServer::Http(server) => server.close(),
+            InterfaceServer::Socket(server) => server.close(),
+            InterfaceServer::Websocket(server) => server.close(),
+        }
+    }
+}
+
+/// Starts serving the instances the interface configuration lists, out of the given ones.
+/// Admin interfaces also serve the admin API of the given container.
+/// HTTP and WebSocket interfaces only listen on localhost.
+pub fn start_interface(
+    config: &InterfaceConfiguration,
+    instances: &HashMap<String, InstanceHandle>,
+    container: Option<Weak<Mutex<Container>>>,
+) -> HolochainResult<InterfaceServer> {
+    let admin = match (config.admin, container) {
+        (false, _) => None,
+        (true, Some(container)) => Some(container),
+        (true, None) => {
+            return Err(HolochainError::ConfigError(format!(
+                "Admin interface '{}' needs a container shared through Container::into_handle()",
+                config.id
+            )).into())
+        }
+    };
+    let mut exposed = HashMap::new();
+    for reference in config.instances.iter() {
+        let instance = instances.get(&reference.id).ok_or_else(|| {
+            HolochainError::ConfigError(format!(
+                "Instance '{}' of interface '{}' is not running",
+                reference.id, config.id
+            ))
+        })?;
+        exposed.insert(reference.id.clone(), instance.clone());
+    }
+
+    match config.driver {
+        InterfaceDriver::Http { port } => {
+            let mut io = rpc_handler(&exposed)?;
+            if let Some(ref container) = admin {
+                add_admin_functions(&mut *io, container);
+            }
+            jsonrpc_http_server::ServerBuilder::new(io)
+                .start_http(&localhost(port))
+                .map(InterfaceServer::Http)
+                .map_err(|err| HolochainError::from(err).into())
+        }
+        InterfaceDriver::Socket { ref file } => {
+            let mut io = rpc_handler(&exposed)?;
+            if let Some(ref container) = admin {
+                add_admin_functions(&mut *io, container);
+            }
+            jsonrpc_ipc_server::ServerBuilder::new(io)
+                .start(file)
+                .map(InterfaceServer::Socket)
+                .map_err(|err| HolochainError::from(err).into())
+        }
+        InterfaceDriver::Websocket { port } => {
+            let mut io = websocket_handler(&exposed)?;
+            if let Some(ref container) = admin {
+                add_admin_functions(&mut *io, container);
+            }
+            start_websocket_server(io, &localhost(port)).map(InterfaceServer::Websocket)
+        }
+    }
+}
+
+fn localhost(port: u16) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], port))
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+
+    use self::tempfile::tempdir;
+    use super::*;
+    use config::{InstanceReferenceConfiguration, StorageConfiguration};
+    use container::{tests::test_config, Container};
+    use holochain_core_types::error::RibosomeErrorReport;
+    use holochain_dna::diagnostics::DnaDiagnostic;
+    use std::{
+        io::{Read, Write},
+        net::TcpStream,
+    };
+
+    /// a running container with the test DNA in the in-memory instance "app_bob_0"
+    pub fn test_container() -> Container {
+        let dir = tempdir().unwrap();
+        let mut container =
+            Container::from_config(test_config(dir.path(), vec![StorageConfiguration::Memory]));
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+        container
+    }
+
+    pub fn test_interface_config(driver: InterfaceDriver) -> InterfaceConfiguration {
+        InterfaceConfiguration {
+            id: "test_interface".to_string(),
+            driver,
+            admin: false,
+            instances: vec![InstanceReferenceConfiguration {
+                id: "app_bob_0".to_string(),
+            }],
+        }
+    }
+
+    fn request(method: &str) -> String {
+        json!({"jsonrpc": "2.0", "method": method, "params": {}, "id": 1}).to_string()
+    }
+
+    fn handle(io: &IoHandler, method: &str) -> Value {
+        let response = io
+            .handle_request_sync(&request(method))
+            .expect("a call should get a response");
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn calls_zome_functions_by_method_name() {
+        let container = test_container();
+        let io = rpc_handler(container.instances()).unwrap();
+
+        let response = handle(&io, "app_bob_0/test_zome/test_cap/main");
+        assert_eq!(response["result"], json!(1337));
+
+        let response = handle(&io, "app_bob_0/test_zome/test_cap/other");
+        assert_eq!(response["error"]["code"], json!(-32601));
+    }
+
+    #[test]
+    /// the calls get awaited without blocking a thread of the pool each
+    fn awaits_more_calls_than_threads() {
+        let container = test_container();
+        let io = rpc_handler(container.instances()).unwrap();
+
+        let calls: Vec<_> = (0..ZOME_CALL_THREADS * 4)
+            .map(|_| io.handle_request(&request("app_bob_0/test_zome/test_cap/main")))
+            .collect();
+        for call in calls {
+            let response = call.wait().unwrap().expect("a call should get a response");
+            let response: Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["result"], json!(1337));
+        }
+    }
+
+    #[test]
+    fn maps_errors_to_rpc_errors() {
+        let error = rpc_error(HolochainInstanceError::InstanceNotActiveYet);
+        assert_eq!(error.code, ErrorCode::ServerError(INSTANCE_NOT_ACTIVE));
+        assert_eq!(error.message, "Holochain instance is not active yet.");
+
+        let error = rpc_error(HolochainError::Timeout("too slow".to_string()).into());
+        assert_eq!(error.code, ErrorCode::ServerError(TIMEOUT));
+        assert_eq!(error.message, "too slow");
+
+        let error = rpc_error(
+            HolochainError::Dna(DnaError::ZomeFunctionNotFound("no fn".to_string())).into(),
+        );
+        assert_eq!(error.code, ErrorCode::MethodNotFound);
+
+        let error = rpc_error(HolochainError::ValidationFailed("invalid".to_string()).into());
+        assert_eq!(error.code, ErrorCode::ServerError(VALIDATION_FAILED));
+
+        let error = rpc_error(
+            HolochainError::ExecutionLimitExceeded("too many instructions".to_string()).into(),
+        );
+        assert_eq!(error.code, ErrorCode::ServerError(EXECUTION_LIMIT_EXCEEDED));
+
+        let error = rpc_error(
+            HolochainError::ZomeAborted(RibosomeErrorReport {
+                description: "oops".to_string(),
+                file_name: "a.ts".to_string(),
+                line: "12".to_string(),
+                column: "3".to_string(),
+            }).into(),
+        );
+        assert_eq!(error.code, ErrorCode::ServerError(ZOME_FUNCTION_FAILED));
+        assert_eq!(error.message, "oops");
+        assert_eq!(
+            error.data,
+            Some(json!({"description": "oops", "file_name": "a.ts", "line": "12", "column": "3"}))
+        );
+
+        let error = rpc_error(HolochainError::LoggingError.into());
+        assert_eq!(error.code, ErrorCode::InternalError);
+
+        let error = rpc_error(HolochainInstanceError::InvalidDna(vec![
+            DnaDiagnostic::EmptyWasm {
+                zome: "blog".to_string(),
+            },
+        ]));
+        assert_eq!(error.code, ErrorCode::InvalidParams);
+        assert_eq!(error.data, Some(json!(["Zome 'blog' has no wasm"])));
+    }
+
+    #[test]
+    fn serves_http() {
+        let container = test_container();
+        let server = start_interface(
+            &test_interface_config(InterfaceDriver::Http { port: 0 }),
+            container.instances(),
+            None,
+        ).unwrap();
+
+        let body = request("app_bob_0/test_zome/test_cap/main");
+        let mut stream = TcpStream::connect(server.address().unwrap()).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        ).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        server.close();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.contains(r#""result":1337"#), "{}", response);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn serves_local_socket() {
+        use std::{
+            io::{BufRead, BufReader},
+            os::unix::net::UnixStream,
+        };
+
+        let container = test_container();
+        let dir = tempdir().unwrap();
+        let file = dir
+            .path()
+            .join("holochain.sock")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let server = start_interface(
+            &test_interface_config(InterfaceDriver::Socket { file: file.clone() }),
+            container.instances(),
+            None,
+        ).unwrap();
+
+        let mut stream = UnixStream::connect(&file).unwrap();
+        writeln!(stream, "{}", request("app_bob_0/test_zome/test_cap/main")).unwrap();
+        let mut response = String::new();
+        BufReader::new(stream).read_line(&mut response).unwrap();
+        server.close();
+
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["result"], json!(1337));
+    }
+
+    #[test]
+    fn container_serves_configured_interfaces() {
+        let dir = tempdir().unwrap();
+        let mut config = test_config(dir.path(), vec![StorageConfiguration::Memory]);
+        config
+            .interfaces
+            .push(test_interface_config(InterfaceDriver::Http { port: 0 }));
+        let mut container = Container::from_config(config);
+        container.load_config().unwrap();
+        container.start_all_instances().unwrap();
+
+        container.start_all_interfaces().unwrap();
+        assert!(container
+            .interface("test_interface")
+            .and_then(|server| server.address())
+            .is_some());
+        container.stop_all_interfaces();
+        assert!(container.interface("test_interface").is_none());
+    }
+
+    #[test]
+    fn admin_interfaces_need_a_shared_container() {
+        let container = test_container();
+        let mut config = test_interface_config(InterfaceDriver::Http { port: 0 });
+        config.admin = true;
+
+        match start_interface(&config, container.instances(), None) {
+            Err(err) => assert!(err.to_string().contains("Container::into_handle()")),
+            Ok(_) => panic!("the interface should not start"),
+        }
+    }
+
+    #[test]
+    fn rejects_instances_that_are_not_running() {
+        let container = test_container();
+        let mut config = test_interface_config(InterfaceDriver::Http { port: 0 });
+        config.instances[0].id = "app_alice".to_string();
+
+        match start_interface(&config, container.instances(), None) {
+            Err(err) => assert!(err.to_string().contains("'app_alice'")),
+            Ok(_) => panic!("the interface should not start"),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! `holochain_container` runs many Holochain instances side by side, as described by a
+//! declarative configuration file listing agents, DNAs, instances and interfaces.
+//! Embedders load a configuration and let the container instantiate, start and stop the
+//! instances through `holochain_core_api` instead of assembling contexts by hand.
+//!
+//! # Examples
+//!
+//! ``` rust,no_run
+//! extern crate holochain_container;
+//! use holochain_container::{config::load_configuration, container::Container};
+//!
+//! let config = load_configuration("container.toml").expect("could not load the config");
+//! let mut container = Container::from_config(config);
+//! container.load_config().expect("could not create the instances");
+//! container.start_all_instances().expect("could not start the instances");
+//! container.start_all_interfaces().expect("could not start the interfaces");
+//!
+//! // clients call zome functions through the interfaces, e.g. via JSON-RPC over HTTP:
+//! // {"jsonrpc": "2.0", "method": "app_bob/zome/cap/fn", "params": {...}, "id": 1}
+//!
+//! container.shutdown().expect("could not stop the instances");
+//! ```
+//! @see doc/architecture/decisions/0015-container-api.md
+
+extern crate futures;
+extern crate holochain_agent;
+extern crate holochain_cas_implementations;
+extern crate holochain_core;
+extern crate holochain_core_api;
+extern crate holochain_core_types;
+extern crate holochain_dna;
+extern crate jsonrpc_core;
+extern crate jsonrpc_http_server;
+extern crate jsonrpc_ipc_server;
+extern crate jsonrpc_pubsub;
+extern crate jsonrpc_ws_server;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+extern crate toml;
+
+pub mod admin;
+pub mod config;
+pub mod container;
+pub mod interface;
+pub mod websocket;
@@ -0,0 +1,47 @@
+extern crate holochain_container;
+
+use holochain_container::container::Container;
+use std::{env, thread};
+
+// this only wires up the library, no need to track code test coverage
+#[cfg_attr(tarpaulin, skip)]
+fn usage() {
+    println!("Usage: holochain_container <config file>");
+    std::process::exit(1);
+}
+
+// this only wires up the library, no need to track code test coverage
+#[cfg_attr(tarpaulin, skip)]
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        usage();
+    }
+
+    // admin interfaces save their changes to the configuration file
+    let container = Container::from_config_file(&args[1])
+        .expect("couldn't load the configuration")
+        .into_handle();
+    {
+        let mut container = container.lock().unwrap();
+        container
+            .load_config()
+            .expect("couldn't create the instances");
+        container
+            .start_all_instances()
+            .expect("couldn't start the instances");
+        println!("Started {} instances..", container.instances().len());
+        container
+            .start_all_interfaces()
+            .expect("couldn't start the interfaces");
+        println!(
+            "Serving {} interfaces..",
+            container.config().interfaces.len()
+        );
+    }
+
+    // interfaces serve from their own threads until the process gets killed
+    loop {
+        thread::park();
+    }
+}
@@ -0,0 +1,411 @@
+//! WebSocket interfaces keep a connection to the client open. Clients can have many zome
+//! calls running at once over it, told apart by their JSON-RPC request ids, and get signals
+//! of the instances pushed to them.
+//!
+//! Clients subscribe to signals by calling `signal_subscribe` with the instance and
+//! optionally a capability:
+//! * `{"instance": "app_bob"}` selects the system signals, i.e. commits, added links and
+//!   status changes of the instance
+//! * `{"instance": "app_bob", "zome": "blog", "cap": "main"}` selects all app signals the
+//!   capability declares, `"name": "Post"` narrows that down to a single one
+//!
+//! The result is the subscription id. Until the client calls `signal_unsubscribe` with it or
+//! disconnects, it gets `signal` notifications with the params
+//! `{"subscription": <id>, "instance": <instance id>, "result": <signal>}`.
+
+use container::{lock_instance, InstanceHandle};
+use holochain_core::signal::{ListenerId, Signal, SignalFilter};
+use holochain_core_api::error::HolochainResult;
+use holochain_core_types::error::HolochainError;
+use interface::{add_zome_functions, rpc_error};
+use jsonrpc_core::{futures::Future, Error as RpcError, MetaIoHandler, Params, Value};
+use jsonrpc_pubsub::{PubSubHandler, Session, Sink, Subscriber, SubscriptionId};
+use jsonrpc_ws_server::{self, RequestContext};
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+};
+
+pub const SIGNAL_NOTIFICATION: &str = "signal";
+pub const SIGNAL_SUBSCRIBE: &str = "signal_subscribe";
+pub const SIGNAL_UNSUBSCRIBE: &str = "signal_unsubscribe";
+
+pub type WebsocketHandler = PubSubHandler<Arc<Session>>;
+
+#[derive(Clone, Debug, Deserialize)]
+struct SubscriptionParams {
+    instance: String,
+    #[serde(default)]
+    zome: Option<String>,
+    #[serde(default)]
+    cap: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+/// The listeners a subscription registered on an instance.
+struct Subscription {
+    instance: InstanceHandle,
+    listeners: Vec<ListenerId>,
+}
+
+#[derive(Clone, Default)]
+struct Subscriptions {
+    subscriptions: Arc<Mutex<HashMap<u64, Subscription>>>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl Subscriptions {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::SeqCst) as u64
+    }
+
+    fn lock(&self) -> MutexGuard<HashMap<u64, Subscription>> {
+        // the map stays consistent even if a thread panicked while holding the lock
+        self.subscriptions
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Builds a handler with the zome functions of the given instances, plus signal subscriptions.
+pub fn websocket_handler(
+    instances: &HashMap<String, InstanceHandle>,
+) -> HolochainResult<WebsocketHandler> {
+    let mut io = PubSubHandler::new(MetaIoHandler::default());
+    add_zome_functions(&mut *io, instances)?;
+
+    let instances = instances.clone();
+    let subscriptions = Subscriptions::default();
+    let unsubscriptions = subscriptions.clone();
+    io.add_subscription(
+        SIGNAL_NOTIFICATION,
+        (
+            SIGNAL_SUBSCRIBE,
+            move |params: Params, _: Arc<Session>, subscriber: Subscriber| {
+                subscribe(&instances, &subscriptions, params, subscriber)
+            },
+        ),
+        (SIGNAL_UNSUBSCRIBE, move |id: SubscriptionId| {
+            unsubscribe(&unsubscriptions, id)
+        }),
+    );
+    Ok(io)
+}
+
+pub fn start_websocket_server(
+    io: WebsocketHandler,
+    address: &SocketAddr,
+) -> HolochainResult<jsonrpc_ws_server::Server> {
+    jsonrpc_ws_server::ServerBuilder::with_meta_extractor(io, |context: &RequestContext| {
+        Arc::new(Session::new(context.sender()))
+    }).start(address)
+    .map_err(|err| {
+        HolochainError::ErrorGeneric(format!("Could not start WebSocket server: {}", err)).into()
+    })
+}
+
+fn subscribe(
+    instances: &HashMap<String, InstanceHandle>,
+    subscriptions: &Subscriptions,
+    params: Params,
+    subscriber: Subscriber,
+) {
+    let (sender, receiver) = channel();
+    let listened = params.parse::<SubscriptionParams>().and_then(|params| {
+        listen(instances, &params, &sender).map(|listeners| (params, listeners))
+    });
+    let (params, listeners) = match listened {
+        Ok(listened) => listened,
+        Err(error) => {
+            // the client is gone if the rejection can't be sent
+            let _ = subscriber.reject(error);
+            return;
+        }
+    };
+
+    // register before answering, so the client can't unsubscribe before the subscription exists
+    let id = subscriptions.next_id();
+    subscriptions.lock().insert(
+        id,
+        Subscription {
+            instance: instances[&params.instance].clone(),
+            listeners,
+        },
+    );
+    match subscriber.assign_id(SubscriptionId::Number(id)) {
+        Ok(sink) => {
+            thread::spawn(move || forward_signals(id, &params.instance, &receiver, &sink));
+        }
+        Err(_) => {
+            if let Some(subscription) = subscriptions.lock().remove(&id) {
+                unlisten(&subscription);
+            }
+        }
+    }
+}
+
+/// Registers listeners that send the signals the params select to the given channel.
+fn listen(
+    instances: &HashMap<String, InstanceHandle>,
+    params: &SubscriptionParams,
+    sender: &Sender<Signal>,
+) -> Result<Vec<ListenerId>, RpcError> {
+    let instance = instances.get(&params.instance).ok_or_else(|| {
+        RpcError::invalid_params(format!("Unknown instance '{}'", params.instance))
+    })?;
+    let mut holochain = lock_instance(instance).map_err(|err| rpc_error(err.into()))?;
+
+    let filters = match (&params.zome, &params.cap) {
+        (None, None) => vec![SignalFilter::System],
+        (Some(zome_name), Some(cap_name)) => {
+            let names = match params.name {
+                Some(ref name) => vec![name.clone()],
+                None => {
+                    let dna = holochain
+                        .state()
+                        .map_err(rpc_error)?
+                        .nucleus()
+                        .dna()
+                        .ok_or_else(|| rpc_error(HolochainError::DnaMissing.into()))?;
+                    dna.get_capability_with_zome_name(zome_name, cap_name)
+                        .map_err(|err| {
+                            RpcError::invalid_params(HolochainError::Dna(err).description())
+                        })?
+                        .signals
+                        .iter()
+                        .map(|signal| signal.name.clone())
+                        .collect()
+                }
+            };
+            if names.is_empty() {
+                return Err(RpcError::invalid_params(format!(
+                    "Capability '{}' of zome '{}' declares no signals",
+                    cap_name, zome_name
+                )));
+            }
+            names
+                .into_iter()
+                .map(|name| SignalFilter::App {
+                    zome_name: zome_name.clone(),
+                    cap_name: cap_name.clone(),
+                    name,
+                }).collect()
+        }
+        _ => {
+            return Err(RpcError::invalid_params(
+                "Subscribing to app signals requires both zome and cap",
+            ))
+        }
+    };
+
+    let mut listeners = Vec::new();
+    for filter in filters {
+        // listeners get called from the action loop, so they only hand signals off
+        let sender = Mutex::new(sender.clone());
+        let listened = holochain.listen(filter, move |signal| {
+            if let Ok(sender) = sender.lock() {
+                let _ = sender.send(signal.clone());
+            }
+        });
+        match listened {
+            Ok(listener) => listeners.push(listener),
+            Err(error) => {
+                for listener in listeners.iter() {
+                    let _ = holochain.unlisten(listener);
+                }
+                return Err(rpc_error(error));
+            }
+        }
+    }
+    Ok(listeners)
+}
+
+fn unsubscribe(subscriptions: &Subscriptions, id: SubscriptionId) -> Result<Value, RpcError> {
+    let subscription = match id {
+        SubscriptionId::Number(id) => subscriptions.lock().remove(&id),
+        SubscriptionId::String(_) => None,
+    };
+    match subscription {
+        Some(subscription) => {
+            unlisten(&subscription);
+            Ok(Value::Bool(true))
+        }
+        None => Err(RpcError::invalid_params("Unknown subscription")),
+    }
+}
+
+fn unlisten(subscription: &Subscription) {
+    if let Ok(mut holochain) = lock_instance(&subscription.instance) {
+        for listener in subscription.listeners.iter() {
+            let _ = holochain.unlisten(listener);
+        }
+    }
+}
+
+/// Sends the signals to the client until the subscription's listeners are gone,
+/// or the client is.
+fn forward_signals(id: u64, instance_id: &str, signals: &Receiver<Signal>, sink: &Sink) {
+    for signal in signals.iter() {
+        let notification = json!({
+            "subscription": id,
+            "instance": instance_id,
+            "result": signal,
+        });
+        let params = match notification {
+            Value::Object(map) => Params::Map(map),
+            _ => unreachable!(),
+        };
+        if sink.notify(params).wait().is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate ws;
+
+    use super::*;
+    use config::InterfaceDriver;
+    use interface::{
+        start_interface,
+        tests::{test_container, test_interface_config},
+        InterfaceServer,
+    };
+    use serde_json;
+    use std::time::Duration;
+
+    /// A WebSocket client running on a thread of its own.
+    pub struct TestClient {
+        out: ws::Sender,
+        received: Receiver<Value>,
+    }
+
+    impl TestClient {
+        pub fn connect(address: SocketAddr) -> Self {
+            let (out_sender, out_receiver) = channel();
+            let (sender, received) = channel();
+            thread::spawn(move || {
+                ws::connect(format!("ws://{}", address), |out| {
+                    out_sender.send(out).unwrap();
+                    let sender = sender.clone();
+                    move |message: ws::Message| {
+                        let _ = sender.send(serde_json::from_str(&message.into_text()?).unwrap());
+                        Ok(())
+                    }
+                }).unwrap()
+            });
+            TestClient {
+                out: out_receiver
+                    .recv_timeout(Duration::from_secs(5))
+                    .expect("client should connect"),
+                received,
+            }
+        }
+
+        pub fn send(&self, method: &str, params: Value, id: u64) {
+            let request = json!({"jsonrpc": "2.0", "method": method, "params": params, "id": id});
+            self.out.send(request.to_string()).unwrap();
+        }
+
+        pub fn receive(&self) -> Value {
+            self.received
+                .recv_timeout(Duration::from_secs(5))
+                .expect("server should send a message")
+        }
+    }
+
+    impl Drop for TestClient {
+        fn drop(&mut self) {
+            let _ = self.out.close(ws::CloseCode::Normal);
+        }
+    }
+
+    fn start_test_server(instances: &HashMap<String, InstanceHandle>) -> InterfaceServer {
+        start_interface(
+            &test_interface_config(InterfaceDriver::Websocket { port: 0 }),
+            instances,
+            None,
+        ).unwrap()
+    }
+
+    #[test]
+    fn multiplexes_zome_calls() {
+        let container = test_container();
+        let server = start_test_server(container.instances());
+        let client = TestClient::connect(server.address().unwrap());
+
+        client.send("app_bob_0/test_zome/test_cap/main", json!({}), 1);
+        client.send("app_bob_0/test_zome/test_cap/main", json!({}), 2);
+        client.send("app_bob_0/test_zome/test_cap/other", json!({}), 3);
+        let mut responses = vec![client.receive(), client.receive(), client.receive()];
+        responses.sort_by_key(|response| response["id"].as_u64());
+
+        assert_eq!(responses[0]["result"], json!(1337));
+        assert_eq!(responses[1]["result"], json!(1337));
+        assert_eq!(responses[2]["error"]["code"], json!(-32601));
+        server.close();
+    }
+
+    #[test]
+    fn pushes_signals_to_subscribers() {
+        let container = test_container();
+        let server = start_test_server(container.instances());
+        let client = TestClient::connect(server.address().unwrap());
+
+        client.send(SIGNAL_SUBSCRIBE, json!({"instance": "app_bob_0"}), 1);
+        let subscription = client.receive()["result"].clone();
+        assert!(subscription.is_number());
+
+        container
+            .instance("app_bob_0")
+            .unwrap()
+            .lock()
+            .unwrap()
+            .stop()
+            .unwrap();
+        let notification = client.receive();
+        assert_eq!(notification["method"], json!(SIGNAL_NOTIFICATION));
+        assert_eq!(
+            notification["params"],
+            json!({
+                "subscription": subscription,
+                "instance": "app_bob_0",
+                "result": {"System": "InstanceStopped"},
+            })
+        );
+
+        client.send(SIGNAL_UNSUBSCRIBE, json!([subscription]), 2);
+        assert_eq!(client.receive()["result"], json!(true));
+        client.send(SIGNAL_UNSUBSCRIBE, json!([subscription]), 3);
+        assert!(client.receive()["error"].is_object());
+        server.close();
+    }
+
+    #[test]
+    fn rejects_invalid_subscriptions() {
+        let container = test_container();
+        let server = start_test_server(container.instances());
+        let client = TestClient::connect(server.address().unwrap());
+
+        for (id, params) in vec![
+            json!({"instance": "app_alice"}),
+            json!({"instance": "app_bob_0", "zome": "test_zome"}),
+            json!({"instance": "app_bob_0", "zome": "test_zome", "cap": "test_cap"}),
+        ].into_iter()
+        .enumerate()
+        {
+            client.send(SIGNAL_SUBSCRIBE, params, id as u64);
+            assert_eq!(client.receive()["error"]["code"], json!(-32602));
+        }
+        server.close();
+    }
+}
@@ -9,11 +9,12 @@ use nucleus::{
     state::{NucleusState, ValidationResult},
     ExecuteZomeFnResponse, ZomeFnCall,
 };
-use snowflake;
 use std::{
+    borrow::Borrow,
     hash::{Hash, Hasher},
     sync::Arc,
 };
+use uuid::Uuid;
 
 /// Wrapper for actions that provides a unique ID
 /// The unique ID is needed for state tracking to ensure that we can differentiate between two
@@ -21,20 +22,22 @@ use std::{
 /// The standard approach is to drop the ActionWrapper into the key of a state history HashMap and
 /// use the convenience unwrap_to! macro to extract the action data in a reducer.
 /// All reducer functions must accept an ActionWrapper so all dispatchers take an ActionWrapper.
-#[derive(Clone, Debug)]
+/// IDs are v4 UUIDs so they stay unique across processes and restarts, which makes it safe to
+/// persist, log and replay ActionWrappers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ActionWrapper {
     action: Action,
-    id: snowflake::ProcessUniqueId,
+    id: Uuid,
 }
 
 impl ActionWrapper {
     /// constructor from &Action
-    /// internal snowflake ID is automatically set
+    /// internal UUID is automatically set
     pub fn new(a: Action) -> Self {
         ActionWrapper {
             action: a,
             // auto generate id
-            id: snowflake::ProcessUniqueId::new(),
+            id: Uuid::new_v4(),
         }
     }
 
@@ -44,7 +47,7 @@ impl ActionWrapper {
     }
 
     /// read only access to id
-    pub fn id(&self) -> &snowflake::ProcessUniqueId {
+    pub fn id(&self) -> &Uuid {
         &self.id
     }
 }
@@ -58,16 +61,21 @@ impl PartialEq for ActionWrapper {
 impl Eq for ActionWrapper {}
 
 impl Hash for ActionWrapper {
-    /// @TODO dangerous when persisted!
-    /// snowflake only guarantees uniqueness per process
-    /// @see https://github.com/holochain/holochain-rust/issues/203
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
     }
 }
 
+/// Equality and hashing only look at the id, so maps keyed by ActionWrapper can be
+/// queried with the id alone.
+impl Borrow<Uuid> for ActionWrapper {
+    fn borrow(&self) -> &Uuid {
+        &self.id
+    }
+}
+
 /// All Actions for the Holochain Instance Store, according to Redux pattern.
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Action {
     /// entry to Commit
     /// MUST already have passed all callback checks
@@ -99,14 +107,43 @@ pub enum Action {
     /// A validation result that should be stored
     /// Key is an unique id of the calling context
     /// and the hash of the entry that was validated
-    ReturnValidationResult(((snowflake::ProcessUniqueId, Address), ValidationResult)),
-
-    ReturnValidationPackage(
-        (
-            snowflake::ProcessUniqueId,
-            Result<ValidationPackage, HolochainError>,
-        ),
-    ),
+    ReturnValidationResult(((Uuid, Address), ValidationResult)),
+
+    ReturnValidationPackage((Uuid, Result<ValidationPackage, HolochainError>)),
+
+    // The Clear* actions drop a response from the state once the future or observer
+    // waiting for it has read it, so the state doesn't grow with every call.
+    /// drop the response to the action with the given id
+    /// from AgentState::actions and DhtStore::add_link_actions
+    ClearActionResponse(Uuid),
+    /// drop the result of a zome function call
+    ClearZomeFunctionCall(ZomeFnCall),
+    /// drop the validation result stored under the given key
+    ClearValidationResult((Uuid, Address)),
+    /// drop the validation package stored under the given id
+    ClearValidationPackage(Uuid),
+}
+
+impl Action {
+    /// name of the variant, e.g. to count actions by kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Commit(_) => "Commit",
+            Action::GetEntry(_) => "GetEntry",
+            Action::AddLink(_) => "AddLink",
+            Action::ExecuteZomeFunction(_) => "ExecuteZomeFunction",
+            Action::ReturnZomeFunctionResult(_) => "ReturnZomeFunctionResult",
+            Action::InitApplication(_) => "InitApplication",
+            Action::ReturnInitializationResult(_) => "ReturnInitializationResult",
+            Action::Call(_) => "Call",
+            Action::ReturnValidationResult(_) => "ReturnValidationResult",
+            Action::ReturnValidationPackage(_) => "ReturnValidationPackage",
+            Action::ClearActionResponse(_) => "ClearActionResponse",
+            Action::ClearZomeFunctionCall(_) => "ClearZomeFunctionCall",
+            Action::ClearValidationResult(_) => "ClearValidationResult",
+            Action::ClearValidationPackage(_) => "ClearValidationPackage",
+        }
+    }
 }
 
 /// function signature for action handler functions
@@ -122,6 +159,7 @@ pub mod tests {
     use action::{Action, ActionWrapper};
     use holochain_core_types::entry::{test_entry, test_entry_address};
     use nucleus::tests::test_call_response;
+    use serde_json;
     use test_utils::calculate_hash;
 
     /// dummy action
@@ -164,7 +202,7 @@ pub mod tests {
         let aw1 = test_action_wrapper();
         let aw2 = test_action_wrapper();
 
-        // snowflake enforces uniqueness
+        // UUIDs enforce uniqueness
         assert_eq!(aw1, aw1);
         assert_ne!(aw1, aw2);
     }
@@ -199,4 +237,23 @@ pub mod tests {
         assert_ne!(calculate_hash(&aw1), calculate_hash(&aw2));
     }
 
+    #[test]
+    /// tests that action wrappers keep their id and action through a json round trip
+    fn action_wrapper_serialization_round_trip() {
+        for aw in vec![
+            test_action_wrapper(),
+            test_action_wrapper_commit(),
+            test_action_wrapper_rzfr(),
+        ] {
+            let json = serde_json::to_string(&aw).expect("action wrapper should serialize");
+            let restored: ActionWrapper =
+                serde_json::from_str(&json).expect("action wrapper should deserialize");
+
+            assert_eq!(aw, restored);
+            assert_eq!(aw.id(), restored.id());
+            assert_eq!(aw.action(), restored.action());
+            assert_eq!(calculate_hash(&aw), calculate_hash(&restored));
+        }
+    }
+
 }
@@ -4,8 +4,9 @@ use agent::state::ActionResponse;
 use context::Context;
 use futures::Future;
 use holochain_core_types::{cas::content::Address, entry::Entry, error::HolochainError};
-use instance::dispatch_action;
-use std::sync::{mpsc::SyncSender, Arc};
+use instance::{dispatch_action, ActionSender};
+use std::sync::Arc;
+use waker_registry::poll_response;
 
 /// Commit Action Creator
 /// This is the high-level commit function that wraps the whole commit process and is what should
@@ -14,11 +15,12 @@ use std::sync::{mpsc::SyncSender, Arc};
 /// Returns a future that resolves to an ActionResponse.
 pub fn commit_entry(
     entry: Entry,
-    action_channel: &SyncSender<ActionWrapper>,
+    action_channel: &ActionSender,
     context: &Arc<Context>,
 ) -> CommitFuture {
     let action_wrapper = ActionWrapper::new(Action::Commit(entry));
-    dispatch_action(action_channel, action_wrapper.clone());
+    // if the action loop has been stopped the future fails once it gets polled
+    let _ = dispatch_action(action_channel, action_wrapper.clone());
     CommitFuture {
         context: context.clone(),
         action: action_wrapper,
@@ -40,25 +42,20 @@ impl Future for CommitFuture {
         &mut self,
         cx: &mut futures::task::Context<'_>,
     ) -> Result<futures::Async<Address>, Self::Error> {
-        //
-        // TODO: connect the waker to state updates for performance reasons
-        // See: https://github.com/holochain/holochain-rust/issues/314
-        //
-        cx.waker().wake();
-        match self
-            .context
-            .state()
-            .unwrap()
-            .agent()
-            .actions()
-            .get(&self.action)
-        {
-            Some(ActionResponse::Commit(result)) => match result {
-                Ok(address) => Ok(futures::Async::Ready(address.clone())),
-                Err(error) => Err(error.clone()),
-            },
-            Some(_) => unreachable!(),
-            None => Ok(futures::Async::Pending),
+        let action = &self.action;
+        let response = poll_response(
+            &self.context,
+            action.id(),
+            Action::ClearActionResponse(action.id().clone()),
+            cx,
+            |state| state.agent().actions().get(action).cloned(),
+        )?;
+        match response {
+            futures::Async::Ready(ActionResponse::Commit(result)) => {
+                result.map(futures::Async::Ready)
+            }
+            futures::Async::Ready(_) => unreachable!(),
+            futures::Async::Pending => Ok(futures::Async::Pending),
         }
     }
 }
@@ -1,11 +1,10 @@
 use action::{Action, ActionWrapper, AgentReduceFn};
 use agent::chain_store::ChainStore;
 use context::Context;
-use holochain_cas_implementations::cas::file::FilesystemStorage;
 use holochain_core_types::{
     cas::{
         content::{Address, AddressableContent},
-        storage::ContentAddressableStorage,
+        storage::{ContentAddressableStorage, DynContentAddressableStorage},
     },
     chain_header::ChainHeader,
     entry::Entry,
@@ -23,16 +22,15 @@ use std::{collections::HashMap, sync::Arc};
 pub struct AgentState {
     keys: Option<Keys>,
     /// every action and the result of that action
-    // @TODO this will blow up memory, implement as some kind of dropping/FIFO with a limit?
-    // @see https://github.com/holochain/holochain-rust/issues/166
+    /// results get dropped by a ClearActionResponse action once they have been read
     actions: HashMap<ActionWrapper, ActionResponse>,
-    chain: ChainStore<FilesystemStorage>,
+    chain: ChainStore<DynContentAddressableStorage>,
     top_chain_header: Option<ChainHeader>,
 }
 
 impl AgentState {
     /// builds a new, empty AgentState
-    pub fn new(chain: ChainStore<FilesystemStorage>) -> AgentState {
+    pub fn new(chain: ChainStore<DynContentAddressableStorage>) -> AgentState {
         AgentState {
             keys: None,
             actions: HashMap::new(),
@@ -41,6 +39,19 @@ impl AgentState {
         }
     }
 
+    /// builds an AgentState on top of an existing source chain, e.g. after a restart
+    pub fn new_with_top_chain_header(
+        chain: ChainStore<DynContentAddressableStorage>,
+        top_chain_header: Option<ChainHeader>,
+    ) -> AgentState {
+        AgentState {
+            keys: None,
+            actions: HashMap::new(),
+            chain,
+            top_chain_header,
+        }
+    }
+
     /// getter for a copy of self.keys
     pub fn keys(&self) -> Option<Keys> {
         self.keys.clone()
@@ -52,7 +63,7 @@ impl AgentState {
         self.actions.clone()
     }
 
-    pub fn chain(&self) -> ChainStore<FilesystemStorage> {
+    pub fn chain(&self) -> ChainStore<DynContentAddressableStorage> {
         self.chain.clone()
     }
 
@@ -172,15 +183,46 @@ fn reduce_get_entry(
     );
 }
 
+/// drop the response to an action once it has been read
+fn reduce_clear_action_response(
+    _context: Arc<Context>,
+    state: &mut AgentState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let id = unwrap_to!(action => Action::ClearActionResponse);
+    state.actions.remove(id);
+}
+
 /// maps incoming action to the correct handler
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<AgentReduceFn> {
     match action_wrapper.action() {
         Action::Commit(_) => Some(reduce_commit_entry),
         Action::GetEntry(_) => Some(reduce_get_entry),
+        Action::ClearActionResponse(_) => Some(reduce_clear_action_response),
         _ => None,
     }
 }
 
+/// Resolve the response to a Commit that middleware vetoed with the reason for the veto.
+pub(crate) fn reduce_veto(
+    old_state: Arc<AgentState>,
+    action_wrapper: &ActionWrapper,
+    reason: &HolochainError,
+) -> Arc<AgentState> {
+    match action_wrapper.action() {
+        Action::Commit(_) => {
+            let mut new_state: AgentState = (*old_state).clone();
+            new_state.actions.insert(
+                action_wrapper.clone(),
+                ActionResponse::Commit(Err(reason.clone())),
+            );
+            Arc::new(new_state)
+        }
+        _ => old_state,
+    }
+}
+
 /// Reduce Agent's state according to provided Action
 pub fn reduce(
     context: Arc<Context>,
@@ -200,11 +242,17 @@ pub fn reduce(
 
 #[cfg(test)]
 pub mod tests {
-    use super::{reduce_commit_entry, reduce_get_entry, ActionResponse, AgentState};
-    use action::tests::{test_action_wrapper_commit, test_action_wrapper_get};
-    use agent::chain_store::tests::test_chain_store;
+    use super::{
+        reduce_clear_action_response, reduce_commit_entry, reduce_get_entry, ActionResponse,
+        AgentState,
+    };
+    use action::{
+        tests::{test_action_wrapper_commit, test_action_wrapper_get},
+        Action, ActionWrapper,
+    };
+    use agent::chain_store::{tests::test_chain_store, ChainStore};
     use holochain_core_types::{
-        cas::content::AddressableContent,
+        cas::{content::AddressableContent, storage::DynContentAddressableStorage},
         entry::{test_entry, test_entry_address},
         error::HolochainError,
         json::ToJson,
@@ -214,7 +262,9 @@ pub mod tests {
 
     /// dummy agent state
     pub fn test_agent_state() -> AgentState {
-        AgentState::new(test_chain_store())
+        AgentState::new(ChainStore::new(DynContentAddressableStorage::new(
+            test_chain_store().content_storage(),
+        )))
     }
 
     /// dummy action response for a successful commit as test_entry()
@@ -259,6 +309,20 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test that a consumed commit response gets dropped from the state
+    fn test_reduce_clear_action_response() {
+        let mut state = test_agent_state();
+        let action_wrapper = test_action_wrapper_commit();
+        reduce_commit_entry(test_context("bob"), &mut state, &action_wrapper);
+
+        let clear_action_wrapper =
+            ActionWrapper::new(Action::ClearActionResponse(action_wrapper.id().clone()));
+        reduce_clear_action_response(test_context("bob"), &mut state, &clear_action_wrapper);
+
+        assert_eq!(state.actions().get(&action_wrapper), None);
+    }
+
     #[test]
     /// test for reducing get entry
     fn test_reduce_get_entry() {
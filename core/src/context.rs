@@ -1,16 +1,29 @@
 use action::ActionWrapper;
+use history::RetentionPolicy;
 use holochain_agent::Agent;
-use holochain_core_types::error::HolochainError;
-use instance::Observer;
-use logger::Logger;
+use holochain_core_types::{
+    cas::storage::{ContentAddressableStorage, DynContentAddressableStorage},
+    eav::{DynEntityAttributeValueStorage, EntityAttributeValueStorage},
+    error::HolochainError,
+};
+use instance::{ActionSender, Observer};
+use journal::Journal;
+use logger::{LogRecord, Logger};
+use metrics::{MeteredEavStorage, MeteredStorage, MetricsRegistry};
+use middleware::Middleware;
+use nucleus::ribosome::{metering::ExecutionLimits, module_cache::ModuleCache};
 use persister::Persister;
+use signal::SignalRegistry;
 use state::State;
-use std::sync::{
-    mpsc::{sync_channel, SyncSender},
-    Arc, Mutex, RwLock, RwLockReadGuard,
+use std::{
+    fmt::Debug,
+    sync::{
+        mpsc::{sync_channel, SyncSender},
+        Arc, Mutex, RwLock, RwLockReadGuard,
+    },
 };
-
-use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+use tasks::{TaskGuard, TaskTracker};
+use waker_registry::WakerRegistry;
 
 /// Context holds the components that parts of a Holochain instance need in order to operate.
 /// This includes components that are injected from the outside like logger and persister
@@ -22,10 +35,27 @@ pub struct Context {
     pub logger: Arc<Mutex<Logger>>,
     pub persister: Arc<Mutex<Persister>>,
     state: Option<Arc<RwLock<State>>>,
-    pub action_channel: SyncSender<ActionWrapper>,
+    pub action_channel: ActionSender,
     pub observer_channel: SyncSender<Observer>,
-    pub file_storage: FilesystemStorage,
-    pub eav_storage: EavFileStorage,
+    /// backs the source chain and the DHT shard, any ContentAddressableStorage can be injected
+    pub content_storage: DynContentAddressableStorage,
+    /// backs the DHT meta data, any EntityAttributeValueStorage can be injected
+    pub eav_storage: DynEntityAttributeValueStorage,
+    pub retention_policy: RetentionPolicy,
+    pub waker_registry: Arc<WakerRegistry>,
+    pub tasks: Arc<TaskTracker>,
+    pub middleware: Vec<Arc<Middleware>>,
+    /// records every reduced action if set
+    pub journal: Option<Arc<Mutex<Journal>>>,
+    /// set while a journal gets replayed, so reducers don't run their side effects again
+    pub replaying: bool,
+    pub signal_registry: Arc<SignalRegistry>,
+    /// also meters the storages and the action queue of the context
+    pub metrics: Arc<MetricsRegistry>,
+    /// parsed wasm modules of the zomes, shared by all calls into them
+    pub module_cache: Arc<ModuleCache>,
+    /// resources a zome call may use
+    pub execution_limits: ExecutionLimits,
 }
 
 impl Context {
@@ -33,45 +63,87 @@ impl Context {
         100
     }
 
-    pub fn new(
+    pub fn new<CAS, EAVS>(
         agent: Agent,
         logger: Arc<Mutex<Logger>>,
         persister: Arc<Mutex<Persister>>,
-        cas: FilesystemStorage,
-        eav: EavFileStorage,
-    ) -> Result<Context, HolochainError> {
+        cas: CAS,
+        eav: EAVS,
+    ) -> Result<Context, HolochainError>
+    where
+        CAS: ContentAddressableStorage + PartialEq + Debug + 'static,
+        EAVS: EntityAttributeValueStorage + Send + Sync + PartialEq + Debug + 'static,
+    {
         let (tx_action, _) = sync_channel(Self::default_channel_buffer_size());
         let (tx_observer, _) = sync_channel(Self::default_channel_buffer_size());
+        let metrics = Arc::new(MetricsRegistry::new());
         Ok(Context {
             agent,
             logger,
             persister,
             state: None,
-            action_channel: tx_action,
+            action_channel: ActionSender::new(tx_action, metrics.clone()),
             observer_channel: tx_observer,
-            file_storage: cas,
-            eav_storage: eav,
+            content_storage: DynContentAddressableStorage::new(MeteredStorage::new(
+                cas,
+                metrics.clone(),
+            )),
+            eav_storage: DynEntityAttributeValueStorage::new(MeteredEavStorage::new(
+                eav,
+                metrics.clone(),
+            )),
+            retention_policy: RetentionPolicy::default(),
+            waker_registry: Arc::new(WakerRegistry::new()),
+            tasks: Arc::new(TaskTracker::new()),
+            middleware: Vec::new(),
+            journal: None,
+            replaying: false,
+            signal_registry: Arc::new(SignalRegistry::new()),
+            metrics,
+            module_cache: Arc::new(ModuleCache::new()),
+            execution_limits: ExecutionLimits::default(),
         })
     }
 
-    pub fn new_with_channels(
+    pub fn new_with_channels<CAS, EAVS>(
         agent: Agent,
         logger: Arc<Mutex<Logger>>,
         persister: Arc<Mutex<Persister>>,
         action_channel: SyncSender<ActionWrapper>,
         observer_channel: SyncSender<Observer>,
-        cas: FilesystemStorage,
-        eav: EavFileStorage,
-    ) -> Result<Context, HolochainError> {
+        cas: CAS,
+        eav: EAVS,
+    ) -> Result<Context, HolochainError>
+    where
+        CAS: ContentAddressableStorage + PartialEq + Debug + 'static,
+        EAVS: EntityAttributeValueStorage + Send + Sync + PartialEq + Debug + 'static,
+    {
+        let metrics = Arc::new(MetricsRegistry::new());
         Ok(Context {
             agent,
             logger,
             persister,
             state: None,
-            action_channel,
+            action_channel: ActionSender::new(action_channel, metrics.clone()),
             observer_channel,
-            file_storage: cas,
-            eav_storage: eav,
+            content_storage: DynContentAddressableStorage::new(MeteredStorage::new(
+                cas,
+                metrics.clone(),
+            )),
+            eav_storage: DynEntityAttributeValueStorage::new(MeteredEavStorage::new(
+                eav,
+                metrics.clone(),
+            )),
+            retention_policy: RetentionPolicy::default(),
+            waker_registry: Arc::new(WakerRegistry::new()),
+            tasks: Arc::new(TaskTracker::new()),
+            middleware: Vec::new(),
+            journal: None,
+            replaying: false,
+            signal_registry: Arc::new(SignalRegistry::new()),
+            metrics,
+            module_cache: Arc::new(ModuleCache::new()),
+            execution_limits: ExecutionLimits::default(),
         })
     }
     // helper function to make it easier to call the logger
@@ -81,6 +153,23 @@ impl Context {
         Ok(())
     }
 
+    // helper function to log a structured record with level, target and fields
+    pub fn log_record(&self, record: LogRecord) -> Result<(), HolochainError> {
+        let mut logger = self.logger.lock().or(Err(HolochainError::LoggingError))?;
+        logger.log_record(&record);
+        Ok(())
+    }
+
+    /// registers middleware that sees every action of the instance, after the already registered ones
+    pub fn add_middleware(&mut self, middleware: Arc<Middleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// registers a background task that stopping the instance has to wait for
+    pub fn start_task(&self) -> TaskGuard {
+        TaskTracker::start(&self.tasks)
+    }
+
     pub(crate) fn set_state(&mut self, state: Arc<RwLock<State>>) {
         self.state = Some(state);
     }
@@ -100,6 +189,7 @@ mod tests {
     extern crate test_utils;
     use self::tempfile::tempdir;
     use super::*;
+    use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
     use instance::tests::test_logger;
     use persister::SimplePersister;
     use state::State;
@@ -6,6 +6,7 @@ use futures::{Async, Future};
 use holochain_core_types::{error::HolochainError, links_entry::Link};
 use instance::dispatch_action;
 use std::sync::Arc;
+use waker_registry::poll_response;
 
 /// AddLink Action Creator
 /// This action creator dispatches an AddLink action which is consumed by the DHT reducer.
@@ -17,7 +18,8 @@ use std::sync::Arc;
 /// Returns a future that resolves to an Ok(()) or an Err(HolochainError).
 pub fn add_link(link: &Link, context: &Arc<Context>) -> AddLinkFuture {
     let action_wrapper = ActionWrapper::new(Action::AddLink(link.clone()));
-    dispatch_action(&context.action_channel, action_wrapper.clone());
+    // if the action loop has been stopped the future fails once it gets polled
+    let _ = dispatch_action(&context.action_channel, action_wrapper.clone());
 
     AddLinkFuture {
         context: context.clone(),
@@ -38,19 +40,17 @@ impl Future for AddLinkFuture {
         &mut self,
         cx: &mut futures::task::Context<'_>,
     ) -> Result<Async<Self::Item>, Self::Error> {
-        //
-        // TODO: connect the waker to state updates for performance reasons
-        // See: https://github.com/holochain/holochain-rust/issues/314
-        //
-        cx.waker().wake();
-        if let Some(state) = self.context.state() {
-            match state.dht().add_link_actions().get(&self.action) {
-                Some(Ok(())) => Ok(futures::Async::Ready(())),
-                Some(Err(e)) => Err(e.clone()),
-                None => Ok(futures::Async::Pending),
-            }
-        } else {
-            Ok(futures::Async::Pending)
+        let action = &self.action;
+        let response = poll_response(
+            &self.context,
+            action.id(),
+            Action::ClearActionResponse(action.id().clone()),
+            cx,
+            |state| state.dht().add_link_actions().get(action).cloned(),
+        )?;
+        match response {
+            Async::Ready(result) => result.map(Async::Ready),
+            Async::Pending => Ok(Async::Pending),
         }
     }
 }
@@ -81,6 +81,8 @@ mod tests {
         let result = block_on(add_link(&link, &context.clone()));
 
         assert!(result.is_ok(), "result = {:?}", result);
+        // resolved futures don't leave their waker behind
+        assert!(context.waker_registry.is_empty());
     }
 
     #[test]
@@ -9,6 +9,7 @@ use holochain_core_types::{
     entry::Entry,
     error::HolochainError,
 };
+use logger::{LogLevel, LogRecord};
 use std::sync::Arc;
 
 // A function that might return a mutated DhtStore
@@ -40,6 +41,28 @@ where
     }
 }
 
+/// Resolve the response to an AddLink that middleware vetoed with the reason for the veto.
+pub(crate) fn reduce_veto<CAS, EAVS>(
+    old_store: Arc<DhtStore<CAS, EAVS>>,
+    action_wrapper: &ActionWrapper,
+    reason: &HolochainError,
+) -> Arc<DhtStore<CAS, EAVS>>
+where
+    CAS: ContentAddressableStorage + Sized + Clone + PartialEq,
+    EAVS: EntityAttributeValueStorage + Sized + Clone + PartialEq,
+{
+    match action_wrapper.action() {
+        Action::AddLink(_) => {
+            let mut new_store = (*old_store).clone();
+            new_store
+                .add_link_actions_mut()
+                .insert(action_wrapper.clone(), Err(reason.clone()));
+            Arc::new(new_store)
+        }
+        _ => old_store,
+    }
+}
+
 /// Maps incoming action to the correct reducer
 fn resolve_reducer<CAS, EAVS>(action_wrapper: &ActionWrapper) -> Option<DhtReducer<CAS, EAVS>>
 where
@@ -51,13 +74,14 @@ where
         Action::GetEntry(_) => Some(reduce_get_entry_from_network),
         Action::AddLink(_) => Some(reduce_add_link),
         //Action::GetLinks(_) => Some(reduce_get_links),
+        Action::ClearActionResponse(_) => Some(reduce_clear_action_response),
         _ => None,
     }
 }
 
 //
 pub(crate) fn commit_sys_entry<CAS, EAVS>(
-    _context: Arc<Context>,
+    context: Arc<Context>,
     old_store: &DhtStore<CAS, EAVS>,
     entry: &Entry,
 ) -> Option<DhtStore<CAS, EAVS>>
@@ -72,8 +96,16 @@ where
     // Add it local storage
     let mut new_store = (*old_store).clone();
     let res = new_store.content_storage_mut().add(entry);
-    if res.is_err() {
-        // TODO #439 - Log the error. Once we have better logging.
+    if let Err(err) = res {
+        let _ = context.log_record(LogRecord::new(
+            LogLevel::Error,
+            "dht",
+            format!(
+                "could not add sys entry {} to the DHT: {}",
+                entry.address(),
+                err
+            ),
+        ));
         return None;
     }
     // Note: System entry types are not published to the network
@@ -100,7 +132,14 @@ where
         .expect("context.state must hold DNA in order to commit an app entry.");
     let maybe_def = dna.get_entry_type_def(&entry.entry_type().to_string());
     if maybe_def.is_none() {
-        // TODO #439 - Log the error. Once we have better logging.
+        let _ = context.log_record(LogRecord::new(
+            LogLevel::Error,
+            "dht",
+            format!(
+                "no definition for entry type '{}' in the DNA",
+                entry.entry_type()
+            ),
+        ));
         return None;
     }
     let entry_type_def = maybe_def.unwrap();
@@ -113,8 +152,16 @@ where
     // Add it to local storage...
     let mut new_store = (*old_store).clone();
     let res = new_store.content_storage_mut().add(entry);
-    if res.is_err() {
-        // TODO #439 - Log the error. Once we have better logging.
+    if let Err(err) = res {
+        let _ = context.log_record(LogRecord::new(
+            LogLevel::Error,
+            "dht",
+            format!(
+                "could not add entry {} to the DHT: {}",
+                entry.address(),
+                err
+            ),
+        ));
         return None;
     }
     // ...and publish to the network if its not private
@@ -142,7 +189,13 @@ where
         .contains(&entry.address())
         .unwrap()
     {
-        // TODO #439 - Log a warning saying this should not happen. Once we have better logging.
+        let _ = context.log_record(
+            LogRecord::new(
+                LogLevel::Warn,
+                "dht",
+                format!("entry {} is already in the DHT", entry.address()),
+            ).action_id(action_wrapper.id()),
+        );
         return None;
     }
 
@@ -155,7 +208,7 @@ where
 
 //
 pub(crate) fn reduce_get_entry_from_network<CAS, EAVS>(
-    _context: Arc<Context>,
+    context: Arc<Context>,
     old_store: &DhtStore<CAS, EAVS>,
     action_wrapper: &ActionWrapper,
 ) -> Option<DhtStore<CAS, EAVS>>
@@ -168,7 +221,13 @@ where
     let address = unwrap_to!(action => Action::GetEntry);
     // pre-condition check: Look in local storage if it already has it.
     if old_store.content_storage().contains(address).unwrap() {
-        // TODO #439 - Log a warning saying this should not happen. Once we have better logging.
+        let _ = context.log_record(
+            LogRecord::new(
+                LogLevel::Warn,
+                "dht",
+                format!("entry {} is already in the DHT, not fetching it", address),
+            ).action_id(action_wrapper.id()),
+        );
         return None;
     }
     // Retrieve it from the network...
@@ -182,7 +241,16 @@ where
             // ...and add it to the local storage
             let res = new_store.content_storage_mut().add(&entry);
             match res {
-                Err(_) => None,
+                Err(err) => {
+                    let _ = context.log_record(
+                        LogRecord::new(
+                            LogLevel::Error,
+                            "dht",
+                            format!("could not add entry {} to the DHT: {}", address, err),
+                        ).action_id(action_wrapper.id()),
+                    );
+                    None
+                }
                 Ok(()) => Some(new_store),
             }
         })
@@ -224,6 +292,26 @@ where
     Some(new_store)
 }
 
+/// Drops the result of an AddLink action once its future has read it
+pub(crate) fn reduce_clear_action_response<CAS, EAVS>(
+    _context: Arc<Context>,
+    old_store: &DhtStore<CAS, EAVS>,
+    action_wrapper: &ActionWrapper,
+) -> Option<DhtStore<CAS, EAVS>>
+where
+    CAS: ContentAddressableStorage + Sized + Clone + PartialEq,
+    EAVS: EntityAttributeValueStorage + Sized + Clone + PartialEq,
+{
+    let action = action_wrapper.action();
+    let id = unwrap_to!(action => Action::ClearActionResponse);
+    if !old_store.add_link_actions().contains_key(id) {
+        return None;
+    }
+    let mut new_store = (*old_store).clone();
+    new_store.add_link_actions_mut().remove(id);
+    Some(new_store)
+}
+
 #[allow(dead_code)]
 pub(crate) fn reduce_get_links<CAS, EAVS>(
     _context: Arc<Context>,
@@ -246,14 +334,13 @@ pub mod tests {
         dht_reducers::{commit_sys_entry, reduce},
         dht_store::DhtStore,
     };
-    use holochain_cas_implementations::eav::file::EavFileStorage;
     use holochain_core_types::{
         cas::{content::AddressableContent, storage::ContentAddressableStorage},
-        eav::EntityAttributeValueStorage,
+        eav::{DynEntityAttributeValueStorage, EntityAttributeValueStorage},
         entry::{test_entry, test_sys_entry, test_unpublishable_entry, Entry},
         links_entry::Link,
     };
-    use instance::tests::test_context;
+    use instance::tests::{test_context, test_context_and_logger};
     use state::test_store;
     use std::sync::{Arc, RwLock};
 
@@ -301,6 +388,24 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test that committing an entry that is already stored gets logged instead of ignored
+    fn logs_commit_of_stored_entry() {
+        let (context, logger) = test_context_and_logger("bob");
+        let store = test_store(context.clone());
+        let entry = test_entry();
+        store.dht().content_storage().add(&entry).unwrap();
+
+        let action = ActionWrapper::new(Action::Commit(entry.clone()));
+        let new_dht_store = reduce(Arc::clone(&context), store.dht(), &action);
+
+        assert_eq!(new_dht_store, store.dht());
+        assert_eq!(
+            logger.lock().unwrap().log,
+            vec![format!("entry {} is already in the DHT", entry.address())]
+        );
+    }
+
     #[test]
     fn can_add_links() {
         let context = test_context("bob");
@@ -311,13 +416,13 @@ pub mod tests {
 
         let mut context = (*context).clone();
         context.set_state(locked_state.clone());
-        let _ = context.file_storage.add(&entry);
+        let _ = context.content_storage.add(&entry);
         let context = Arc::new(context);
 
         let link = Link::new(&entry.address(), &entry.address(), "test-tag");
         let action = ActionWrapper::new(Action::AddLink(link.clone()));
 
-        let new_dht_store: DhtStore<_, EavFileStorage>;
+        let new_dht_store: DhtStore<_, DynEntityAttributeValueStorage>;
         {
             let state = locked_state.read().unwrap();
 
@@ -352,7 +457,7 @@ pub mod tests {
         let link = Link::new(&entry.address(), &entry.address(), "test-tag");
         let action = ActionWrapper::new(Action::AddLink(link.clone()));
 
-        let new_dht_store: DhtStore<_, EavFileStorage>;
+        let new_dht_store: DhtStore<_, DynEntityAttributeValueStorage>;
         {
             let state = locked_state.read().unwrap();
 
@@ -12,7 +12,8 @@ use holochain_core_types::{
 use std::collections::{HashMap, HashSet};
 
 // Placeholder network module
-#[derive(Clone, Debug, PartialEq)]
+// Its setup is part of the StateSnapshot, so it survives a restart of the instance.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Network {
     // FIXME
 }
@@ -55,7 +56,12 @@ where
     // LifeCycle
     // =========
     pub fn new(content_storage: CAS, meta_storage: EAVS) -> Self {
-        let network = Network {};
+        Self::new_with_network(content_storage, meta_storage, Network::default())
+    }
+
+    /// Creates a DhtStore on the given storages with the network module set up like it was
+    /// when the instance got persisted.
+    pub fn new_with_network(content_storage: CAS, meta_storage: EAVS, network: Network) -> Self {
         DhtStore {
             content_storage,
             meta_storage,
@@ -98,7 +104,7 @@ where
     pub(crate) fn meta_storage_mut(&mut self) -> &mut EAVS {
         &mut self.meta_storage
     }
-    pub(crate) fn network(&self) -> &Network {
+    pub fn network(&self) -> &Network {
         &self.network
     }
     pub(crate) fn network_mut(&mut self) -> &mut Network {
@@ -0,0 +1,183 @@
+//! Bounded history of the actions that went through the redux store.
+
+use action::ActionWrapper;
+use std::{
+    collections::{HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Maximum number of actions kept in the history by default.
+pub const DEFAULT_MAX_HISTORY_LEN: usize = 1000;
+
+/// Configures how much of the action history an instance keeps around.
+/// Set on the Context, so every instance can have its own policy.
+/// Responses that got stored in the state for a future or observer (zome call results,
+/// validation results etc.) are dropped as soon as they have been read,
+/// independent of this policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetentionPolicy {
+    /// drop the oldest actions once the history holds more than this many actions
+    pub max_history_len: Option<usize>,
+    /// drop actions that have been reduced longer ago than this
+    pub max_history_age: Option<Duration>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_history_len: Some(DEFAULT_MAX_HISTORY_LEN),
+            max_history_age: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// keeps every action forever, e.g. for time travel debugging
+    pub fn unbounded() -> Self {
+        RetentionPolicy {
+            max_history_len: None,
+            max_history_age: None,
+        }
+    }
+}
+
+/// The actions reduced by the store in the order they were reduced.
+/// The most recent action is never pruned, since dispatch_action_and_wait() relies on finding it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ActionHistory {
+    actions: VecDeque<(ActionWrapper, Instant)>,
+    ids: HashSet<Uuid>,
+}
+
+impl ActionHistory {
+    pub fn new() -> Self {
+        ActionHistory::default()
+    }
+
+    /// appends an action that just got reduced
+    pub fn insert(&mut self, action_wrapper: ActionWrapper) {
+        if self.ids.insert(action_wrapper.id().clone()) {
+            self.actions.push_back((action_wrapper, Instant::now()));
+        }
+    }
+
+    pub fn contains(&self, action_wrapper: &ActionWrapper) -> bool {
+        self.ids.contains(action_wrapper.id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// iterates the retained actions, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &ActionWrapper> {
+        self.actions
+            .iter()
+            .map(|(action_wrapper, _)| action_wrapper)
+    }
+
+    /// drops the oldest actions until the history complies with the given policy
+    pub fn prune(&mut self, policy: &RetentionPolicy) {
+        while self.actions.len() > 1 && self.exceeds(policy) {
+            if let Some((action_wrapper, _)) = self.actions.pop_front() {
+                self.ids.remove(action_wrapper.id());
+            }
+        }
+    }
+
+    fn exceeds(&self, policy: &RetentionPolicy) -> bool {
+        let too_long = policy
+            .max_history_len
+            .map(|max_len| self.actions.len() > max_len)
+            .unwrap_or(false);
+        let too_old = match (policy.max_history_age, self.actions.front()) {
+            (Some(max_age), Some((_, reduced_at))) => reduced_at.elapsed() > max_age,
+            _ => false,
+        };
+        too_long || too_old
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use action::tests::test_action_wrapper;
+    use std::thread::sleep;
+
+    #[test]
+    /// test that actions are iterated in the order they were inserted
+    fn history_keeps_order() {
+        let mut history = ActionHistory::new();
+        let actions: Vec<ActionWrapper> = (0..3).map(|_| test_action_wrapper()).collect();
+        for action_wrapper in actions.iter() {
+            history.insert(action_wrapper.clone());
+        }
+        // inserting the same action twice doesn't duplicate it
+        history.insert(actions[0].clone());
+
+        assert_eq!(history.len(), 3);
+        assert!(history.contains(&actions[1]));
+        assert_eq!(history.iter().cloned().collect::<Vec<_>>(), actions);
+    }
+
+    #[test]
+    /// test that pruning by length drops the oldest actions
+    fn prune_by_len() {
+        let mut history = ActionHistory::new();
+        let actions: Vec<ActionWrapper> = (0..5).map(|_| test_action_wrapper()).collect();
+        for action_wrapper in actions.iter() {
+            history.insert(action_wrapper.clone());
+        }
+
+        history.prune(&RetentionPolicy {
+            max_history_len: Some(2),
+            max_history_age: None,
+        });
+
+        assert_eq!(history.len(), 2);
+        assert!(!history.contains(&actions[0]));
+        assert!(!history.contains(&actions[2]));
+        assert!(history.contains(&actions[3]));
+        assert!(history.contains(&actions[4]));
+    }
+
+    #[test]
+    /// test that pruning by age drops old actions but keeps the most recent one
+    fn prune_by_age() {
+        let mut history = ActionHistory::new();
+        let old = test_action_wrapper();
+        let new = test_action_wrapper();
+        history.insert(old.clone());
+        history.insert(new.clone());
+        sleep(Duration::from_millis(20));
+
+        history.prune(&RetentionPolicy {
+            max_history_len: None,
+            max_history_age: Some(Duration::from_millis(10)),
+        });
+
+        assert_eq!(history.len(), 1);
+        assert!(!history.contains(&old));
+        assert!(history.contains(&new));
+    }
+
+    #[test]
+    /// test that the unbounded policy never drops anything
+    fn prune_unbounded() {
+        let mut history = ActionHistory::new();
+        for _ in 0..(DEFAULT_MAX_HISTORY_LEN + 1) {
+            history.insert(test_action_wrapper());
+        }
+
+        history.prune(&RetentionPolicy::unbounded());
+        assert_eq!(history.len(), DEFAULT_MAX_HISTORY_LEN + 1);
+
+        history.prune(&RetentionPolicy::default());
+        assert_eq!(history.len(), DEFAULT_MAX_HISTORY_LEN);
+    }
+}
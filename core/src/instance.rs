@@ -1,25 +1,100 @@
 use action::ActionWrapper;
 use context::Context;
+use holochain_core_types::error::{HcResult, HolochainError};
+use metrics::MetricsRegistry;
+use middleware;
+use signal::system_signal;
 use state::State;
 use std::{
     sync::{
-        mpsc::{sync_channel, Receiver, SyncSender},
-        Arc, RwLock, RwLockReadGuard,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{sync_channel, Receiver, RecvTimeoutError, SendError, SyncSender, TrySendError},
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard,
     },
-    thread,
-    time::Duration,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
+use waker_registry::{response_key, waiting_key};
 
 pub const RECV_DEFAULT_TIMEOUT_MS: Duration = Duration::from_millis(10000);
 
+/// Default time a stopping instance waits for running zome calls and validations
+pub const STOP_DEFAULT_TIMEOUT_MS: Duration = Duration::from_millis(10000);
+
+/// How often an idle action loop checks if it should stop
+const ACTION_LOOP_TICK_MS: Duration = Duration::from_millis(100);
+
 /// Object representing a Holochain instance, i.e. a running holochain (DNA + DHT + source-chain)
 /// Holds the Event loop and processes it with the redux pattern.
 #[derive(Clone)]
 pub struct Instance {
     /// The object holding the state. Actions go through the store sequentially.
     state: Arc<RwLock<State>>,
-    action_channel: SyncSender<ActionWrapper>,
+    action_channel: ActionSender,
     observer_channel: SyncSender<Observer>,
+    action_loop: Arc<Mutex<Option<ActionLoop>>>,
+}
+
+/// Sending end of the action queue of an instance.
+/// Counts the actions in the queue, so the metrics show how saturated it is.
+/// All clones share a stopped flag, so everybody holding one can tell that the action loop
+/// is gone before dispatching to it or waiting on it.
+#[derive(Clone)]
+pub struct ActionSender {
+    sender: SyncSender<ActionWrapper>,
+    metrics: Arc<MetricsRegistry>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl ActionSender {
+    pub fn new(sender: SyncSender<ActionWrapper>, metrics: Arc<MetricsRegistry>) -> Self {
+        ActionSender {
+            sender,
+            metrics,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Puts the action into the queue, blocking while the queue is full.
+    /// Fails without queueing if the action loop has been stopped.
+    pub fn send(&self, action_wrapper: ActionWrapper) -> Result<(), SendError<ActionWrapper>> {
+        if self.is_stopped() {
+            return Err(SendError(action_wrapper));
+        }
+        // count before sending, the action loop could take it out of the queue right away
+        self.metrics.action_enqueued();
+        let result = match self.sender.try_send(action_wrapper) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(action_wrapper)) => {
+                self.metrics.action_queue_full();
+                self.sender.send(action_wrapper)
+            }
+            Err(TrySendError::Disconnected(action_wrapper)) => Err(SendError(action_wrapper)),
+        };
+        if result.is_err() {
+            // nobody receives on this queue anymore, or never did
+            self.stop();
+            self.metrics.action_dequeued();
+        }
+        result
+    }
+
+    /// True once the action loop has been stopped, actions sent from then on get refused.
+    /// Futures check this so they fail instead of waiting for a response that never comes.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Handle on the thread running the action loop
+struct ActionLoop {
+    stop: Arc<AtomicBool>,
+    finished: Receiver<()>,
+    thread: JoinHandle<()>,
 }
 
 type ClosureType = Box<FnMut(&State) -> bool + Send>;
@@ -29,7 +104,12 @@ pub struct Observer {
     pub sensor: ClosureType,
 }
 
-pub static DISPATCH_WITHOUT_CHANNELS: &str = "dispatch called without channels open";
+pub static ACTION_LOOP_STOPPED: &str = "action loop is not running";
+
+/// Error of dispatching to, or waiting on, an action loop that has been stopped
+pub fn action_loop_stopped() -> HolochainError {
+    HolochainError::new(ACTION_LOOP_STOPPED)
+}
 
 impl Instance {
     pub fn default_channel_buffer_size() -> usize {
@@ -37,7 +117,7 @@ impl Instance {
     }
 
     /// get a clone of the action channel
-    pub fn action_channel(&self) -> SyncSender<ActionWrapper> {
+    pub fn action_channel(&self) -> ActionSender {
         self.action_channel.clone()
     }
 
@@ -48,28 +128,26 @@ impl Instance {
 
     /// Stack an Action in the Event Queue
     ///
-    /// # Panics
-    ///
-    /// Panics if called before `start_action_loop`.
-    pub fn dispatch(&mut self, action_wrapper: ActionWrapper) {
+    /// Fails if called before `start_action_loop` or after `stop_action_loop`.
+    pub fn dispatch(&mut self, action_wrapper: ActionWrapper) -> HcResult<()> {
         dispatch_action(&self.action_channel, action_wrapper)
     }
 
     /// Stack an Action in the Event Queue and block until is has been processed.
     ///
-    /// # Panics
-    ///
-    /// Panics if called before `start_action_loop`.
-    pub fn dispatch_and_wait(&mut self, action_wrapper: ActionWrapper) {
-        dispatch_action_and_wait(&self.action_channel, &self.observer_channel, action_wrapper);
+    /// Fails if called before `start_action_loop` or after `stop_action_loop`.
+    pub fn dispatch_and_wait(&mut self, action_wrapper: ActionWrapper) -> HcResult<()> {
+        dispatch_action_and_wait(&self.action_channel, &self.observer_channel, action_wrapper)
     }
 
     /// Stack an action in the Event Queue and create an Observer on it with the specified closure
     ///
-    /// # Panics
-    ///
-    /// Panics if called before `start_action_loop`.
-    pub fn dispatch_with_observer<F>(&mut self, action_wrapper: ActionWrapper, closure: F)
+    /// Fails if called before `start_action_loop` or after `stop_action_loop`.
+    pub fn dispatch_with_observer<F>(
+        &mut self,
+        action_wrapper: ActionWrapper,
+        closure: F,
+    ) -> HcResult<()>
     where
         F: 'static + FnMut(&State) -> bool + Send,
     {
@@ -82,12 +160,19 @@ impl Instance {
     }
 
     /// Returns recievers for actions and observers that get added to this instance
-    fn initialize_channels(&mut self) -> (Receiver<ActionWrapper>, Receiver<Observer>) {
+    /// The action queue gets metered by the metrics registry of the given context.
+    fn initialize_channels(
+        &mut self,
+        context: &Arc<Context>,
+    ) -> (Receiver<ActionWrapper>, Receiver<Observer>) {
         let (tx_action, rx_action) =
             sync_channel::<ActionWrapper>(Self::default_channel_buffer_size());
         let (tx_observer, rx_observer) =
             sync_channel::<Observer>(Self::default_channel_buffer_size());
-        self.action_channel = tx_action.clone();
+        context
+            .metrics
+            .set_action_queue_capacity(Self::default_channel_buffer_size());
+        self.action_channel = ActionSender::new(tx_action, context.metrics.clone());
         self.observer_channel = tx_observer.clone();
 
         (rx_action, rx_observer)
@@ -103,24 +188,118 @@ impl Instance {
 
     /// Start the Event Loop on a seperate thread
     pub fn start_action_loop(&mut self, context: Arc<Context>) {
-        let (rx_action, rx_observer) = self.initialize_channels();
+        let (rx_action, rx_observer) = self.initialize_channels(&context);
 
         let sync_self = self.clone();
         let sub_context = self.initialize_context(context);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_requested = stop.clone();
+        let (tx_finished, rx_finished) = sync_channel(1);
 
-        thread::spawn(move || {
+        let thread = thread::spawn(move || {
             let mut state_observers: Vec<Observer> = Vec::new();
-            for action_wrapper in rx_action {
-                state_observers = sync_self.process_action(
-                    action_wrapper,
-                    state_observers,
-                    &rx_observer,
-                    &sub_context,
-                );
+            loop {
+                match rx_action.recv_timeout(ACTION_LOOP_TICK_MS) {
+                    Ok(action_wrapper) => {
+                        sub_context.metrics.action_dequeued();
+                        state_observers = sync_self.process_action(
+                            action_wrapper,
+                            state_observers,
+                            &rx_observer,
+                            &sub_context,
+                        );
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+                if stop_requested.load(Ordering::SeqCst) {
+                    // Drain the queue so nobody keeps waiting for an action that got lost
+                    for action_wrapper in rx_action.try_iter() {
+                        sub_context.metrics.action_dequeued();
+                        state_observers = sync_self.process_action(
+                            action_wrapper,
+                            state_observers,
+                            &rx_observer,
+                            &sub_context,
+                        );
+                    }
+                    break;
+                }
             }
+            // nobody listens anymore if stopping timed out
+            let _ = tx_finished.send(());
+        });
+
+        *self.action_loop() = Some(ActionLoop {
+            stop,
+            finished: rx_finished,
+            thread,
         });
     }
 
+    /// Stop the Event Loop and join its thread.
+    /// Waits for running zome calls, validations etc. (see Context::start_task()) to finish,
+    /// processes the actions that are still queued and hands the final state to the persister.
+    /// Returns a HolochainError::Timeout if that takes longer than the given timeout.
+    /// If tasks were still running the action loop is left running so they can finish.
+    pub fn stop_action_loop(&mut self, context: &Arc<Context>, timeout: Duration) -> HcResult<()> {
+        let deadline = Instant::now() + timeout;
+        let action_loop = match self.action_loop().take() {
+            Some(action_loop) => action_loop,
+            None => return Ok(()),
+        };
+
+        // Tasks need the action loop in order to finish, so stop it only after they are done
+        if !context.tasks.wait_until_idle(deadline) {
+            let running = context.tasks.count();
+            *self.action_loop() = Some(action_loop);
+            return Err(HolochainError::Timeout(format!(
+                "{} zome call or validation task(s) still running",
+                running
+            )));
+        }
+
+        action_loop.stop.store(true, Ordering::SeqCst);
+        let now = Instant::now();
+        let remaining = if deadline > now {
+            deadline - now
+        } else {
+            Duration::from_millis(0)
+        };
+        if action_loop.finished.recv_timeout(remaining).is_err() {
+            return Err(HolochainError::Timeout(
+                "action loop did not process the queued actions in time".to_string(),
+            ));
+        }
+        action_loop
+            .thread
+            .join()
+            .map_err(|_| HolochainError::new("action loop thread panicked"))?;
+        // refuse further actions and let pending futures find out that no response will come
+        self.action_channel.stop();
+        context.waker_registry.wake_all();
+
+        flush_journal(context)?;
+
+        // Flush the final state
+        let mut persister = context
+            .persister
+            .lock()
+            .map_err(|_| HolochainError::new("persister lock is poisoned"))?;
+        persister.save(&self.state())
+    }
+
+    /// true if the Event Loop has been started and not stopped since
+    pub fn action_loop_running(&self) -> bool {
+        self.action_loop().is_some()
+    }
+
+    fn action_loop(&self) -> MutexGuard<Option<ActionLoop>> {
+        self.action_loop
+            .lock()
+            .expect("owners of the action loop Mutex shouldn't panic")
+    }
+
     /// Calls the reducers for an action and calls the observers with the new state
     /// returns the new vector of observers
     pub(crate) fn process_action(
@@ -130,6 +309,11 @@ impl Instance {
         rx_observer: &Receiver<Observer>,
         context: &Arc<Context>,
     ) -> Vec<Observer> {
+        // Remember which future waits for this action before the reducers consume it
+        let mut maybe_response_key = response_key(&action_wrapper);
+        let vetoed: bool;
+        let snapshot_changed: bool;
+
         // Mutate state
         {
             let new_state: State;
@@ -141,8 +325,40 @@ impl Instance {
                     .read()
                     .expect("owners of the state RwLock shouldn't panic");
 
-                // Create new state by reducing the action on old state
-                new_state = state.reduce(context.clone(), action_wrapper);
+                // Middleware can veto the action before it touches the state
+                match middleware::before_reduce(context, &action_wrapper, &state) {
+                    Err(reason) => {
+                        let _ = context.log(&format!(
+                            "debug/instance: action {:?} vetoed by middleware: {}",
+                            action_wrapper.action(),
+                            reason
+                        ));
+                        // Whoever waits for the action gets the reason as its result
+                        vetoed = true;
+                        maybe_response_key = waiting_key(&action_wrapper);
+                        new_state = state.reduce_veto(context, &action_wrapper, &reason);
+                    }
+                    Ok(()) => {
+                        vetoed = false;
+                        // Create new state by reducing the action on old state
+                        new_state = state.reduce(context.clone(), action_wrapper.clone());
+                    }
+                }
+                snapshot_changed = new_state.snapshot_differs_from(&state);
+            }
+
+            if !vetoed {
+                context.metrics.record_action(action_wrapper.action());
+
+                // Record the action so the history of this instance can be replayed
+                record_action(context, &action_wrapper);
+
+                // Persist the new state before anybody can see it,
+                // so the instance can be loaded again after a restart.
+                // Most actions don't touch the parts that get persisted.
+                if snapshot_changed {
+                    persist_state(context, &new_state);
+                }
             }
 
             // Get write lock
@@ -155,6 +371,19 @@ impl Instance {
             *state = new_state;
         }
 
+        // Wake the future waiting for this action now that it can see the result
+        if let Some(key) = maybe_response_key {
+            context.waker_registry.wake(&key);
+        }
+
+        // Let listeners know what the action caused
+        if !vetoed && !context.signal_registry.is_empty() {
+            let maybe_signal = system_signal(&action_wrapper, &self.state());
+            if let Some(signal) = maybe_signal {
+                context.signal_registry.emit(&signal);
+            }
+        }
+
         // Add new observers
         state_observers.extend(rx_observer.try_iter());
 
@@ -173,17 +402,42 @@ impl Instance {
                 }
             }
         }
+
+        // Let middleware look at the result and process its follow-up actions right away
+        if vetoed {
+            return state_observers;
+        }
+        let follow_ups = {
+            let state = self
+                .state
+                .read()
+                .expect("owners of the state RwLock shouldn't panic");
+            middleware::after_reduce(context, &action_wrapper, &state)
+        };
+        for follow_up in follow_ups {
+            state_observers = self.process_action(follow_up, state_observers, rx_observer, context);
+        }
         state_observers
     }
 
     /// Creates a new Instance with disconnected channels.
     pub fn new(context: Arc<Context>) -> Self {
+        Self::from_state(State::new(context))
+    }
+
+    /// Creates a new Instance with disconnected channels from an existing State,
+    /// e.g. one that was loaded by a Persister.
+    pub fn from_state(state: State) -> Self {
         let (tx_action, _) = sync_channel(1);
         let (tx_observer, _) = sync_channel(1);
+        let action_channel = ActionSender::new(tx_action, Arc::new(MetricsRegistry::new()));
+        // there is no action loop until start_action_loop
+        action_channel.stop();
         Instance {
-            state: Arc::new(RwLock::new(State::new(context))),
-            action_channel: tx_action,
+            state: Arc::new(RwLock::new(state)),
+            action_channel,
             observer_channel: tx_observer,
+            action_loop: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -194,6 +448,45 @@ impl Instance {
     }
 }
 
+/// Hands a state to the persister of the context.
+/// A failing persister must not bring down the action loop, so errors only get logged.
+fn persist_state(context: &Arc<Context>, state: &State) {
+    let result = match context.persister.lock() {
+        Ok(mut persister) => persister.save(state),
+        Err(_) => Err(HolochainError::new("persister lock is poisoned")),
+    };
+    if let Err(err) = result {
+        // ignore logging errors here, there is nothing left we could do about it
+        let _ = context.log(&format!("err/instance: could not persist state: {}", err));
+    }
+}
+
+/// Appends a reduced action to the journal of the context, if there is one.
+fn record_action(context: &Arc<Context>, action_wrapper: &ActionWrapper) {
+    let result = match context.journal {
+        None => return,
+        Some(ref journal) => match journal.lock() {
+            Ok(mut journal) => journal.append(action_wrapper),
+            Err(_) => Err(HolochainError::new("journal lock is poisoned")),
+        },
+    };
+    if let Err(err) = result {
+        // ignore logging errors here, there is nothing left we could do about it
+        let _ = context.log(&format!("err/instance: could not record action: {}", err));
+    }
+}
+
+/// Writes out the actions the journal of the context still buffers, if there is one.
+fn flush_journal(context: &Arc<Context>) -> HcResult<()> {
+    match context.journal {
+        None => Ok(()),
+        Some(ref journal) => journal
+            .lock()
+            .map_err(|_| HolochainError::new("journal lock is poisoned"))?
+            .flush(),
+    }
+}
+
 /*impl Default for Instance {
     fn default(context:Context) -> Self {
         Self::new(context)
@@ -202,14 +495,12 @@ impl Instance {
 
 /// Send Action to Instance's Event Queue and block until is has been processed.
 ///
-/// # Panics
-///
-/// Panics if the channels passed are disconnected.
+/// Fails if the action loop has been stopped, or stops before processing the action.
 pub fn dispatch_action_and_wait(
-    action_channel: &SyncSender<ActionWrapper>,
+    action_channel: &ActionSender,
     observer_channel: &SyncSender<Observer>,
     action_wrapper: ActionWrapper,
-) {
+) -> HcResult<()> {
     // Create blocking channel
     let (sender, receiver) = sync_channel::<()>(1);
 
@@ -228,44 +519,58 @@ pub fn dispatch_action_and_wait(
         }
     };
 
-    dispatch_action_with_observer(&action_channel, &observer_channel, action_wrapper, closure);
+    dispatch_action_with_observer(&action_channel, &observer_channel, action_wrapper, closure)?;
 
-    // Block until Observer has sensed the completion of the Action
-    receiver.recv().expect(DISPATCH_WITHOUT_CHANNELS);
+    // Block until Observer has sensed the completion of the Action,
+    // the observer gets dropped if the loop stops without processing it
+    receiver.recv().map_err(|_| action_loop_stopped())
 }
 
 /// Send Action to the Event Queue and create an Observer for it with the specified closure
 ///
-/// # Panics
-///
-/// Panics if the channels passed are disconnected.
+/// Fails if the action loop has been stopped.
 pub fn dispatch_action_with_observer<F>(
-    action_channel: &SyncSender<ActionWrapper>,
+    action_channel: &ActionSender,
     observer_channel: &SyncSender<Observer>,
     action_wrapper: ActionWrapper,
     closure: F,
-) where
+) -> HcResult<()>
+where
     F: 'static + FnMut(&State) -> bool + Send,
 {
+    if action_channel.is_stopped() {
+        return Err(action_loop_stopped());
+    }
     let observer = Observer {
         sensor: Box::new(closure),
     };
 
     observer_channel
         .send(observer)
-        .expect(DISPATCH_WITHOUT_CHANNELS);
-    dispatch_action(action_channel, action_wrapper);
+        .map_err(|_| action_loop_stopped())?;
+    dispatch_action(action_channel, action_wrapper)
 }
 
 /// Send Action to the Event Queue
 ///
-/// # Panics
-///
-/// Panics if the channels passed are disconnected.
-pub fn dispatch_action(action_channel: &SyncSender<ActionWrapper>, action_wrapper: ActionWrapper) {
+/// Fails if the action loop has been stopped.
+pub fn dispatch_action(
+    action_channel: &ActionSender,
+    action_wrapper: ActionWrapper,
+) -> HcResult<()> {
     action_channel
         .send(action_wrapper)
-        .expect(DISPATCH_WITHOUT_CHANNELS);
+        .map_err(|_| action_loop_stopped())
+}
+
+/// Send Action to the Event Queue of the context from a background task.
+/// Tasks can outlive an action loop that got stopped, so instead of panicking
+/// the action gets dropped with a log message if the channel is disconnected.
+pub fn dispatch_action_from_task(context: &Arc<Context>, action_wrapper: ActionWrapper) {
+    if context.action_channel.send(action_wrapper).is_err() {
+        // ignore logging errors here, there is nothing left we could do about it
+        let _ = context.log("err/instance: action dropped, the action loop has been stopped");
+    }
 }
 
 #[cfg(test)]
@@ -279,20 +584,27 @@ pub mod tests {
     use context::Context;
     use futures::executor::block_on;
     use holochain_agent::Agent;
-    use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
+    use holochain_cas_implementations::{
+        cas::{file::FilesystemStorage, memory::MemoryStorage},
+        eav::{file::EavFileStorage, memory::EavMemoryStorage},
+    };
     use holochain_core_types::{
-        cas::content::AddressableContent, entry::ToEntry, entry_type::EntryType,
+        cas::{content::AddressableContent, storage::ContentAddressableStorage},
+        entry::ToEntry,
+        entry_type::EntryType,
     };
     use holochain_dna::{zome::Zome, Dna};
     use logger::Logger;
     use nucleus::{
-        actions::initialize::initialize_application,
+        actions::{call_zome_function::call_zome_function, initialize::initialize_application},
         ribosome::{callback::Callback, Defn},
+        ZomeFnCall,
     };
-    use persister::SimplePersister;
+    use persister::{Persister, SimplePersister};
     use state::State;
     use std::{
         sync::{
+            atomic::AtomicUsize,
             mpsc::{channel, sync_channel},
             Arc, Mutex,
         },
@@ -366,6 +678,19 @@ pub mod tests {
         )
     }
 
+    /// create a test context that keeps the source chain and the DHT in memory
+    pub fn test_memory_context(agent_name: &str) -> Arc<Context> {
+        Arc::new(
+            Context::new(
+                Agent::from(agent_name.to_owned()),
+                test_logger(),
+                Arc::new(Mutex::new(SimplePersister::new())),
+                MemoryStorage::new().unwrap(),
+                EavMemoryStorage::new().unwrap(),
+            ).unwrap(),
+        )
+    }
+
     pub fn test_context_with_state() -> Arc<Context> {
         let mut context = Context::new(
             Agent::from("Florence".to_string()),
@@ -532,20 +857,21 @@ pub mod tests {
 
         let dna = Dna::new();
         let (sender, receiver) = sync_channel(1);
-        instance.dispatch_with_observer(
-            ActionWrapper::new(Action::InitApplication(dna.clone())),
-            move |state: &State| match state.nucleus().dna() {
-                Some(dna) => {
-                    sender
-                        .send(dna)
-                        // the channel stays connected until the first message has been sent
-                        // if this fails that means that it was called after having returned done=true
-                        .expect("observer called after done");
-                    true
-                }
-                None => false,
-            },
-        );
+        instance
+            .dispatch_with_observer(
+                ActionWrapper::new(Action::InitApplication(dna.clone())),
+                move |state: &State| match state.nucleus().dna() {
+                    Some(dna) => {
+                        sender
+                            .send(dna)
+                            // the channel stays connected until the first message has been sent
+                            // if this fails that means that it was called after having returned done=true
+                            .expect("observer called after done");
+                        true
+                    }
+                    None => false,
+                },
+            ).expect("action loop should be running");
 
         let stored_dna = receiver.recv().expect("observer dropped before done");
 
@@ -573,7 +899,9 @@ pub mod tests {
             ::nucleus::state::NucleusStatus::New
         );
 
-        instance.dispatch_and_wait(action);
+        instance
+            .dispatch_and_wait(action)
+            .expect("action loop should be running");
         assert_eq!(instance.state().nucleus().dna(), Some(dna));
         assert_eq!(
             instance.state().nucleus().status(),
@@ -581,6 +909,139 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// tests that stopping processes queued actions, flushes the state and ends the loop
+    fn can_stop_action_loop() {
+        let context = test_context("jason");
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+        assert!(instance.action_loop_running());
+
+        let dna = Dna::new();
+        instance
+            .dispatch(ActionWrapper::new(Action::InitApplication(dna.clone())))
+            .expect("action loop should be running");
+        instance
+            .stop_action_loop(&context, Duration::from_secs(10))
+            .expect("action loop should stop");
+
+        assert!(!instance.action_loop_running());
+        // the queued action got processed before the loop stopped
+        assert_eq!(instance.state().nucleus().dna(), Some(dna.clone()));
+        // and the final state was handed to the persister
+        let persisted = context
+            .persister
+            .lock()
+            .unwrap()
+            .load(context.clone())
+            .unwrap()
+            .expect("state should have been persisted");
+        assert_eq!(persisted.nucleus().dna(), Some(dna));
+
+        // stopping a stopped loop is a no-op
+        assert!(instance
+            .stop_action_loop(&context, Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    /// tests that stopping gives up if running tasks don't finish in time
+    fn stop_action_loop_times_out() {
+        let context = test_context("jason");
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+
+        let task = context.start_task();
+        assert_eq!(
+            instance.stop_action_loop(&context, Duration::from_millis(10)),
+            Err(HolochainError::Timeout(
+                "1 zome call or validation task(s) still running".to_string()
+            )),
+        );
+        // the loop keeps running so the task can finish
+        assert!(instance.action_loop_running());
+
+        drop(task);
+        assert!(instance
+            .stop_action_loop(&context, Duration::from_secs(10))
+            .is_ok());
+        assert!(!instance.action_loop_running());
+    }
+
+    /// persister that only counts how often it was asked to save
+    struct CountingPersister {
+        saves: Arc<AtomicUsize>,
+    }
+
+    impl Persister for CountingPersister {
+        fn save(&mut self, _state: &State) -> Result<(), HolochainError> {
+            self.saves.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+        fn load(&self, _context: Arc<Context>) -> Result<Option<State>, HolochainError> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    /// tests that the state only gets persisted if the parts that survive a restart changed
+    fn persists_changed_snapshots_only() {
+        let saves = Arc::new(AtomicUsize::new(0));
+        let mut context = (*test_context("jason")).clone();
+        context.persister = Arc::new(Mutex::new(CountingPersister {
+            saves: saves.clone(),
+        }));
+        let context = Arc::new(context);
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context);
+
+        instance
+            .dispatch_and_wait(ActionWrapper::new(Action::InitApplication(Dna::new())))
+            .expect("action loop should be running");
+        assert_eq!(saves.load(Ordering::SeqCst), 1);
+
+        // looking up an entry doesn't change what gets persisted
+        instance
+            .dispatch_and_wait(test_action_wrapper_get())
+            .expect("action loop should be running");
+        assert_eq!(saves.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    /// tests that dispatching to a stopped action loop fails instead of panicking
+    /// and that futures waiting on it resolve to an error instead of hanging
+    fn dispatch_after_stop_fails() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (mut instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+        instance
+            .stop_action_loop(&context, Duration::from_secs(10))
+            .expect("action loop should stop");
+        assert!(context.action_channel.is_stopped());
+
+        let action = ActionWrapper::new(Action::InitApplication(Dna::new()));
+        assert_eq!(
+            instance.dispatch(action.clone()),
+            Err(action_loop_stopped())
+        );
+        assert_eq!(
+            instance.dispatch_and_wait(action.clone()),
+            Err(action_loop_stopped())
+        );
+        assert_eq!(
+            instance.dispatch_with_observer(action, |_| true),
+            Err(action_loop_stopped())
+        );
+
+        let call = ZomeFnCall::new("test_zome", "test_cap", "main", "");
+        assert_eq!(
+            block_on(call_zome_function(call, &context)),
+            Err(action_loop_stopped())
+        );
+    }
+
     #[test]
     /// tests that an unimplemented genesis allows the nucleus to initialize
     /// @TODO is this right? should return unimplemented?
@@ -714,4 +1175,33 @@ pub mod tests {
                 _ => false,
             });
     }
+
+    /// Instances can run on storages that only live in memory
+    #[test]
+    fn can_commit_in_memory() {
+        let context = test_memory_context("alex");
+        let agent_entry = context.agent.to_entry();
+        let commit_agent_action = ActionWrapper::new(Action::Commit(agent_entry.clone()));
+
+        let instance = Instance::new(context.clone());
+        let (_, rx_observer) = channel::<Observer>();
+        instance.process_action(commit_agent_action, Vec::new(), &rx_observer, &context);
+
+        let state = instance.state();
+        let top_header = state
+            .agent()
+            .top_chain_header()
+            .expect("commit should have added a header");
+        assert_eq!(top_header.entry_address(), &agent_entry.address());
+        assert!(state
+            .agent()
+            .chain()
+            .content_storage()
+            .contains(&agent_entry.address())
+            .unwrap());
+        assert!(context
+            .content_storage
+            .contains(&agent_entry.address())
+            .unwrap());
+    }
 }
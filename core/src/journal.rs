@@ -0,0 +1,290 @@
+//! Append-only journal of the actions an instance reduced, and replay of such a journal
+//! to reproduce the state of an instance at any point of its history (time travel debugging).
+
+use action::ActionWrapper;
+use context::Context;
+use holochain_core_types::error::HolochainError;
+use serde_json;
+use state::State;
+use std::{
+    fs::{File, OpenOptions},
+    io::{prelude::*, BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// trait that defines where the action loop records the actions it reduced
+pub trait Journal: Send {
+    /// appends an action that just got reduced
+    fn append(&mut self, action_wrapper: &ActionWrapper) -> Result<(), HolochainError>;
+    /// all recorded actions in the order they were reduced
+    fn actions(&self) -> Result<Vec<ActionWrapper>, HolochainError>;
+    /// makes sure the appended actions are stored durably, called when the action loop stops
+    fn flush(&mut self) -> Result<(), HolochainError> {
+        Ok(())
+    }
+}
+
+/// Journal that keeps the actions in memory, e.g. for tests.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryJournal {
+    actions: Vec<ActionWrapper>,
+}
+
+impl MemoryJournal {
+    pub fn new() -> Self {
+        MemoryJournal::default()
+    }
+}
+
+impl Journal for MemoryJournal {
+    fn append(&mut self, action_wrapper: &ActionWrapper) -> Result<(), HolochainError> {
+        self.actions.push(action_wrapper.clone());
+        Ok(())
+    }
+
+    fn actions(&self) -> Result<Vec<ActionWrapper>, HolochainError> {
+        Ok(self.actions.clone())
+    }
+}
+
+/// Journal that appends one json line per action to a file in the given directory.
+/// The file can be copied off a user's machine and replayed locally.
+/// The file stays open while actions get appended. Every action gets written to it right away,
+/// so the journal survives a crash of the instance, but it only gets synced to disk
+/// when the journal is flushed, so recording doesn't cost a disk sync per reduced action.
+#[derive(Debug)]
+pub struct FileJournal {
+    file_path: PathBuf,
+    file: Option<File>,
+}
+
+impl FileJournal {
+    pub fn new<P: AsRef<Path>>(dir_path: P) -> Self {
+        FileJournal {
+            file_path: dir_path.as_ref().join(Self::file_name()),
+            file: None,
+        }
+    }
+
+    pub fn file_name() -> &'static str {
+        "journal.jsonl"
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+}
+
+impl Journal for FileJournal {
+    fn append(&mut self, action_wrapper: &ActionWrapper) -> Result<(), HolochainError> {
+        let mut line = serde_json::to_string(action_wrapper)?;
+        line.push('\n');
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.file_path)?;
+            self.file = Some(file);
+        }
+        // a single write per line, so a crash can at most leave the last line truncated
+        self.file
+            .as_mut()
+            .expect("file was opened above")
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn actions(&self) -> Result<Vec<ActionWrapper>, HolochainError> {
+        let file = match File::open(&self.file_path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+        let lines = BufReader::new(file)
+            .lines()
+            .collect::<Result<Vec<String>, _>>()?;
+        let mut actions = Vec::with_capacity(lines.len());
+        for (index, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(action_wrapper) => actions.push(action_wrapper),
+                // the instance crashed while appending the last action, so it never got reduced
+                Err(_) if index + 1 == lines.len() => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(actions)
+    }
+
+    fn flush(&mut self) -> Result<(), HolochainError> {
+        if let Some(ref file) = self.file {
+            file.sync_data()?;
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds the state of an instance by reducing the recorded actions on a fresh state.
+/// The given context should come with fresh CAS and EAV storages that the reducers fill again.
+/// If `until` is given, replay stops after the action with that index,
+/// so the state can be inspected at that point of the history.
+/// Zome calls and other side effects of reducers are not run again, since the journal
+/// already holds the actions they dispatched.
+pub fn replay(context: Arc<Context>, actions: &[ActionWrapper], until: Option<usize>) -> State {
+    let mut replay_context = (*context).clone();
+    replay_context.replaying = true;
+    // neither record the replay nor let middleware interfere with it
+    replay_context.journal = None;
+    replay_context.middleware.clear();
+
+    let state = Arc::new(RwLock::new(State::new(Arc::new(replay_context.clone()))));
+    replay_context.set_state(state.clone());
+    let replay_context = Arc::new(replay_context);
+
+    let count = until
+        .map(|index| index + 1)
+        .unwrap_or_else(|| actions.len());
+    let mut replayed_state = State::new(replay_context.clone());
+    for action_wrapper in actions.iter().take(count) {
+        // reducers can read the state from the context while reducing, just like in the action loop
+        replayed_state = state
+            .read()
+            .expect("owners of the state RwLock shouldn't panic")
+            .reduce(replay_context.clone(), action_wrapper.clone());
+        *state
+            .write()
+            .expect("owners of the state RwLock shouldn't panic") = replayed_state.clone();
+    }
+    replayed_state
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use super::*;
+    use action::{tests::test_action_wrapper_get, Action};
+    use holochain_core_types::{
+        cas::{content::AddressableContent, storage::ContentAddressableStorage},
+        entry::{test_entry_a, test_entry_b},
+    };
+    use instance::{tests::test_context, Instance, Observer};
+    use std::{
+        sync::{mpsc::channel, Mutex},
+        time::Duration,
+    };
+
+    fn test_context_with_journal(journal: Arc<Mutex<Journal>>) -> Arc<Context> {
+        let mut context = (*test_context("jason")).clone();
+        context.journal = Some(journal);
+        Arc::new(context)
+    }
+
+    /// commits test_entry_a() and test_entry_b() on an instance with a journal
+    fn test_journal() -> (Instance, Arc<Mutex<MemoryJournal>>) {
+        let journal = Arc::new(Mutex::new(MemoryJournal::new()));
+        let context = test_context_with_journal(journal.clone());
+        let instance = Instance::new(context.clone());
+        let (_, rx_observer) = channel::<Observer>();
+        for entry in vec![test_entry_a(), test_entry_b()] {
+            let commit = ActionWrapper::new(Action::Commit(entry));
+            instance.process_action(commit, Vec::new(), &rx_observer, &context);
+        }
+        (instance, journal)
+    }
+
+    #[test]
+    /// test that the action loop records every reduced action in order
+    fn journal_records_reduced_actions() {
+        let (instance, journal) = test_journal();
+
+        let actions = journal.lock().unwrap().actions().unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(
+            instance.state().history.iter().cloned().collect::<Vec<_>>(),
+            actions
+        );
+    }
+
+    #[test]
+    /// test that a file journal holds every appended action right away
+    /// and ignores a truncated last line
+    fn file_journal_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut journal = FileJournal::new(dir.path());
+        assert_eq!(journal.actions(), Ok(Vec::new()));
+
+        let actions = vec![test_action_wrapper_get(), test_action_wrapper_get()];
+        for action_wrapper in actions.iter() {
+            journal.append(action_wrapper).unwrap();
+        }
+        OpenOptions::new()
+            .append(true)
+            .open(journal.file_path())
+            .unwrap()
+            .write_all(b"{\"action\":")
+            .unwrap();
+
+        assert_eq!(FileJournal::new(dir.path()).actions(), Ok(actions));
+    }
+
+    #[test]
+    /// test that a file journal holds the history once the action loop stopped
+    fn file_journal_flushed_on_stop() {
+        let dir = tempdir().unwrap();
+        let journal = Arc::new(Mutex::new(FileJournal::new(dir.path())));
+        let context = test_context_with_journal(journal.clone());
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+
+        instance
+            .dispatch_and_wait(ActionWrapper::new(Action::Commit(test_entry_a())))
+            .unwrap();
+        instance
+            .stop_action_loop(&context, Duration::from_secs(10))
+            .unwrap();
+
+        let actions = FileJournal::new(dir.path()).actions().unwrap();
+        assert_eq!(
+            instance.state().history.iter().cloned().collect::<Vec<_>>(),
+            actions
+        );
+    }
+
+    #[test]
+    /// test that replaying a journal against fresh storages reproduces the state
+    fn replay_rebuilds_state() {
+        let (instance, journal) = test_journal();
+        let actions = journal.lock().unwrap().actions().unwrap();
+
+        let replayed = replay(test_context("jason"), &actions, None);
+        assert_eq!(
+            replayed.agent().top_chain_header(),
+            instance.state().agent().top_chain_header()
+        );
+        assert_eq!(
+            replayed.history.iter().cloned().collect::<Vec<_>>(),
+            actions
+        );
+        assert!(replayed
+            .agent()
+            .chain()
+            .content_storage()
+            .contains(&test_entry_b().address())
+            .unwrap());
+    }
+
+    #[test]
+    /// test that replay can stop at any action to inspect the state at that point
+    fn replay_until_index() {
+        let (_, journal) = test_journal();
+        let actions = journal.lock().unwrap().actions().unwrap();
+
+        let replayed = replay(test_context("jason"), &actions, Some(0));
+        assert_eq!(replayed.history.len(), 1);
+        let content_storage = replayed.agent().chain().content_storage();
+        assert!(content_storage.contains(&test_entry_a().address()).unwrap());
+        assert!(!content_storage.contains(&test_entry_b().address()).unwrap());
+    }
+}
@@ -4,12 +4,12 @@
 extern crate serde_derive;
 extern crate chrono;
 extern crate multihash;
+extern crate parity_wasm;
 extern crate rust_base58;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
 extern crate futures;
-extern crate snowflake;
 #[cfg(test)]
 extern crate test_utils;
 extern crate wasmi;
@@ -19,6 +19,7 @@ extern crate unwrap_to;
 extern crate num_derive;
 extern crate num_traits;
 extern crate regex;
+extern crate uuid;
 
 extern crate config;
 extern crate holochain_agent;
@@ -33,10 +34,17 @@ pub mod action;
 pub mod agent;
 pub mod context;
 pub mod dht;
+pub mod history;
 pub mod instance;
+pub mod journal;
 #[cfg(test)]
 pub mod link_tests;
 pub mod logger;
+pub mod metrics;
+pub mod middleware;
 pub mod nucleus;
 pub mod persister;
+pub mod signal;
 pub mod state;
+pub mod tasks;
+pub mod waker_registry;
@@ -1,14 +1,35 @@
 //! This logger is the logger that's attached to each Holochain application
 //! which is separate from standard logging via the log crate warn! info! debug! logging that
 //! gets emitted globaly from the container.
+//!
+//! Besides plain messages, loggers receive structured LogRecords with a level, a target naming
+//! the part of core (or the zome) that logged it and fields identifying the instance, zome,
+//! function and action. The StructuredLogger filters them per target and hands them to sinks.
 
 use chrono::Local;
+use holochain_core_types::error::HolochainError;
+use serde_json;
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{prelude::*, ErrorKind},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 /// trait that defines the logging functionality that holochain_core requires
 pub trait Logger: Send {
     // Add log message to logger
     fn log(&mut self, msg: String);
 
+    // Add structured log record to logger.
+    // Loggers that don't know about records only get the message.
+    fn log_record(&mut self, record: &LogRecord) {
+        self.log(record.message.clone());
+    }
+
     // Dump all held logs
     fn dump(&self) -> String {
         String::new()
@@ -27,7 +48,578 @@ impl Logger for SimpleLogger {
         let date = Local::now();
         println!("{}:{}", date.format("%Y-%m-%d %H:%M:%S"), msg);
     }
+    fn log_record(&mut self, record: &LogRecord) {
+        println!("{}", record);
+    }
     // fn new() -> SimpleLogger {
     //      SimpleLogger {}
     // }
 }
+
+/// Severity of a log record, from the most to the least severe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = HolochainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "err" | "error" => Ok(LogLevel::Error),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(HolochainError::ErrorGeneric(format!(
+                "Unknown log level '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Identifies where a log record comes from. Unset fields are left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LogFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zome: Option<String>,
+    #[serde(default, rename = "fn", skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_id: Option<String>,
+}
+
+impl fmt::Display for LogFields {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields: Vec<String> = vec![
+            ("instance", &self.instance),
+            ("zome", &self.zome),
+            ("fn", &self.function),
+            ("action", &self.action_id),
+        ].into_iter()
+        .filter_map(|(name, value)| value.as_ref().map(|value| format!("{}={}", name, value)))
+        .collect();
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+/// A single log message together with its level, target and fields.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// local time in RFC 3339 format
+    pub timestamp: String,
+    pub level: LogLevel,
+    /// the part of core that logged the record, e.g. "dht" or "nucleus::ribosome"
+    pub target: String,
+    pub message: String,
+    pub fields: LogFields,
+}
+
+impl LogRecord {
+    pub fn new<T: Into<String>, M: Into<String>>(level: LogLevel, target: T, message: M) -> Self {
+        LogRecord {
+            timestamp: Local::now().to_rfc3339(),
+            level,
+            target: target.into(),
+            message: message.into(),
+            fields: LogFields::default(),
+        }
+    }
+
+    /// Turns a plain log message into a record.
+    /// Messages following the "level/target: message" convention, e.g.
+    /// "err/instance: could not persist state", get that level and target,
+    /// all others are logged at info level with the target "holochain".
+    pub fn from_message(msg: &str) -> Self {
+        let parsed = msg.find(": ").and_then(|colon| {
+            let (prefix, message) = (&msg[..colon], &msg[colon + 2..]);
+            let slash = prefix.find('/')?;
+            let level = prefix[..slash].parse().ok()?;
+            let target = &prefix[slash + 1..];
+            if target.is_empty() || target.contains(' ') {
+                return None;
+            }
+            Some(LogRecord::new(level, target, message))
+        });
+        parsed.unwrap_or_else(|| LogRecord::new(LogLevel::Info, "holochain", msg))
+    }
+
+    pub fn instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.fields.instance = Some(instance.into());
+        self
+    }
+
+    pub fn zome<S: Into<String>>(mut self, zome: S) -> Self {
+        self.fields.zome = Some(zome.into());
+        self
+    }
+
+    pub fn function<S: Into<String>>(mut self, function: S) -> Self {
+        self.fields.function = Some(function.into());
+        self
+    }
+
+    pub fn action_id<S: ToString>(mut self, action_id: &S) -> Self {
+        self.fields.action_id = Some(action_id.to_string());
+        self
+    }
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fields = self.fields.to_string();
+        if fields.is_empty() {
+            write!(
+                f,
+                "{} {} {}: {}",
+                self.timestamp, self.level, self.target, self.message
+            )
+        } else {
+            write!(
+                f,
+                "{} {} {} [{}]: {}",
+                self.timestamp, self.level, self.target, fields, self.message
+            )
+        }
+    }
+}
+
+/// Decides which records get logged: the most specific target rule that matches the target
+/// of a record sets the least severe level that still gets through, the default level
+/// applies to all other targets.
+/// A rule for "nucleus" also matches "nucleus::ribosome", but not "nucleus_ext".
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    default_level: LogLevel,
+    targets: Vec<(String, LogLevel)>,
+}
+
+impl LogFilter {
+    pub fn new(default_level: LogLevel) -> Self {
+        LogFilter {
+            default_level,
+            targets: Vec::new(),
+        }
+    }
+
+    /// sets the level for a target and everything below it
+    pub fn target<S: Into<String>>(mut self, target: S, level: LogLevel) -> Self {
+        let target = target.into();
+        self.targets.retain(|(existing, _)| *existing != target);
+        self.targets.push((target, level));
+        self
+    }
+
+    pub fn enabled(&self, target: &str, level: LogLevel) -> bool {
+        let max_level = self
+            .targets
+            .iter()
+            .filter(|(rule, _)| {
+                target == rule.as_str()
+                    || (target.starts_with(rule.as_str()) && target[rule.len()..].starts_with("::"))
+            }).max_by_key(|(rule, _)| rule.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default_level);
+        level <= max_level
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter::new(LogLevel::Info)
+    }
+}
+
+/// Parses filters like "info,dht=debug,nucleus::ribosome=trace".
+/// A bare level sets the default level.
+impl FromStr for LogFilter {
+    type Err = HolochainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        for directive in s.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.find('=') {
+                None => filter.default_level = directive.parse()?,
+                Some(equals) => {
+                    let level = directive[equals + 1..].trim().parse()?;
+                    filter = filter.target(directive[..equals].trim(), level);
+                }
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// trait that defines where a StructuredLogger writes the records that pass its filter
+pub trait LogSink: Send {
+    fn write(&mut self, record: &LogRecord) -> Result<(), HolochainError>;
+}
+
+/// Logger that filters records per target and writes them to any number of sinks.
+/// Plain messages get turned into records with LogRecord::from_message().
+pub struct StructuredLogger {
+    instance: Option<String>,
+    filter: LogFilter,
+    sinks: Vec<Box<LogSink>>,
+}
+
+impl StructuredLogger {
+    pub fn new(filter: LogFilter) -> Self {
+        StructuredLogger {
+            instance: None,
+            filter,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// names the instance this logger is attached to in all records that don't name one
+    pub fn with_instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    pub fn with_sink<S: LogSink + 'static>(mut self, sink: S) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn filter(&self) -> &LogFilter {
+        &self.filter
+    }
+}
+
+impl Logger for StructuredLogger {
+    fn log(&mut self, msg: String) {
+        self.log_record(&LogRecord::from_message(&msg));
+    }
+
+    fn log_record(&mut self, record: &LogRecord) {
+        if !self.filter.enabled(&record.target, record.level) {
+            return;
+        }
+        let mut record = record.clone();
+        if record.fields.instance.is_none() {
+            record.fields.instance = self.instance.clone();
+        }
+        for sink in self.sinks.iter_mut() {
+            // there is nowhere left to log a failing sink to
+            if let Err(err) = sink.write(&record) {
+                eprintln!("could not write log record: {}", err);
+            }
+        }
+    }
+}
+
+/// Sink that writes one json object per line, e.g. to a file or to stdout.
+pub struct JsonLinesSink<W: Write + Send> {
+    writer: W,
+}
+
+impl<W: Write + Send> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesSink { writer }
+    }
+}
+
+impl JsonLinesSink<File> {
+    /// appends to the file at the given path
+    pub fn file<P: AsRef<Path>>(path: P) -> Result<Self, HolochainError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JsonLinesSink::new(file))
+    }
+}
+
+impl<W: Write + Send> LogSink for JsonLinesSink<W> {
+    fn write(&mut self, record: &LogRecord) -> Result<(), HolochainError> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Sink that writes text lines to a file and rotates it once it would grow beyond max_bytes:
+/// "holochain.log" becomes "holochain.log.1", "holochain.log.1" becomes "holochain.log.2" and
+/// so on, keeping at most max_files rotated files.
+pub struct RotatingFileSink {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFileSink {
+    pub fn new<P: AsRef<Path>>(path: P, max_bytes: u64, max_files: usize) -> Self {
+        RotatingFileSink {
+            path: path.as_ref().to_path_buf(),
+            max_bytes,
+            max_files,
+            file: None,
+            written: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// path of the rotated file with the given index, starting at 1
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(format!(".{}", index));
+        self.path.with_file_name(file_name)
+    }
+
+    fn rotate(&mut self) -> Result<(), HolochainError> {
+        self.file = None;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..self.max_files).rev() {
+                match fs::rename(self.rotated_path(index), self.rotated_path(index + 1)) {
+                    Err(ref err) if err.kind() == ErrorKind::NotFound => {}
+                    result => result?,
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+
+    fn open(&mut self) -> Result<&mut File, HolochainError> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.written = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().expect("file was opened above"))
+    }
+}
+
+impl LogSink for RotatingFileSink {
+    fn write(&mut self, record: &LogRecord) -> Result<(), HolochainError> {
+        let line = format!("{}\n", record);
+        self.open()?;
+        if self.written > 0 && self.written + line.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.open()?.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// Sink that keeps the latest records in memory, so they can be shown by a debugging UI
+/// or checked in tests. Clones share the same buffer.
+#[derive(Clone)]
+pub struct RingBufferSink {
+    capacity: usize,
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+}
+
+impl RingBufferSink {
+    pub fn new(capacity: usize) -> Self {
+        RingBufferSink {
+            capacity,
+            records: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    /// the buffered records, oldest first
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.records
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&mut self, record: &LogRecord) -> Result<(), HolochainError> {
+        let mut records = self
+            .records
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        if self.capacity > 0 {
+            records.push_back(record.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
+    use super::*;
+    use std::io::BufReader;
+
+    #[test]
+    /// test that levels order from the most to the least severe and parse the usual spellings
+    fn log_levels() {
+        assert!(LogLevel::Error < LogLevel::Warn);
+        assert!(LogLevel::Debug < LogLevel::Trace);
+        assert_eq!("err".parse::<LogLevel>(), Ok(LogLevel::Error));
+        assert_eq!("WARN".parse::<LogLevel>(), Ok(LogLevel::Warn));
+        assert!("loud".parse::<LogLevel>().is_err());
+        assert_eq!(LogLevel::Info.to_string(), "info");
+    }
+
+    #[test]
+    /// test that plain messages following the "level/target: message" convention get parsed
+    fn record_from_message() {
+        let record = LogRecord::from_message("err/instance: could not persist state: disk full");
+        assert_eq!(record.level, LogLevel::Error);
+        assert_eq!(record.target, "instance");
+        assert_eq!(record.message, "could not persist state: disk full");
+
+        let record = LogRecord::from_message("TestApp instantiated");
+        assert_eq!(record.level, LogLevel::Info);
+        assert_eq!(record.target, "holochain");
+        assert_eq!(record.message, "TestApp instantiated");
+
+        let record = LogRecord::from_message("Zome Function 'a/b' returned: Success");
+        assert_eq!(record.target, "holochain");
+    }
+
+    #[test]
+    /// test that the most specific target rule wins
+    fn filter_per_target() {
+        let filter: LogFilter = "warn,nucleus=debug,nucleus::ribosome=error"
+            .parse()
+            .unwrap();
+
+        assert!(filter.enabled("dht", LogLevel::Warn));
+        assert!(!filter.enabled("dht", LogLevel::Info));
+        assert!(filter.enabled("nucleus", LogLevel::Debug));
+        assert!(filter.enabled("nucleus::actions", LogLevel::Debug));
+        assert!(!filter.enabled("nucleus_ext", LogLevel::Debug));
+        assert!(!filter.enabled("nucleus::ribosome", LogLevel::Warn));
+        assert!(filter.enabled("nucleus::ribosome::api", LogLevel::Error));
+
+        assert!("info,dht=loud".parse::<LogFilter>().is_err());
+    }
+
+    #[test]
+    /// test that the structured logger filters records and fills in the instance
+    fn structured_logger_writes_to_sinks() {
+        let ring_buffer = RingBufferSink::new(10);
+        let mut logger = StructuredLogger::new("info,dht=warn".parse().unwrap())
+            .with_instance("test-instance")
+            .with_sink(ring_buffer.clone());
+
+        logger.log_record(&LogRecord::new(LogLevel::Info, "dht", "too chatty"));
+        logger.log_record(
+            &LogRecord::new(LogLevel::Warn, "dht", "entry already stored").action_id(&"action-1"),
+        );
+        logger.log("debug/instance: too chatty as well".to_string());
+        logger.log("err/instance: could not persist state".to_string());
+
+        let records = ring_buffer.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message, "entry already stored");
+        assert_eq!(
+            records[0].fields,
+            LogFields {
+                instance: Some("test-instance".to_string()),
+                action_id: Some("action-1".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(records[1].level, LogLevel::Error);
+        assert_eq!(records[1].target, "instance");
+    }
+
+    #[test]
+    /// test that the ring buffer only keeps the latest records
+    fn ring_buffer_drops_oldest() {
+        let mut ring_buffer = RingBufferSink::new(2);
+        for message in vec!["a", "b", "c"] {
+            ring_buffer
+                .write(&LogRecord::new(LogLevel::Info, "test", message))
+                .unwrap();
+        }
+        let messages: Vec<String> = ring_buffer
+            .records()
+            .into_iter()
+            .map(|record| record.message)
+            .collect();
+        assert_eq!(messages, vec!["b".to_string(), "c".to_string()]);
+    }
+
+    #[test]
+    /// test that json lines can be read back as records
+    fn json_lines_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("holochain.jsonl");
+        let record = LogRecord::new(LogLevel::Debug, "zome", "hello")
+            .zome("test_zome")
+            .function("main");
+        let mut sink = JsonLinesSink::file(&path).unwrap();
+        sink.write(&record).unwrap();
+        sink.write(&record).unwrap();
+
+        let lines: Vec<String> = BufReader::new(File::open(&path).unwrap())
+            .lines()
+            .map(|line| line.unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains(r#""fields":{"zome":"test_zome","fn":"main"}"#));
+        assert_eq!(
+            serde_json::from_str::<LogRecord>(&lines[1]).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    /// test that the file gets rotated once it's full and old files get dropped
+    fn rotating_file() {
+        let dir = tempdir().unwrap();
+        let mut sink = RotatingFileSink::new(dir.path().join("holochain.log"), 100, 2);
+        let record = LogRecord::new(LogLevel::Info, "test", "x".repeat(60));
+        for _ in 0..4 {
+            sink.write(&record).unwrap();
+        }
+
+        assert!(sink.path().exists());
+        assert!(sink.rotated_path(1).exists());
+        assert!(sink.rotated_path(2).exists());
+        assert!(!sink.rotated_path(3).exists());
+        let content = fs::read_to_string(sink.path()).unwrap();
+        assert_eq!(content.lines().count(), 1);
+        assert!(content.contains(&"x".repeat(60)));
+    }
+}
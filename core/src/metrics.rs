@@ -0,0 +1,647 @@
+//! Runtime metrics of an instance: which actions it reduces, how long reducers, zome calls
+//! and storage operations take, how validation goes and how full the action queue is.
+//! Metrics get collected in the MetricsRegistry of the Context and read through snapshots,
+//! which can be rendered in the Prometheus text format for scraping.
+
+use action::Action;
+use holochain_core_types::{
+    cas::{
+        content::{Address, AddressableContent},
+        storage::ContentAddressableStorage,
+    },
+    eav::{Attribute, Entity, EntityAttributeValue, EntityAttributeValueStorage, Value},
+    error::HolochainError,
+};
+use nucleus::ZomeFnCall;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+/// Number, total and maximum duration of timed operations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub total_micros: u64,
+    pub max_micros: u64,
+}
+
+impl LatencySummary {
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros());
+        self.count += 1;
+        self.total_micros += micros;
+        self.max_micros = self.max_micros.max(micros);
+    }
+
+    pub fn mean_micros(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.total_micros / self.count
+        }
+    }
+}
+
+/// Latency and errors of the calls of one zome function.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ZomeCallMetrics {
+    pub zome_name: String,
+    pub cap_name: String,
+    pub fn_name: String,
+    pub latency: LatencySummary,
+    pub errors: u64,
+}
+
+/// Outcomes of entry validations.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidationMetrics {
+    pub passed: u64,
+    pub failed: u64,
+}
+
+/// Saturation of the action queue feeding the action loop.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionQueueMetrics {
+    /// actions dispatched but not picked up by the action loop yet,
+    /// including those of dispatchers that are blocked on a full queue
+    pub depth: usize,
+    /// highest depth seen so far
+    pub max_depth: usize,
+    /// size of the queue, 0 until an action loop has been started
+    pub capacity: usize,
+    /// dispatches that found the queue full and had to block
+    pub full: usize,
+}
+
+/// Point in time copy of the metrics of an instance.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// reduced actions by Action variant
+    pub actions: BTreeMap<String, u64>,
+    /// reducer latency by state slice (nucleus, agent, dht)
+    pub reducers: BTreeMap<String, LatencySummary>,
+    /// by zome, capability and function
+    pub zome_calls: Vec<ZomeCallMetrics>,
+    pub validations: ValidationMetrics,
+    /// storage operation latency by operation, e.g. "cas_add" or "eav_fetch"
+    pub storage: BTreeMap<String, LatencySummary>,
+    pub action_queue: ActionQueueMetrics,
+}
+
+impl MetricsSnapshot {
+    /// Renders the snapshot in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        self.write_prometheus(&mut out)
+            .expect("writing to a String can't fail");
+        out
+    }
+
+    fn write_prometheus(&self, out: &mut String) -> fmt::Result {
+        writeln!(
+            out,
+            "# HELP holochain_actions_total Reduced actions by kind."
+        )?;
+        writeln!(out, "# TYPE holochain_actions_total counter")?;
+        for (action, count) in self.actions.iter() {
+            writeln!(
+                out,
+                "holochain_actions_total{{action=\"{}\"}} {}",
+                escape_label(action),
+                count
+            )?;
+        }
+
+        write_latency(
+            out,
+            "holochain_reducer",
+            "Time spent in the reducers of a state slice.",
+            self.reducers
+                .iter()
+                .map(|(slice, latency)| (format!("slice=\"{}\"", escape_label(slice)), latency)),
+        )?;
+
+        write_latency(
+            out,
+            "holochain_zome_call",
+            "Time spent in zome function calls.",
+            self.zome_calls
+                .iter()
+                .map(|call| (zome_call_labels(call), &call.latency)),
+        )?;
+        writeln!(
+            out,
+            "# HELP holochain_zome_call_errors_total Zome function calls that returned an error."
+        )?;
+        writeln!(out, "# TYPE holochain_zome_call_errors_total counter")?;
+        for call in self.zome_calls.iter() {
+            writeln!(
+                out,
+                "holochain_zome_call_errors_total{{{}}} {}",
+                zome_call_labels(call),
+                call.errors
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP holochain_validations_total Entry validations by outcome."
+        )?;
+        writeln!(out, "# TYPE holochain_validations_total counter")?;
+        writeln!(
+            out,
+            "holochain_validations_total{{result=\"pass\"}} {}",
+            self.validations.passed
+        )?;
+        writeln!(
+            out,
+            "holochain_validations_total{{result=\"fail\"}} {}",
+            self.validations.failed
+        )?;
+
+        write_latency(
+            out,
+            "holochain_storage",
+            "Time spent in CAS and EAV storage operations.",
+            self.storage
+                .iter()
+                .map(|(op, latency)| (format!("op=\"{}\"", escape_label(op)), latency)),
+        )?;
+
+        let queue = &self.action_queue;
+        for (name, kind, help, value) in vec![
+            (
+                "holochain_action_queue_depth",
+                "gauge",
+                "Actions waiting for the action loop.",
+                queue.depth,
+            ),
+            (
+                "holochain_action_queue_max_depth",
+                "gauge",
+                "Highest action queue depth seen.",
+                queue.max_depth,
+            ),
+            (
+                "holochain_action_queue_capacity",
+                "gauge",
+                "Size of the action queue.",
+                queue.capacity,
+            ),
+            (
+                "holochain_action_queue_full_total",
+                "counter",
+                "Dispatches that blocked on a full action queue.",
+                queue.full,
+            ),
+        ] {
+            writeln!(out, "# HELP {} {}", name, help)?;
+            writeln!(out, "# TYPE {} {}", name, kind)?;
+            writeln!(out, "{} {}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// writes count, sum and max of timed operations, with durations in seconds as Prometheus expects
+fn write_latency<'a, I>(out: &mut String, name: &str, help: &str, series: I) -> fmt::Result
+where
+    I: Iterator<Item = (String, &'a LatencySummary)>,
+{
+    let series: Vec<_> = series.collect();
+    writeln!(out, "# HELP {}_seconds {}", name, help)?;
+    writeln!(out, "# TYPE {}_seconds summary", name)?;
+    for (labels, latency) in series.iter() {
+        writeln!(
+            out,
+            "{}_seconds_count{{{}}} {}",
+            name, labels, latency.count
+        )?;
+        writeln!(
+            out,
+            "{}_seconds_sum{{{}}} {}",
+            name,
+            labels,
+            latency.total_micros as f64 / 1_000_000.0
+        )?;
+    }
+    writeln!(out, "# HELP {}_max_seconds Longest of: {}", name, help)?;
+    writeln!(out, "# TYPE {}_max_seconds gauge", name)?;
+    for (labels, latency) in series.iter() {
+        writeln!(
+            out,
+            "{}_max_seconds{{{}}} {}",
+            name,
+            labels,
+            latency.max_micros as f64 / 1_000_000.0
+        )?;
+    }
+    Ok(())
+}
+
+fn zome_call_labels(call: &ZomeCallMetrics) -> String {
+    format!(
+        "zome=\"{}\",cap=\"{}\",fn=\"{}\"",
+        escape_label(&call.zome_name),
+        escape_label(&call.cap_name),
+        escape_label(&call.fn_name)
+    )
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// zome name, capability name, function name
+type ZomeFnKey = (String, String, String);
+
+#[derive(Default)]
+struct Metrics {
+    actions: BTreeMap<&'static str, u64>,
+    reducers: BTreeMap<&'static str, LatencySummary>,
+    zome_calls: BTreeMap<ZomeFnKey, (LatencySummary, u64)>,
+    validations: ValidationMetrics,
+    storage: BTreeMap<&'static str, LatencySummary>,
+}
+
+/// Collects the metrics of an instance.
+/// The action queue gets counted on every dispatch, so it only uses atomics,
+/// everything else is recorded at most once per action or operation.
+#[derive(Default)]
+pub struct MetricsRegistry {
+    metrics: Mutex<Metrics>,
+    queue_depth: AtomicUsize,
+    queue_max_depth: AtomicUsize,
+    queue_capacity: AtomicUsize,
+    queue_full: AtomicUsize,
+}
+
+impl MetricsRegistry {
+    pub fn new() -> Self {
+        MetricsRegistry::default()
+    }
+
+    pub fn record_action(&self, action: &Action) {
+        *self.metrics().actions.entry(action.name()).or_insert(0) += 1;
+    }
+
+    pub fn record_reducer(&self, slice: &'static str, elapsed: Duration) {
+        self.metrics()
+            .reducers
+            .entry(slice)
+            .or_insert_with(LatencySummary::default)
+            .record(elapsed);
+    }
+
+    pub fn record_zome_call(&self, call: &ZomeFnCall, elapsed: Duration, is_error: bool) {
+        let key = (
+            call.zome_name.clone(),
+            call.cap_name.clone(),
+            call.fn_name.clone(),
+        );
+        let mut metrics = self.metrics();
+        let (latency, errors) = metrics
+            .zome_calls
+            .entry(key)
+            .or_insert_with(Default::default);
+        latency.record(elapsed);
+        if is_error {
+            *errors += 1;
+        }
+    }
+
+    pub fn record_validation(&self, passed: bool) {
+        let mut metrics = self.metrics();
+        if passed {
+            metrics.validations.passed += 1;
+        } else {
+            metrics.validations.failed += 1;
+        }
+    }
+
+    pub fn record_storage(&self, operation: &'static str, elapsed: Duration) {
+        self.metrics()
+            .storage
+            .entry(operation)
+            .or_insert_with(LatencySummary::default)
+            .record(elapsed);
+    }
+
+    /// an action is about to be put into the action queue
+    pub fn action_enqueued(&self) {
+        let depth = self.queue_depth.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut max_depth = self.queue_max_depth.load(Ordering::SeqCst);
+        while depth > max_depth {
+            match self.queue_max_depth.compare_exchange(
+                max_depth,
+                depth,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => break,
+                Err(current) => max_depth = current,
+            }
+        }
+    }
+
+    /// the action loop took an action out of the queue, or putting it in failed
+    pub fn action_dequeued(&self) {
+        self.queue_depth.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// a dispatch found the action queue full
+    pub fn action_queue_full(&self) {
+        self.queue_full.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn set_action_queue_capacity(&self, capacity: usize) {
+        self.queue_capacity.store(capacity, Ordering::SeqCst);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let metrics = self.metrics();
+        MetricsSnapshot {
+            actions: metrics
+                .actions
+                .iter()
+                .map(|(action, count)| (action.to_string(), *count))
+                .collect(),
+            reducers: metrics
+                .reducers
+                .iter()
+                .map(|(slice, latency)| (slice.to_string(), latency.clone()))
+                .collect(),
+            zome_calls: metrics
+                .zome_calls
+                .iter()
+                .map(
+                    |((zome_name, cap_name, fn_name), (latency, errors))| ZomeCallMetrics {
+                        zome_name: zome_name.clone(),
+                        cap_name: cap_name.clone(),
+                        fn_name: fn_name.clone(),
+                        latency: latency.clone(),
+                        errors: *errors,
+                    },
+                ).collect(),
+            validations: metrics.validations.clone(),
+            storage: metrics
+                .storage
+                .iter()
+                .map(|(op, latency)| (op.to_string(), latency.clone()))
+                .collect(),
+            action_queue: ActionQueueMetrics {
+                depth: self.queue_depth.load(Ordering::SeqCst),
+                max_depth: self.queue_max_depth.load(Ordering::SeqCst),
+                capacity: self.queue_capacity.load(Ordering::SeqCst),
+                full: self.queue_full.load(Ordering::SeqCst),
+            },
+        }
+    }
+
+    fn metrics(&self) -> MutexGuard<Metrics> {
+        // counters stay usable even if a thread panicked while recording
+        self.metrics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// ContentAddressableStorage that records the duration of every operation of the wrapped one.
+/// Two of them are equal if they wrap equal storages, the registry doesn't matter.
+#[derive(Clone)]
+pub struct MeteredStorage<CAS> {
+    storage: CAS,
+    metrics: Arc<MetricsRegistry>,
+}
+
+impl<CAS> MeteredStorage<CAS> {
+    pub fn new(storage: CAS, metrics: Arc<MetricsRegistry>) -> Self {
+        MeteredStorage { storage, metrics }
+    }
+}
+
+impl<CAS: PartialEq> PartialEq for MeteredStorage<CAS> {
+    fn eq(&self, other: &MeteredStorage<CAS>) -> bool {
+        self.storage == other.storage
+    }
+}
+
+impl<CAS: fmt::Debug> fmt::Debug for MeteredStorage<CAS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MeteredStorage({:?})", self.storage)
+    }
+}
+
+impl<CAS: ContentAddressableStorage> ContentAddressableStorage for MeteredStorage<CAS> {
+    fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError> {
+        let start = Instant::now();
+        let result = self.storage.add(content);
+        self.metrics.record_storage("cas_add", start.elapsed());
+        result
+    }
+
+    fn contains(&self, address: &Address) -> Result<bool, HolochainError> {
+        let start = Instant::now();
+        let result = self.storage.contains(address);
+        self.metrics.record_storage("cas_contains", start.elapsed());
+        result
+    }
+
+    fn fetch<C: AddressableContent>(&self, address: &Address) -> Result<Option<C>, HolochainError> {
+        let start = Instant::now();
+        let result = self.storage.fetch(address);
+        self.metrics.record_storage("cas_fetch", start.elapsed());
+        result
+    }
+}
+
+/// EntityAttributeValueStorage that records the duration of every operation of the wrapped one.
+/// Two of them are equal if they wrap equal storages, the registry doesn't matter.
+#[derive(Clone)]
+pub struct MeteredEavStorage<EAVS> {
+    storage: EAVS,
+    metrics: Arc<MetricsRegistry>,
+}
+
+impl<EAVS> MeteredEavStorage<EAVS> {
+    pub fn new(storage: EAVS, metrics: Arc<MetricsRegistry>) -> Self {
+        MeteredEavStorage { storage, metrics }
+    }
+}
+
+impl<EAVS: PartialEq> PartialEq for MeteredEavStorage<EAVS> {
+    fn eq(&self, other: &MeteredEavStorage<EAVS>) -> bool {
+        self.storage == other.storage
+    }
+}
+
+impl<EAVS: fmt::Debug> fmt::Debug for MeteredEavStorage<EAVS> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MeteredEavStorage({:?})", self.storage)
+    }
+}
+
+impl<EAVS: EntityAttributeValueStorage> EntityAttributeValueStorage for MeteredEavStorage<EAVS> {
+    fn add_eav(&mut self, eav: &EntityAttributeValue) -> Result<(), HolochainError> {
+        let start = Instant::now();
+        let result = self.storage.add_eav(eav);
+        self.metrics.record_storage("eav_add", start.elapsed());
+        result
+    }
+
+    fn fetch_eav(
+        &self,
+        entity: Option<Entity>,
+        attribute: Option<Attribute>,
+        value: Option<Value>,
+    ) -> Result<HashSet<EntityAttributeValue>, HolochainError> {
+        let start = Instant::now();
+        let result = self.storage.fetch_eav(entity, attribute, value);
+        self.metrics.record_storage("eav_fetch", start.elapsed());
+        result
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use action::tests::{test_action_wrapper_commit, test_action_wrapper_get};
+    use holochain_cas_implementations::{
+        cas::memory::MemoryStorage, eav::memory::EavMemoryStorage,
+    };
+    use holochain_core_types::{cas::content::ExampleAddressableContent, eav::test_eav};
+
+    #[test]
+    /// test that the registry counts actions, calls and validations and tracks the queue depth
+    fn snapshot_of_recorded_metrics() {
+        let registry = MetricsRegistry::new();
+        registry.record_action(test_action_wrapper_commit().action());
+        registry.record_action(test_action_wrapper_commit().action());
+        registry.record_action(test_action_wrapper_get().action());
+        let call = ZomeFnCall::new("test_zome", "test_cap", "main", "");
+        registry.record_zome_call(&call, Duration::from_millis(2), false);
+        registry.record_zome_call(&call, Duration::from_millis(4), true);
+        registry.record_validation(true);
+        registry.record_validation(false);
+        registry.record_validation(true);
+        registry.set_action_queue_capacity(100);
+        registry.action_enqueued();
+        registry.action_enqueued();
+        registry.action_dequeued();
+        registry.action_queue_full();
+
+        let snapshot = registry.snapshot();
+        assert_eq!(snapshot.actions.get("Commit"), Some(&2));
+        assert_eq!(snapshot.actions.get("GetEntry"), Some(&1));
+        assert_eq!(
+            snapshot.zome_calls,
+            vec![ZomeCallMetrics {
+                zome_name: "test_zome".to_string(),
+                cap_name: "test_cap".to_string(),
+                fn_name: "main".to_string(),
+                latency: LatencySummary {
+                    count: 2,
+                    total_micros: 6000,
+                    max_micros: 4000,
+                },
+                errors: 1,
+            }]
+        );
+        assert_eq!(snapshot.zome_calls[0].latency.mean_micros(), 3000);
+        assert_eq!(
+            snapshot.validations,
+            ValidationMetrics {
+                passed: 2,
+                failed: 1,
+            }
+        );
+        assert_eq!(
+            snapshot.action_queue,
+            ActionQueueMetrics {
+                depth: 1,
+                max_depth: 2,
+                capacity: 100,
+                full: 1,
+            }
+        );
+    }
+
+    #[test]
+    /// test that metered storages time their operations and still compare by their content
+    fn metered_storages() {
+        let registry = Arc::new(MetricsRegistry::new());
+        let mut cas = MeteredStorage::new(MemoryStorage::new().unwrap(), registry.clone());
+        let content = ExampleAddressableContent::from_content(&"foo".to_string());
+        cas.add(&content).unwrap();
+        assert!(cas.contains(&content.address()).unwrap());
+        assert_eq!(
+            cas.fetch::<ExampleAddressableContent>(&content.address())
+                .unwrap(),
+            Some(content)
+        );
+        assert_eq!(
+            cas,
+            MeteredStorage::new(cas.storage.clone(), Arc::new(MetricsRegistry::new()))
+        );
+
+        let mut eav = MeteredEavStorage::new(EavMemoryStorage::new().unwrap(), registry.clone());
+        eav.add_eav(&test_eav()).unwrap();
+        assert_eq!(
+            eav.fetch_eav(Some(test_eav().entity()), None, None)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let storage = registry.snapshot().storage;
+        for op in vec![
+            "cas_add",
+            "cas_contains",
+            "cas_fetch",
+            "eav_add",
+            "eav_fetch",
+        ] {
+            assert_eq!(
+                storage.get(op).map(|latency| latency.count),
+                Some(1),
+                "{}",
+                op
+            );
+        }
+    }
+
+    #[test]
+    /// test that the Prometheus rendering exposes every metric with its labels
+    fn prometheus_text_format() {
+        let registry = MetricsRegistry::new();
+        registry.record_action(test_action_wrapper_commit().action());
+        registry.record_reducer("agent", Duration::from_millis(1500));
+        let call = ZomeFnCall::new("test_zome", "test_cap", "ma\"in", "");
+        registry.record_zome_call(&call, Duration::from_millis(1), true);
+        registry.set_action_queue_capacity(100);
+
+        let text = registry.snapshot().to_prometheus();
+        for line in vec![
+            "# TYPE holochain_actions_total counter",
+            "holochain_actions_total{action=\"Commit\"} 1",
+            "holochain_reducer_seconds_count{slice=\"agent\"} 1",
+            "holochain_reducer_seconds_sum{slice=\"agent\"} 1.5",
+            "holochain_reducer_max_seconds{slice=\"agent\"} 1.5",
+            "holochain_zome_call_errors_total{zome=\"test_zome\",cap=\"test_cap\",fn=\"ma\\\"in\"} 1",
+            "holochain_validations_total{result=\"pass\"} 0",
+            "holochain_action_queue_depth 0",
+            "holochain_action_queue_capacity 100",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing line: {}", line);
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Middleware taps into the flow of actions through the redux store of an instance,
+//! so tracing, auditing, metrics or fault injection in tests can be built
+//! without touching the reducers.
+
+use action::ActionWrapper;
+use context::Context;
+use holochain_core_types::error::HolochainError;
+use state::State;
+use std::sync::Arc;
+
+/// Middleware gets registered on the Context and sees every action the action loop processes,
+/// once before and once after it gets reduced.
+/// Middleware runs on the action loop thread in the order it was registered,
+/// so it should return quickly and must not block on the action channel.
+pub trait Middleware: Send + Sync {
+    /// Called with the current state before the action gets reduced.
+    /// Returning an error vetoes the action: it doesn't get reduced, doesn't get journaled
+    /// and no later middleware sees it. It still shows up in the history, so callers of
+    /// dispatch_action_and_wait return. Futures waiting for its response,
+    /// like the one of a zome call, a commit or a validation, resolve to the error instead.
+    fn before_reduce(
+        &self,
+        _context: &Arc<Context>,
+        _action_wrapper: &ActionWrapper,
+        _state: &State,
+    ) -> Result<(), HolochainError> {
+        Ok(())
+    }
+
+    /// Called with the new state once the action has been reduced and observers have run.
+    /// Returned actions are follow-ups that get processed right after this action,
+    /// ahead of everything else that is queued on the action channel.
+    fn after_reduce(
+        &self,
+        _context: &Arc<Context>,
+        _action_wrapper: &ActionWrapper,
+        _state: &State,
+    ) -> Vec<ActionWrapper> {
+        Vec::new()
+    }
+}
+
+/// Asks all middleware of the context whether the action may be reduced.
+pub(crate) fn before_reduce(
+    context: &Arc<Context>,
+    action_wrapper: &ActionWrapper,
+    state: &State,
+) -> Result<(), HolochainError> {
+    context
+        .middleware
+        .iter()
+        .map(|middleware| middleware.before_reduce(context, action_wrapper, state))
+        .collect()
+}
+
+/// Shows the reduced action to all middleware of the context and collects their follow-ups.
+pub(crate) fn after_reduce(
+    context: &Arc<Context>,
+    action_wrapper: &ActionWrapper,
+    state: &State,
+) -> Vec<ActionWrapper> {
+    context
+        .middleware
+        .iter()
+        .flat_map(|middleware| middleware.after_reduce(context, action_wrapper, state))
+        .collect()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use action::{
+        tests::{test_action_wrapper_commit, test_action_wrapper_get},
+        Action,
+    };
+    use futures::executor::block_on;
+    use holochain_dna::Dna;
+    use instance::{dispatch_action_and_wait, tests::test_context, Instance, Observer};
+    use nucleus::{actions::call_zome_function::call_zome_function, ZomeFnCall};
+    use std::{
+        sync::{mpsc::channel, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    /// records what it sees, vetoes commits and follows every get up with a commit
+    #[derive(Default)]
+    struct TestMiddleware {
+        seen: Mutex<Vec<String>>,
+        follow_up: Mutex<Option<ActionWrapper>>,
+    }
+
+    impl Middleware for TestMiddleware {
+        fn before_reduce(
+            &self,
+            _context: &Arc<Context>,
+            action_wrapper: &ActionWrapper,
+            state: &State,
+        ) -> Result<(), HolochainError> {
+            assert!(!state.history.contains(action_wrapper));
+            self.seen
+                .lock()
+                .unwrap()
+                .push(format!("before {}", action_wrapper.id()));
+            match action_wrapper.action() {
+                Action::Commit(_) if self.follow_up.lock().unwrap().is_none() => {
+                    Err(HolochainError::new("commits are not allowed"))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn after_reduce(
+            &self,
+            _context: &Arc<Context>,
+            action_wrapper: &ActionWrapper,
+            state: &State,
+        ) -> Vec<ActionWrapper> {
+            assert!(state.history.contains(action_wrapper));
+            self.seen
+                .lock()
+                .unwrap()
+                .push(format!("after {}", action_wrapper.id()));
+            match action_wrapper.action() {
+                Action::GetEntry(_) => self.follow_up.lock().unwrap().iter().cloned().collect(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    /// vetoes every zome call
+    struct VetoZomeCalls;
+
+    impl Middleware for VetoZomeCalls {
+        fn before_reduce(
+            &self,
+            _context: &Arc<Context>,
+            action_wrapper: &ActionWrapper,
+            _state: &State,
+        ) -> Result<(), HolochainError> {
+            match action_wrapper.action() {
+                Action::ExecuteZomeFunction(_) => {
+                    Err(HolochainError::new("zome calls are not allowed"))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    /// vetoes initializing the application
+    struct VetoInitialization;
+
+    impl Middleware for VetoInitialization {
+        fn before_reduce(
+            &self,
+            _context: &Arc<Context>,
+            action_wrapper: &ActionWrapper,
+            _state: &State,
+        ) -> Result<(), HolochainError> {
+            match action_wrapper.action() {
+                Action::InitApplication(_) => {
+                    Err(HolochainError::new("initialization is not allowed"))
+                }
+                _ => Ok(()),
+            }
+        }
+    }
+
+    fn test_instance_with(middleware: Arc<TestMiddleware>) -> (Instance, Arc<Context>) {
+        let mut context = (*test_context("jason")).clone();
+        context.add_middleware(middleware);
+        let context = Arc::new(context);
+        (Instance::new(context.clone()), context)
+    }
+
+    #[test]
+    /// test that a vetoed action doesn't get reduced
+    fn middleware_can_veto() {
+        let middleware = Arc::new(TestMiddleware::default());
+        let (instance, context) = test_instance_with(middleware.clone());
+        let (_, rx_observer) = channel::<Observer>();
+
+        let commit = test_action_wrapper_commit();
+        instance.process_action(commit.clone(), Vec::new(), &rx_observer, &context);
+
+        assert!(instance.state().history.contains(&commit));
+        assert_eq!(instance.state().agent().top_chain_header(), None);
+        assert_eq!(
+            *middleware.seen.lock().unwrap(),
+            vec![format!("before {}", commit.id())]
+        );
+    }
+
+    #[test]
+    /// test that follow-up actions get processed right after the action that caused them
+    fn middleware_can_dispatch_follow_ups() {
+        let middleware = Arc::new(TestMiddleware::default());
+        let follow_up = test_action_wrapper_commit();
+        *middleware.follow_up.lock().unwrap() = Some(follow_up.clone());
+        let (instance, context) = test_instance_with(middleware.clone());
+        let (_, rx_observer) = channel::<Observer>();
+
+        let get = test_action_wrapper_get();
+        instance.process_action(get.clone(), Vec::new(), &rx_observer, &context);
+
+        let history: Vec<ActionWrapper> = instance.state().history.iter().cloned().collect();
+        assert_eq!(history, vec![get.clone(), follow_up.clone()]);
+        assert_eq!(
+            *middleware.seen.lock().unwrap(),
+            vec![
+                format!("before {}", get.id()),
+                format!("after {}", get.id()),
+                format!("before {}", follow_up.id()),
+                format!("after {}", follow_up.id()),
+            ]
+        );
+    }
+
+    #[test]
+    /// test that a vetoed zome call resolves to the reason instead of waiting forever
+    fn vetoed_zome_call_fails() {
+        let mut context = (*test_context("jason")).clone();
+        context.add_middleware(Arc::new(VetoZomeCalls));
+        let context = Arc::new(context);
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            let call = ZomeFnCall::new("test_zome", "test_cap", "main", "");
+            let _ = tx.send(block_on(call_zome_function(call, &context)));
+        });
+
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(5)),
+            Ok(Err(HolochainError::new("zome calls are not allowed"))),
+        );
+    }
+
+    #[test]
+    /// test that waiting for a vetoed action returns instead of blocking forever
+    fn vetoed_action_stops_the_wait() {
+        let mut context = (*test_context("jason")).clone();
+        context.add_middleware(Arc::new(VetoInitialization));
+        let context = Arc::new(context);
+        let mut instance = Instance::new(context.clone());
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            let init = ActionWrapper::new(Action::InitApplication(Dna::new()));
+            let _ = tx.send(dispatch_action_and_wait(
+                &context.action_channel,
+                &context.observer_channel,
+                init,
+            ));
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok(Ok(())));
+    }
+}
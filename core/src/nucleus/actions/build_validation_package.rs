@@ -11,15 +11,17 @@ use holochain_core_types::{
     error::HolochainError,
     validation::{ValidationPackage, ValidationPackageDefinition::*},
 };
+use instance::dispatch_action_from_task;
 use nucleus::ribosome::callback::{self, CallbackResult};
-use snowflake;
 use std::{sync::Arc, thread};
+use uuid::Uuid;
+use waker_registry::poll_response;
 
 pub fn build_validation_package(
     entry: &Entry,
     context: &Arc<Context>,
 ) -> Box<dyn Future<Item = ValidationPackage, Error = HolochainError>> {
-    let id = snowflake::ProcessUniqueId::new();
+    let id = Uuid::new_v4();
 
     match context
         .state()
@@ -55,7 +57,9 @@ pub fn build_validation_package(
                 agent::state::create_new_chain_header(&entry, &*context.state().unwrap().agent()),
             );
 
+            let task = context.start_task();
             thread::spawn(move || {
+                let _task = task;
                 let maybe_callback_result =
                     callback::validation_package::get_validation_package_definition(
                         entry.entry_type().clone(),
@@ -107,13 +111,13 @@ pub fn build_validation_package(
                         })
                     });
 
-                context
-                    .action_channel
-                    .send(ActionWrapper::new(Action::ReturnValidationPackage((
+                dispatch_action_from_task(
+                    &context,
+                    ActionWrapper::new(Action::ReturnValidationPackage((
                         id,
                         maybe_validation_package,
-                    ))))
-                    .expect("action channel to be open in reducer");
+                    ))),
+                );
             });
         }
     };
@@ -160,7 +164,7 @@ fn all_public_chain_headers(context: &Arc<Context>) -> Vec<ChainHeader> {
 /// ValidationPackageFuture resolves to the ValidationPackage or a HolochainError.
 pub struct ValidationPackageFuture {
     context: Arc<Context>,
-    key: snowflake::ProcessUniqueId,
+    key: Uuid,
 }
 
 impl Future for ValidationPackageFuture {
@@ -171,21 +175,17 @@ impl Future for ValidationPackageFuture {
         &mut self,
         cx: &mut futures::task::Context<'_>,
     ) -> Result<Async<Self::Item>, Self::Error> {
-        //
-        // TODO: connect the waker to state updates for performance reasons
-        // See: https://github.com/holochain/holochain-rust/issues/314
-        //
-        cx.waker().wake();
-        if let Some(state) = self.context.state() {
-            match state.nucleus().validation_packages.get(&self.key) {
-                Some(Ok(validation_package)) => {
-                    Ok(futures::Async::Ready(validation_package.clone()))
-                }
-                Some(Err(error)) => Err(error.clone()),
-                None => Ok(futures::Async::Pending),
-            }
-        } else {
-            Ok(futures::Async::Pending)
+        let key = &self.key;
+        let response = poll_response(
+            &self.context,
+            key,
+            Action::ClearValidationPackage(key.clone()),
+            cx,
+            |state| state.nucleus().validation_packages.get(key).cloned(),
+        )?;
+        match response {
+            Async::Ready(result) => result.map(Async::Ready),
+            Async::Pending => Ok(Async::Pending),
         }
     }
 }
@@ -0,0 +1,132 @@
+extern crate futures;
+use action::{Action, ActionWrapper};
+use context::Context;
+use futures::{Async, Future};
+use holochain_core_types::error::HolochainError;
+use instance::dispatch_action;
+use nucleus::ZomeFnCall;
+use std::sync::Arc;
+use waker_registry::poll_response;
+
+/// ExecuteZomeFunction Action Creator
+/// Dispatches the zome function call without blocking.
+///
+/// Returns a future that resolves to the result of the zome function.
+/// Dropping the future before it resolved abandons the call, its result gets dropped then.
+pub fn call_zome_function(call: ZomeFnCall, context: &Arc<Context>) -> ZomeCallFuture {
+    // if the action loop has been stopped the future fails once it gets polled
+    let _ = dispatch_action(
+        &context.action_channel,
+        ActionWrapper::new(Action::ExecuteZomeFunction(call.clone())),
+    );
+    ZomeCallFuture {
+        context: context.clone(),
+        call,
+        done: false,
+    }
+}
+
+/// ZomeCallFuture resolves to the result of a zome function call.
+/// Tracks the state for the result of the call.
+pub struct ZomeCallFuture {
+    context: Arc<Context>,
+    call: ZomeFnCall,
+    done: bool,
+}
+
+impl ZomeCallFuture {
+    /// Stop waiting for the result and drop it once it arrives.
+    fn clear(&mut self) -> Result<(), HolochainError> {
+        self.done = true;
+        dispatch_action(
+            &self.context.action_channel,
+            ActionWrapper::new(Action::ClearZomeFunctionCall(self.call.clone())),
+        )
+    }
+}
+
+impl Future for ZomeCallFuture {
+    type Item = String;
+    type Error = HolochainError;
+
+    fn poll(
+        &mut self,
+        cx: &mut futures::task::Context<'_>,
+    ) -> Result<Async<Self::Item>, Self::Error> {
+        let call = &self.call;
+        let response = poll_response(
+            &self.context,
+            call.id(),
+            Action::ClearZomeFunctionCall(call.clone()),
+            cx,
+            |state| state.nucleus().zome_call_result(call),
+        );
+        match response {
+            Ok(Async::Ready(result)) => {
+                // the call got cleared already
+                self.done = true;
+                result.map(Async::Ready)
+            }
+            Ok(Async::Pending) => Ok(Async::Pending),
+            Err(error) => {
+                // there is nothing left to clear
+                self.done = true;
+                Err(error)
+            }
+        }
+    }
+}
+
+impl Drop for ZomeCallFuture {
+    fn drop(&mut self) {
+        if !self.done {
+            self.context.waker_registry.unregister(self.call.id());
+            // the instance might be shutting down already, nobody cares about the result then
+            let _ = self.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate test_utils;
+
+    use super::*;
+    use futures::executor::block_on;
+    use instance::tests::test_instance_and_context;
+    use std::{thread, time::Duration};
+
+    #[test]
+    /// test that the future resolves to the result of the zome function
+    fn call_zome_function_future() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+        let call = ZomeFnCall::new("test_zome", "test_cap", "main", "");
+
+        let result = block_on(call_zome_function(call.clone(), &context));
+
+        assert_eq!(result, Ok("1337".to_string()));
+        // the clear action might still be queued
+        thread::sleep(Duration::from_millis(100));
+        let state = instance.state();
+        assert!(!state.nucleus().zome_calls.contains_key(&call));
+        assert!(!state.nucleus().abandoned_zome_calls.contains(&call));
+    }
+
+    #[test]
+    /// test that dropping a pending future abandons the call
+    fn drop_abandons_call() {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+        let call = ZomeFnCall::new("test_zome", "test_cap", "xxx", "");
+
+        drop(call_zome_function(call.clone(), &context));
+
+        thread::sleep(Duration::from_millis(100));
+        let state = instance.state();
+        assert!(!state.nucleus().zome_calls.contains_key(&call));
+        assert!(!state.nucleus().abandoned_zome_calls.contains(&call));
+    }
+}
@@ -5,7 +5,7 @@ use context::Context;
 use futures::{executor::block_on, future, Async, Future};
 use holochain_core_types::entry::ToEntry;
 use holochain_dna::Dna;
-use instance::dispatch_action_and_wait;
+use instance::{dispatch_action_and_wait, dispatch_action_from_task, ACTION_LOOP_STOPPED};
 use nucleus::{
     ribosome::callback::{genesis::genesis, CallbackParams, CallbackResult},
     state::NucleusStatus,
@@ -35,14 +35,20 @@ pub fn initialize_application(
     }
 
     let context_clone = context.clone();
+    let task = context.start_task();
 
     thread::spawn(move || {
+        let _task = task;
         let action_wrapper = ActionWrapper::new(Action::InitApplication(dna.clone()));
-        dispatch_action_and_wait(
+        if dispatch_action_and_wait(
             &context_clone.action_channel,
             &context_clone.observer_channel,
             action_wrapper.clone(),
-        );
+        ).is_err()
+        {
+            // the action loop has been stopped, InitializationFuture fails because of that
+            return;
+        }
 
         // Commit DNA to chain
         let dna_entry = dna.to_entry();
@@ -59,12 +65,12 @@ pub fn initialize_application(
         #[cfg_attr(tarpaulin, skip)]
         {
             if dna_commit.is_err() {
-                context_clone
-                    .action_channel
-                    .send(ActionWrapper::new(Action::ReturnInitializationResult(
-                        Some(dna_commit.map_err(|e| e.to_string()).err().unwrap()),
-                    )))
-                    .expect("Action channel not usable in initialize_application()");
+                dispatch_action_from_task(
+                    &context_clone,
+                    ActionWrapper::new(Action::ReturnInitializationResult(Some(
+                        dna_commit.map_err(|e| e.to_string()).err().unwrap(),
+                    ))),
+                );
                 return;
             };
         }
@@ -84,12 +90,12 @@ pub fn initialize_application(
         #[cfg_attr(tarpaulin, skip)]
         {
             if agent_id_commit.is_err() {
-                context_clone
-                    .action_channel
-                    .send(ActionWrapper::new(Action::ReturnInitializationResult(
-                        Some(agent_id_commit.map_err(|e| e.to_string()).err().unwrap()),
-                    )))
-                    .expect("Action channel not usable in initialize_application()");
+                dispatch_action_from_task(
+                    &context_clone,
+                    ActionWrapper::new(Action::ReturnInitializationResult(Some(
+                        agent_id_commit.map_err(|e| e.to_string()).err().unwrap(),
+                    ))),
+                );
                 return;
             };
         }
@@ -114,12 +120,10 @@ pub fn initialize_application(
             None => None,
         };
 
-        context_clone
-            .action_channel
-            .send(ActionWrapper::new(Action::ReturnInitializationResult(
-                maybe_error,
-            )))
-            .expect("Action channel not usable in initialize_application()");
+        dispatch_action_from_task(
+            &context_clone,
+            ActionWrapper::new(Action::ReturnInitializationResult(maybe_error)),
+        );
     });
 
     Box::new(InitializationFuture {
@@ -154,8 +158,13 @@ impl Future for InitializationFuture {
         {
             return Err("Timeout while initializing".to_string());
         }
+        // a stopped action loop won't finish the initialization anymore
+        let stopped = self.context.action_channel.is_stopped();
         if let Some(state) = self.context.state() {
             match state.nucleus().status {
+                NucleusStatus::New | NucleusStatus::Initializing if stopped => {
+                    Err(ACTION_LOOP_STOPPED.to_string())
+                }
                 NucleusStatus::New => Ok(futures::Async::Pending),
                 NucleusStatus::Initializing => Ok(futures::Async::Pending),
                 NucleusStatus::Initialized => Ok(futures::Async::Ready(NucleusStatus::Initialized)),
@@ -1,4 +1,5 @@
 pub mod build_validation_package;
+pub mod call_zome_function;
 pub mod get_entry;
 pub mod initialize;
 pub mod validate;
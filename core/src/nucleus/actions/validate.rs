@@ -7,9 +7,11 @@ use holochain_core_types::{
     cas::content::AddressableContent, entry::Entry, entry_type::EntryType, error::HolochainError,
     hash::HashString, validation::ValidationData,
 };
+use instance::dispatch_action_from_task;
 use nucleus::ribosome::callback::{self, CallbackResult};
-use snowflake;
 use std::{sync::Arc, thread};
+use uuid::Uuid;
+use waker_registry::poll_response;
 
 /// ValidateEntry Action Creator
 /// This is the high-level validate function that wraps the whole validation process and is what should
@@ -22,7 +24,7 @@ pub fn validate_entry(
     validation_data: ValidationData,
     context: &Arc<Context>,
 ) -> Box<dyn Future<Item = HashString, Error = HolochainError>> {
-    let id = snowflake::ProcessUniqueId::new();
+    let id = Uuid::new_v4();
     let address = entry.address();
 
     match context
@@ -44,7 +46,9 @@ pub fn validate_entry(
             let address = address.clone();
             let entry = entry.clone();
             let context = context.clone();
+            let task = context.start_task();
             thread::spawn(move || {
+                let _task = task;
                 let maybe_validation_result = callback::validate_entry::validate_entry(
                     entry.clone(),
                     entry_type.clone(),
@@ -64,14 +68,12 @@ pub fn validate_entry(
                     },
                     Err(error) => Err(error.to_string()),
                 };
+                context.metrics.record_validation(result.is_ok());
 
-                context
-                    .action_channel
-                    .send(ActionWrapper::new(Action::ReturnValidationResult((
-                        (id, address),
-                        result,
-                    ))))
-                    .expect("action channel to be open in reducer");
+                dispatch_action_from_task(
+                    &context,
+                    ActionWrapper::new(Action::ReturnValidationResult(((id, address), result))),
+                );
             });
         }
     };
@@ -86,7 +88,7 @@ pub fn validate_entry(
 /// Tracks the state for ValidationResults.
 pub struct ValidationFuture {
     context: Arc<Context>,
-    key: (snowflake::ProcessUniqueId, HashString),
+    key: (Uuid, HashString),
 }
 
 impl Future for ValidationFuture {
@@ -97,19 +99,18 @@ impl Future for ValidationFuture {
         &mut self,
         cx: &mut futures::task::Context<'_>,
     ) -> Result<Async<Self::Item>, Self::Error> {
-        //
-        // TODO: connect the waker to state updates for performance reasons
-        // See: https://github.com/holochain/holochain-rust/issues/314
-        //
-        cx.waker().wake();
-        if let Some(state) = self.context.state() {
-            match state.nucleus().validation_results.get(&self.key) {
-                Some(Ok(())) => Ok(futures::Async::Ready(self.key.1.clone())),
-                Some(Err(e)) => Err(HolochainError::ValidationFailed(e.clone())),
-                None => Ok(futures::Async::Pending),
-            }
-        } else {
-            Ok(futures::Async::Pending)
+        let key = &self.key;
+        let response = poll_response(
+            &self.context,
+            &key.0,
+            Action::ClearValidationResult(key.clone()),
+            cx,
+            |state| state.nucleus().validation_results.get(key).cloned(),
+        )?;
+        match response {
+            Async::Ready(Ok(())) => Ok(Async::Ready(key.1.clone())),
+            Async::Ready(Err(e)) => Err(HolochainError::ValidationFailed(e)),
+            Async::Pending => Ok(Async::Pending),
         }
     }
 }
@@ -8,24 +8,29 @@ use action::{Action, ActionWrapper, NucleusReduceFn};
 use context::Context;
 use holochain_core_types::error::{DnaError, HcResult, HolochainError};
 use holochain_dna::{wasm::DnaWasm, zome::capabilities::Capability, Dna};
-use instance::{dispatch_action_with_observer, Observer};
+use instance::{
+    action_loop_stopped, dispatch_action_and_wait, dispatch_action_from_task,
+    dispatch_action_with_observer, ActionSender, Observer,
+};
 use nucleus::{
     ribosome::api::call::reduce_call,
     state::{NucleusState, NucleusStatus},
 };
-use snowflake;
+use state::State;
 use std::{
     sync::{
-        mpsc::{sync_channel, SyncSender},
+        mpsc::{sync_channel, RecvTimeoutError, SyncSender},
         Arc,
     },
     thread,
+    time::{Duration, Instant},
 };
+use uuid::Uuid;
 
 /// Struct holding data for requesting the execution of a Zome function (ExecutionZomeFunction Action)
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ZomeFnCall {
-    id: snowflake::ProcessUniqueId,
+    id: Uuid,
     pub zome_name: String,
     pub cap_name: String,
     pub fn_name: String,
@@ -37,7 +42,7 @@ impl ZomeFnCall {
         ZomeFnCall {
             // @TODO can we defer to the ActionWrapper id?
             // @see https://github.com/holochain/holochain-rust/issues/198
-            id: snowflake::ProcessUniqueId::new(),
+            id: Uuid::new_v4(),
             zome_name: zome.to_string(),
             cap_name: capability.to_string(),
             fn_name: function.to_string(),
@@ -45,6 +50,10 @@ impl ZomeFnCall {
         }
     }
 
+    pub fn id(&self) -> &Uuid {
+        &self.id
+    }
+
     pub fn same_fn_as(&self, fn_call: &ZomeFnCall) -> bool {
         self.zome_name == fn_call.zome_name
             && self.cap_name == fn_call.cap_name
@@ -73,7 +82,7 @@ impl EntrySubmission {
 /// Dispatch ExecuteZoneFunction to and block until call has finished.
 pub fn call_zome_and_wait_for_result(
     call: ZomeFnCall,
-    action_channel: &SyncSender<ActionWrapper>,
+    action_channel: &ActionSender,
     observer_channel: &SyncSender<Observer>,
 ) -> Result<String, HolochainError> {
     let call_action_wrapper = ActionWrapper::new(Action::ExecuteZomeFunction(call.clone()));
@@ -84,19 +93,21 @@ pub fn call_zome_and_wait_for_result(
         action_channel,
         observer_channel,
         call_action_wrapper,
-        move |state: &super::state::State| {
-            if let Some(result) = state.nucleus().zome_call_result(&call) {
-                sender
-                    .send(result.clone())
-                    .expect("local channel to be open");
-                true
-            } else {
-                false
-            }
-        },
+        zome_call_result_observer(call.clone(), sender),
+    )?;
+    // Block until we got that result through the channel,
+    // the observer gets dropped if the action loop stops before:
+    let result = receiver
+        .recv()
+        .unwrap_or_else(|_| Err(action_loop_stopped()));
+    // Nobody else is waiting for this result, so drop it from the state,
+    // unless the action loop has been stopped and there is no state to change
+    let _ = dispatch_action_and_wait(
+        action_channel,
+        observer_channel,
+        ActionWrapper::new(Action::ClearZomeFunctionCall(call)),
     );
-    // Block until we got that result through the channel:
-    receiver.recv().expect("local channel to work")
+    result
 }
 
 /// Dispatch ExecuteZoneFunction to Instance and block until call has finished.
@@ -104,29 +115,74 @@ pub fn call_zome_and_wait_for_result(
 pub fn call_and_wait_for_result(
     call: ZomeFnCall,
     instance: &mut super::instance::Instance,
+) -> Result<String, HolochainError> {
+    call_and_wait(call, instance, None)
+}
+
+/// Dispatch ExecuteZoneFunction to Instance and block until call has finished or the timeout
+/// has passed. A zome function that times out keeps running, but its result gets dropped.
+pub fn call_and_wait_for_result_with_timeout(
+    call: ZomeFnCall,
+    instance: &mut super::instance::Instance,
+    timeout: Duration,
+) -> Result<String, HolochainError> {
+    call_and_wait(call, instance, Some(timeout))
+}
+
+fn call_and_wait(
+    call: ZomeFnCall,
+    instance: &mut super::instance::Instance,
+    maybe_timeout: Option<Duration>,
 ) -> Result<String, HolochainError> {
     let call_action = ActionWrapper::new(Action::ExecuteZomeFunction(call.clone()));
 
     // Dispatch action with observer closure that waits for a result in the state
     let (sender, receiver) = sync_channel(1);
-    instance.dispatch_with_observer(call_action, move |state: &super::state::State| {
-        if let Some(result) = state.nucleus().zome_call_result(&call) {
-            sender
-                .send(result.clone())
-                .expect("local channel to be open");
+    instance.dispatch_with_observer(call_action, zome_call_result_observer(call.clone(), sender))?;
+
+    // Block until we got that result through the channel,
+    // the observer gets dropped if the action loop stops before:
+    let result = match maybe_timeout {
+        None => receiver
+            .recv()
+            .unwrap_or_else(|_| Err(action_loop_stopped())),
+        Some(timeout) => match receiver.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => Err(HolochainError::Timeout(format!(
+                "Zome function '{}' did not return within {:?}",
+                call.fn_name, timeout
+            ))),
+            Err(RecvTimeoutError::Disconnected) => Err(action_loop_stopped()),
+        },
+    };
+    // Nobody else is waiting for this result, so drop it from the state,
+    // or once it arrives if the call timed out.
+    // There is no state to change if the action loop has been stopped.
+    let _ = instance.dispatch_and_wait(ActionWrapper::new(Action::ClearZomeFunctionCall(call)));
+    result
+}
+
+/// Observer closure that sends the result of the given zome call once it shows up in the state.
+/// It also finishes if the call got abandoned, since its result would never show up then.
+pub(crate) fn zome_call_result_observer(
+    call: ZomeFnCall,
+    sender: SyncSender<Result<String, HolochainError>>,
+) -> impl FnMut(&State) -> bool + Send + 'static {
+    move |state: &State| {
+        let nucleus = state.nucleus();
+        if let Some(result) = nucleus.zome_call_result(&call) {
+            // the receiver is gone if the caller timed out in the meantime
+            let _ = sender.send(result);
             true
         } else {
-            false
+            nucleus.abandoned_zome_calls.contains(&call)
         }
-    });
-
-    // Block until we got that result through the channel:
-    receiver.recv().expect("local channel to work")
+    }
 }
 
 pub type ZomeFnResult = HcResult<String>;
 
-#[derive(Clone, Debug, PartialEq, Hash)]
+#[derive(Clone, Debug, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ExecuteZomeFnResponse {
     call: ZomeFnCall,
     result: ZomeFnResult,
@@ -198,6 +254,7 @@ fn reduce_init_application(
             state.status = NucleusStatus::Initializing;
             // Set DNA
             state.dna = Some(dna.clone());
+            state.dna_hash = Some(dna.content_hash());
         }
     }
 }
@@ -208,9 +265,16 @@ pub(crate) fn launch_zome_fn_call(
     wasm: &DnaWasm,
     dna_name: String,
 ) {
+    // the result of the call is already part of the journal that is being replayed
+    if context.replaying {
+        return;
+    }
     let code = wasm.code.clone();
+    let task = context.start_task();
 
     thread::spawn(move || {
+        let _task = task;
+        let start = Instant::now();
         // Have Ribosome spin up DNA and call the zome function
         let call_result = ribosome::run_dna(
             &dna_name,
@@ -219,15 +283,16 @@ pub(crate) fn launch_zome_fn_call(
             &zome_call,
             Some(zome_call.clone().parameters.into_bytes()),
         );
+        context
+            .metrics
+            .record_zome_call(&zome_call, start.elapsed(), call_result.is_err());
         // Construct response
         let response = ExecuteZomeFnResponse::new(zome_call.clone(), call_result);
         // Send ReturnZomeFunctionResult Action
-        context
-            .action_channel
-            .send(ActionWrapper::new(Action::ReturnZomeFunctionResult(
-                response,
-            )))
-            .expect("action channel to be open in reducer");
+        dispatch_action_from_task(
+            &context,
+            ActionWrapper::new(Action::ReturnZomeFunctionResult(response)),
+        );
     });
 }
 
@@ -243,16 +308,19 @@ fn reduce_execute_zome_function(
         Action::ExecuteZomeFunction(call) => call,
         _ => unreachable!(),
     };
+    // The call is pending until its result arrives, also if that is an error dispatched below,
+    // so a caller that gives up on it before can tell that a result is still to come.
+    state.zome_calls.insert(fn_call.clone(), None);
 
-    fn dispatch_error_result(
-        action_channel: &SyncSender<ActionWrapper>,
-        fn_call: &ZomeFnCall,
-        error: HolochainError,
-    ) {
+    fn dispatch_error_result(context: &Arc<Context>, fn_call: &ZomeFnCall, error: HolochainError) {
+        if context.replaying {
+            return;
+        }
         let zome_not_found_response =
             ExecuteZomeFnResponse::new(fn_call.clone(), Err(error.clone()));
 
-        action_channel
+        context
+            .action_channel
             .send(ActionWrapper::new(Action::ReturnZomeFunctionResult(
                 zome_not_found_response,
             )))
@@ -262,11 +330,7 @@ fn reduce_execute_zome_function(
     // Get DNA
     let dna = match state.dna {
         None => {
-            dispatch_error_result(
-                &context.action_channel,
-                &fn_call,
-                HolochainError::DnaMissing,
-            );
+            dispatch_error_result(&context, &fn_call, HolochainError::DnaMissing);
             return;
         }
         Some(ref d) => d,
@@ -275,7 +339,7 @@ fn reduce_execute_zome_function(
     let zome = match dna.zomes.get(&fn_call.zome_name) {
         None => {
             dispatch_error_result(
-                &context.action_channel,
+                &context,
                 &fn_call,
                 HolochainError::Dna(DnaError::ZomeNotFound(format!(
                     "Zome '{}' not found",
@@ -290,7 +354,7 @@ fn reduce_execute_zome_function(
     let capability = match zome.capabilities.get(&fn_call.cap_name) {
         None => {
             dispatch_error_result(
-                &context.action_channel,
+                &context,
                 &fn_call,
                 HolochainError::Dna(DnaError::CapabilityNotFound(format!(
                     "Capability '{}' not found in Zome '{}'",
@@ -309,7 +373,7 @@ fn reduce_execute_zome_function(
         .find(|&fn_declaration| fn_declaration.name == fn_call.fn_name);
     if maybe_fn.is_none() {
         dispatch_error_result(
-            &context.action_channel,
+            &context,
             &fn_call,
             HolochainError::Dna(DnaError::ZomeFunctionNotFound(format!(
                 "Zome function '{}' not found",
@@ -319,8 +383,6 @@ fn reduce_execute_zome_function(
         return;
     }
     // Ok Zome function is defined in given capability.
-    // Prepare call - FIXME is this really useful?
-    state.zome_calls.insert(fn_call.clone(), None);
     // Launch thread with function call
     launch_zome_fn_call(
         context,
@@ -353,6 +415,10 @@ fn reduce_return_zome_function_result(
 ) {
     let action = action_wrapper.action();
     let fr = unwrap_to!(action => Action::ReturnZomeFunctionResult);
+    // nobody would ever read the result of an abandoned call
+    if state.abandoned_zome_calls.remove(&fr.call()) {
+        return;
+    }
     // @TODO store the action and result directly
     // @see https://github.com/holochain/holochain-rust/issues/198
     state.zome_calls.insert(fr.call(), Some(fr.result()));
@@ -370,6 +436,43 @@ fn reduce_return_validation_package(
         .insert(id.clone(), maybe_validation_package.clone());
 }
 
+/// Reduce ClearZomeFunctionCall Action.
+/// Drops the result of a zome function call once it has been read.
+fn reduce_clear_zome_function_call(
+    _context: Arc<Context>,
+    state: &mut NucleusState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let call = unwrap_to!(action => Action::ClearZomeFunctionCall);
+    // a pending call got abandoned (it might still be running or its error result might
+    // still be queued), so drop its result once it arrives.
+    // Calls that aren't pending won't deliver a result, there is nothing to wait for.
+    if let Some(None) = state.zome_calls.remove(call) {
+        state.abandoned_zome_calls.insert(call.clone());
+    }
+}
+
+fn reduce_clear_validation_result(
+    _context: Arc<Context>,
+    state: &mut NucleusState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let key = unwrap_to!(action => Action::ClearValidationResult);
+    state.validation_results.remove(key);
+}
+
+fn reduce_clear_validation_package(
+    _context: Arc<Context>,
+    state: &mut NucleusState,
+    action_wrapper: &ActionWrapper,
+) {
+    let action = action_wrapper.action();
+    let id = unwrap_to!(action => Action::ClearValidationPackage);
+    state.validation_packages.remove(id);
+}
+
 /// Maps incoming action to the correct reducer
 fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
     match action_wrapper.action() {
@@ -380,6 +483,9 @@ fn resolve_reducer(action_wrapper: &ActionWrapper) -> Option<NucleusReduceFn> {
         Action::Call(_) => Some(reduce_call),
         Action::ReturnValidationResult(_) => Some(reduce_return_validation_result),
         Action::ReturnValidationPackage(_) => Some(reduce_return_validation_package),
+        Action::ClearZomeFunctionCall(_) => Some(reduce_clear_zome_function_call),
+        Action::ClearValidationResult(_) => Some(reduce_clear_validation_result),
+        Action::ClearValidationPackage(_) => Some(reduce_clear_validation_package),
         _ => None,
     }
 }
@@ -402,6 +508,43 @@ pub fn reduce(
     }
 }
 
+/// Resolve whoever waits for an action that middleware vetoed with the reason for the veto:
+/// zome calls that didn't start or whose result got vetoed, and validations.
+pub(crate) fn reduce_veto(
+    old_state: Arc<NucleusState>,
+    action_wrapper: &ActionWrapper,
+    reason: &HolochainError,
+) -> Arc<NucleusState> {
+    let mut new_state: NucleusState = (*old_state).clone();
+    match action_wrapper.action() {
+        Action::ExecuteZomeFunction(call) | Action::Call(call) => {
+            new_state
+                .zome_calls
+                .insert(call.clone(), Some(Err(reason.clone())));
+        }
+        Action::ReturnZomeFunctionResult(response) => {
+            // nobody would ever read the result of an abandoned call
+            if !new_state.abandoned_zome_calls.remove(&response.call()) {
+                new_state
+                    .zome_calls
+                    .insert(response.call(), Some(Err(reason.clone())));
+            }
+        }
+        Action::ReturnValidationResult(((id, address), _)) => {
+            new_state
+                .validation_results
+                .insert((id.clone(), address.clone()), Err(reason.to_string()));
+        }
+        Action::ReturnValidationPackage((id, _)) => {
+            new_state
+                .validation_packages
+                .insert(id.clone(), Err(reason.clone()));
+        }
+        _ => return old_state,
+    }
+    Arc::new(new_state)
+}
+
 // Helper function for getting a Capability for a ZomeFnCall request
 fn get_capability_with_zome_call(
     dna: &Dna,
@@ -423,12 +566,14 @@ pub mod tests {
     extern crate test_utils;
     use super::*;
     use action::{tests::test_action_wrapper_rzfr, ActionWrapper};
+    use holochain_core_types::entry::test_entry_address;
     use holochain_dna::Dna;
     use instance::{
         tests::{test_context, test_context_with_channels, test_instance},
         Instance,
     };
     use nucleus::state::tests::test_nucleus_state;
+    use serde_json;
     use std::sync::Arc;
 
     use std::error::Error;
@@ -469,7 +614,7 @@ pub mod tests {
     }
 
     #[test]
-    /// test the equality and uniqueness of function calls (based on internal snowflakes)
+    /// test the equality and uniqueness of function calls (based on internal UUIDs)
     fn test_zome_call_eq() {
         let zc1 = test_zome_call();
         let zc2 = test_zome_call();
@@ -478,6 +623,18 @@ pub mod tests {
         assert_ne!(zc1, zc2);
     }
 
+    #[test]
+    /// test that function calls can be persisted and still identify the same call
+    fn test_zome_call_serialization_round_trip() {
+        let zome_call = test_zome_call();
+        let json = serde_json::to_string(&zome_call).expect("zome call should serialize");
+        let restored: ZomeFnCall =
+            serde_json::from_str(&json).expect("zome call should deserialize");
+
+        assert_eq!(zome_call, restored);
+        assert_ne!(test_zome_call(), restored);
+    }
+
     #[test]
     /// test access to function result's function call
     fn test_zome_call_result() {
@@ -520,6 +677,79 @@ pub mod tests {
         assert!(state.zome_calls.contains_key(&fr.call()));
     }
 
+    #[test]
+    /// test that consumed results get dropped from the nucleus state
+    fn test_reduce_clear_results() {
+        let context = test_context("jimmy");
+        let mut state = test_nucleus_state();
+
+        let action_wrapper = test_action_wrapper_rzfr();
+        reduce_return_zome_function_result(context.clone(), &mut state, &action_wrapper);
+        let action = action_wrapper.action();
+        let call = unwrap_to!(action => Action::ReturnZomeFunctionResult).call();
+        reduce_clear_zome_function_call(
+            context.clone(),
+            &mut state,
+            &ActionWrapper::new(Action::ClearZomeFunctionCall(call)),
+        );
+        assert!(state.zome_calls.is_empty());
+        assert!(state.abandoned_zome_calls.is_empty());
+
+        let key = (Uuid::new_v4(), test_entry_address());
+        state.validation_results.insert(key.clone(), Ok(()));
+        reduce_clear_validation_result(
+            context.clone(),
+            &mut state,
+            &ActionWrapper::new(Action::ClearValidationResult(key)),
+        );
+        assert!(state.validation_results.is_empty());
+
+        let id = Uuid::new_v4();
+        state
+            .validation_packages
+            .insert(id.clone(), Err(HolochainError::new("no package")));
+        reduce_clear_validation_package(
+            context,
+            &mut state,
+            &ActionWrapper::new(Action::ClearValidationPackage(id)),
+        );
+        assert!(state.validation_packages.is_empty());
+    }
+
+    #[test]
+    /// test that the late result of an abandoned call doesn't end up in the state
+    fn test_reduce_abandoned_zome_call() {
+        let context = test_context("jimmy");
+        let mut state = test_nucleus_state();
+
+        let action_wrapper = test_action_wrapper_rzfr();
+        let action = action_wrapper.action();
+        let call = unwrap_to!(action => Action::ReturnZomeFunctionResult).call();
+        state.zome_calls.insert(call.clone(), None);
+
+        reduce_clear_zome_function_call(
+            context.clone(),
+            &mut state,
+            &ActionWrapper::new(Action::ClearZomeFunctionCall(call.clone())),
+        );
+        assert!(state.zome_calls.is_empty());
+        assert!(state.abandoned_zome_calls.contains(&call));
+
+        reduce_return_zome_function_result(context.clone(), &mut state, &action_wrapper);
+        assert!(state.zome_calls.is_empty());
+        assert!(state.abandoned_zome_calls.is_empty());
+
+        // clearing a call that isn't pending, e.g. one that got cleared already,
+        // leaves nothing behind that would never be pruned
+        reduce_clear_zome_function_call(
+            context,
+            &mut state,
+            &ActionWrapper::new(Action::ClearZomeFunctionCall(call)),
+        );
+        assert!(state.zome_calls.is_empty());
+        assert!(state.abandoned_zome_calls.is_empty());
+    }
+
     #[test]
     /// smoke test the init of a nucleus reduction
     fn can_reduce_initialize_action() {
@@ -538,6 +768,7 @@ pub mod tests {
         assert_eq!(reduced_nucleus.status(), NucleusStatus::Initializing);
         assert!(reduced_nucleus.dna().is_some());
         assert_eq!(reduced_nucleus.dna().unwrap(), dna);
+        assert_eq!(reduced_nucleus.dna_hash(), Some(dna.content_hash()));
     }
 
     #[test]
@@ -601,9 +832,16 @@ pub mod tests {
         // Create zome function call
         let zome_call = ZomeFnCall::new("test_zome", "test_cap", "main", "");
 
-        let result = super::call_and_wait_for_result(zome_call, &mut instance);
+        let result = super::call_and_wait_for_result(zome_call.clone(), &mut instance);
         assert!(result.is_ok());
         assert_eq!("1337", result.unwrap());
+
+        // the result has been consumed, so it must not linger in the state
+        assert!(!instance
+            .state()
+            .nucleus()
+            .zome_calls
+            .contains_key(&zome_call));
     }
 
     #[test]
@@ -611,14 +849,16 @@ pub mod tests {
     fn can_reduce_execfn_action() {
         let call = ZomeFnCall::new("myZome", "public", "bogusfn", "");
 
-        let action_wrapper = ActionWrapper::new(Action::ExecuteZomeFunction(call));
+        let action_wrapper = ActionWrapper::new(Action::ExecuteZomeFunction(call.clone()));
         let nucleus = Arc::new(NucleusState::new()); // initialize to bogus value
         let (sender, _receiver) = sync_channel::<ActionWrapper>(10);
         let (tx_observer, _observer) = sync_channel::<Observer>(10);
         let context = test_context_with_channels("jimmy", &sender, &tx_observer);
 
         let reduced_nucleus = reduce(context, nucleus.clone(), &action_wrapper);
-        assert_eq!(nucleus, reduced_nucleus);
+        // the call is pending until the error result for the missing DNA arrives
+        assert_eq!(reduced_nucleus.zome_calls.get(&call), Some(&None));
+        assert_eq!(reduced_nucleus.zome_calls.len(), 1);
     }
 
     #[test]
@@ -0,0 +1,162 @@
+use holochain_core_types::error::{RibosomeErrorCode, RibosomeErrorReport};
+use logger::{LogLevel, LogRecord};
+use nucleus::ribosome::{Runtime, RuntimeError};
+use std::fmt;
+use wasmi::{HostError, RuntimeArgs, RuntimeValue, Trap, TrapKind};
+
+/// Host error of a zome that called abort, carrying where it aborted.
+/// The zome call fails with HolochainError::ZomeAborted holding the report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZomeAborted(pub RibosomeErrorReport);
+
+impl fmt::Display for ZomeAborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl HostError for ZomeAborted {}
+
+/// ZomeApiFunction::Abort function code
+/// args: [0] pointer to the AssemblyScript string of the message
+///       [1] pointer to the AssemblyScript string of the filename
+///       [2] line number
+///       [3] column number
+/// Logs a RibosomeErrorReport and traps the zome with it, so it never returns.
+pub fn invoke_abort(
+    runtime: &mut Runtime,
+    args: &RuntimeArgs,
+) -> Result<Option<RuntimeValue>, Trap> {
+    let message_pointer: u32 = args.nth_checked(0)?;
+    let filename_pointer: u32 = args.nth_checked(1)?;
+    let line: u32 = args.nth_checked(2)?;
+    let column: u32 = args.nth_checked(3)?;
+
+    let description = load_assemblyscript_string(runtime, message_pointer).map_err(|err_code| {
+        RuntimeError::trap(format!(
+            "Zome called abort with an invalid message: {}",
+            err_code.to_string()
+        ))
+    })?;
+    let file_name = load_assemblyscript_string(runtime, filename_pointer).map_err(|err_code| {
+        RuntimeError::trap(format!(
+            "Zome called abort with an invalid filename: {}",
+            err_code.to_string()
+        ))
+    })?;
+    let report = RibosomeErrorReport {
+        description,
+        file_name,
+        line: line.to_string(),
+        column: column.to_string(),
+    };
+
+    runtime
+        .context
+        .log_record(
+            LogRecord::new(
+                LogLevel::Error,
+                "zome",
+                format!(
+                    "Zome aborted at {}:{}:{}: {}",
+                    report.file_name, report.line, report.column, report.description
+                ),
+            ).zome(runtime.zome_call.zome_name.clone())
+            .function(runtime.zome_call.fn_name.clone()),
+        ).map_err(|error| RuntimeError::trap(format!("Could not log abort: {}", error)))?;
+
+    Err(Trap::new(TrapKind::Host(Box::new(ZomeAborted(report)))))
+}
+
+/// Load an AssemblyScript string from wasm memory:
+/// its length in UTF-16 code units as a little endian u32, followed by the code units.
+/// A null pointer is an empty string, as AssemblyScript passes it for a missing message.
+fn load_assemblyscript_string(
+    runtime: &Runtime,
+    pointer: u32,
+) -> Result<String, RibosomeErrorCode> {
+    if pointer == 0 {
+        return Ok(String::new());
+    }
+    let header = runtime.memory_manager.read_bytes(pointer, 4)?;
+    let length = header
+        .iter()
+        .rev()
+        .fold(0_usize, |length, byte| length << 8 | usize::from(*byte));
+    let offset = pointer
+        .checked_add(4)
+        .ok_or(RibosomeErrorCode::NotAnAllocation)?;
+    let byte_length = length
+        .checked_mul(2)
+        .ok_or(RibosomeErrorCode::NotAnAllocation)?;
+    let bytes = runtime.memory_manager.read_bytes(offset, byte_length)?;
+    let code_units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|pair| u16::from(pair[0]) | u16::from(pair[1]) << 8)
+        .collect();
+    Ok(String::from_utf16_lossy(&code_units))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use holochain_core_types::error::{HolochainError, RibosomeErrorReport};
+    use nucleus::ribosome::{api::tests::run_wat, metering::ExecutionLimits};
+
+    /// zome laying out strings like AssemblyScript, aborting with the message "oops" in "a.ts" at 12:3
+    const ABORT_TEST_WAT: &str = r#"
+(module
+    (import "env" "abort" (func $abort (param i32 i32 i32 i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (data (i32.const 8) "\04\00\00\00o\00o\00p\00s\00")
+    (data (i32.const 32) "\04\00\00\00a\00.\00t\00s\00")
+
+    (func (export "main") (param $allocation i32) (result i32)
+        (call $abort (i32.const 8) (i32.const 32) (i32.const 12) (i32.const 3))
+        (i32.const 0)
+    )
+
+    (func (export "abort_out_of_memory") (param $allocation i32) (result i32)
+        (call $abort (i32.const 65534) (i32.const 32) (i32.const 12) (i32.const 3))
+        (i32.const 0)
+    )
+)
+"#;
+
+    fn call_abort_test_zome(fn_name: &str) -> (Result<String, HolochainError>, String) {
+        let (result, context) = run_wat(ABORT_TEST_WAT, fn_name, "", ExecutionLimits::default());
+        let log = format!("{}", (*context.logger.lock().unwrap()).dump());
+        (result, log)
+    }
+
+    #[test]
+    fn abort_fails_the_zome_call_with_a_report() {
+        let (result, log) = call_abort_test_zome("main");
+        assert_eq!(
+            Err(HolochainError::ZomeAborted(RibosomeErrorReport {
+                description: "oops".to_string(),
+                file_name: "a.ts".to_string(),
+                line: "12".to_string(),
+                column: "3".to_string(),
+            })),
+            result,
+        );
+        assert!(
+            log.contains("Zome aborted at a.ts:12:3: oops"),
+            "unexpected log: {}",
+            log
+        );
+    }
+
+    #[test]
+    fn abort_with_invalid_message_traps() {
+        match call_abort_test_zome("abort_out_of_memory").0 {
+            Err(HolochainError::RibosomeFailed(message)) => assert!(
+                message.contains("Zome called abort with an invalid message"),
+                "unexpected message: {}",
+                message
+            ),
+            result => panic!("expected the zome to fail, got {:?}", result),
+        }
+    }
+}
@@ -5,10 +5,10 @@ use holochain_dna::zome::capabilities::Membrane;
 use instance::RECV_DEFAULT_TIMEOUT_MS;
 use nucleus::{
     get_capability_with_zome_call, launch_zome_fn_call, ribosome::Runtime, state::NucleusState,
-    ZomeFnCall,
+    zome_call_result_observer, ZomeFnCall,
 };
 use serde_json;
-use std::sync::{mpsc::channel, Arc};
+use std::sync::{mpsc::sync_channel, Arc};
 use wasmi::{RuntimeArgs, RuntimeValue, Trap};
 
 /// Struct for input data received when Call API function is invoked
@@ -44,7 +44,7 @@ pub fn invoke_call(
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
     // deserialize args
-    let args_str = runtime.load_utf8_from_args(&args);
+    let args_str = runtime.load_utf8_from_args(&args)?;
     let input: ZomeCallArgs = match serde_json::from_str(&args_str) {
         Ok(input) => input,
         // Exit on error
@@ -62,36 +62,31 @@ pub fn invoke_call(
     // Create Call Action
     let action_wrapper = ActionWrapper::new(Action::Call(zome_call.clone()));
     // Send Action and block
-    let (sender, receiver) = channel();
-    ::instance::dispatch_action_with_observer(
+    let (sender, receiver) = sync_channel(1);
+    if ::instance::dispatch_action_with_observer(
         &runtime.context.action_channel,
         &runtime.context.observer_channel,
         action_wrapper.clone(),
-        move |state: &::state::State| {
-            // Observer waits for a ribosome_call_result
-            let maybe_result = state.nucleus().zome_call_result(&zome_call);
-            match maybe_result {
-                Some(result) => {
-                    // @TODO never panic in wasm
-                    // @see https://github.com/holochain/holochain-rust/issues/159
-                    sender
-                        .send(result)
-                        // the channel stays connected until the first message has been sent
-                        // if this fails that means that it was called after having returned done=true
-                        .expect("observer called after done");
+        zome_call_result_observer(zome_call.clone(), sender),
+    ).is_err()
+    {
+        return ribosome_error_code!(Unspecified);
+    }
 
-                    true
-                }
-                None => false,
-            }
-        },
+    let maybe_action_result = receiver.recv_timeout(RECV_DEFAULT_TIMEOUT_MS);
+
+    // Nobody else is waiting for this result, so drop it from the state,
+    // or once it arrives if the call timed out.
+    // There is no state to change if the action loop has been stopped.
+    let _ = ::instance::dispatch_action(
+        &runtime.context.action_channel,
+        ActionWrapper::new(Action::ClearZomeFunctionCall(zome_call)),
     );
-    // TODO #97 - Return error if timeout or something failed
-    // return Err(_);
 
-    let action_result = receiver
-        .recv_timeout(RECV_DEFAULT_TIMEOUT_MS)
-        .expect("observer dropped before done");
+    let action_result = match maybe_action_result {
+        Ok(action_result) => action_result,
+        Err(_) => return ribosome_error_code!(Timeout),
+    };
 
     // action_result should be a json str of the result of the zome function called
     match action_result {
@@ -161,11 +156,22 @@ pub(crate) fn reduce_call(
     }
 
     // 3. Get the exposed Zome function WASM and execute it in a separate thread
-    let maybe_code = dna.get_wasm_from_zome_name(fn_call.zome_name.clone());
-    let code =
-        maybe_code.expect("zome not found, Should have failed before when getting capability.");
+    let code = match dna.get_wasm_from_zome_name(fn_call.zome_name.clone()) {
+        Some(code) => code,
+        None => {
+            // Notify failure
+            state.zome_calls.insert(
+                fn_call.clone(),
+                Some(Err(HolochainError::RibosomeFailed(format!(
+                    "Zome '{}' has no wasm",
+                    fn_call.zome_name
+                )))),
+            );
+            return;
+        }
+    };
     state.zome_calls.insert(fn_call.clone(), None);
-    launch_zome_fn_call(context, fn_call, &code, state.dna.clone().unwrap().name);
+    launch_zome_fn_call(context, fn_call, &code, dna.name.clone());
 }
 
 #[cfg(test)]
@@ -197,7 +203,10 @@ pub mod tests {
     };
     use persister::SimplePersister;
     use serde_json;
-    use std::sync::{mpsc::RecvTimeoutError, Arc, Mutex};
+    use std::sync::{
+        mpsc::{channel, RecvTimeoutError},
+        Arc, Mutex,
+    };
     use test_utils::create_test_dna_with_cap;
 
     /// dummy commit args from standard test entry
@@ -1,4 +1,5 @@
 use agent::actions::commit::*;
+use context::Context;
 use futures::{executor::block_on, FutureExt};
 use holochain_core_types::{
     cas::content::Address,
@@ -14,7 +15,7 @@ use nucleus::{
     ribosome::Runtime,
 };
 use serde_json;
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 use wasmi::{RuntimeArgs, RuntimeValue, Trap};
 
 /// ZomeApiFunction::CommitAppEntry function code
@@ -26,7 +27,7 @@ pub fn invoke_commit_app_entry(
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
     // deserialize args
-    let args_str = runtime.load_utf8_from_args(&args);
+    let args_str = runtime.load_utf8_from_args(&args)?;
     let input: CommitEntryArgs = match serde_json::from_str(&args_str) {
         Ok(entry_input) => entry_input,
         // Exit on error
@@ -38,6 +39,11 @@ pub fn invoke_commit_app_entry(
         EntryType::from_str(&input.entry_type_name).expect("could not create EntryType from str");
     let entry = Entry::new(&entry_type, &input.entry_value);
 
+    // Check the entry against the schema of its type before running any wasm callbacks
+    if let Err(error) = check_entry_schema(&entry_type, &input.entry_value, &runtime.context) {
+        return store_commit_result(runtime, Err(error));
+    }
+
     // Wait for future to be resolved
     let task_result: Result<Address, HolochainError> = block_on(
         // 1. Build the context needed for validation of the entry
@@ -62,6 +68,14 @@ pub fn invoke_commit_app_entry(
             .and_then(|_| commit_entry(entry.clone(), &runtime.context.action_channel, &runtime.context)),
     );
 
+    store_commit_result(runtime, task_result)
+}
+
+/// Hands the outcome of a commit back to the zome as a CommitEntryResult.
+fn store_commit_result(
+    runtime: &mut Runtime,
+    task_result: Result<Address, HolochainError>,
+) -> Result<Option<RuntimeValue>, Trap> {
     let maybe_json = match task_result {
         Ok(address) => serde_json::to_string(&CommitEntryResult::success(address)),
         Err(HolochainError::ValidationFailed(fail_string)) => {
@@ -85,16 +99,55 @@ pub fn invoke_commit_app_entry(
     }
 }
 
+/// Checks the value of an app entry against the schema its type declares in the DNA, if any.
+/// Fails with a ValidationFailed holding the SchemaViolation as JSON, so zomes can tell which
+/// part of the entry breaks which part of the schema.
+fn check_entry_schema(
+    entry_type: &EntryType,
+    entry_value: &str,
+    context: &Arc<Context>,
+) -> Result<(), HolochainError> {
+    // entries of undefined types fail validation later on
+    if !EntryType::has_valid_app_name(entry_type.as_str()) {
+        return Ok(());
+    }
+    let dna = context
+        .state()
+        .and_then(|state| state.nucleus().dna())
+        .ok_or(HolochainError::DnaMissing)?;
+    let schema = dna
+        .get_entry_type_def(entry_type.as_str())
+        .and_then(|entry_type_def| entry_type_def.schema.as_ref());
+    match schema {
+        Some(schema) => schema.check_str(entry_value).map_err(|violation| {
+            HolochainError::ValidationFailed(
+                serde_json::to_string(&violation).expect("violation should serialize"),
+            )
+        }),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     extern crate test_utils;
     extern crate wabt;
 
+    use super::*;
     use holochain_core_types::{
         cas::content::AddressableContent, entry::test_entry, entry_type::test_entry_type,
     };
+    use holochain_dna::zome::schema::{EntrySchema, SchemaViolation};
+    use instance::tests::test_instance_and_context;
     use nucleus::ribosome::{
-        api::{commit::CommitEntryArgs, tests::test_zome_api_function, ZomeApiFunction},
+        api::{
+            commit::CommitEntryArgs,
+            tests::{
+                test_capability, test_zome_api_function, test_zome_api_function_call,
+                test_zome_api_function_wasm, test_zome_name,
+            },
+            ZomeApiFunction,
+        },
         Defn,
     };
     use serde_json;
@@ -130,4 +183,55 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// test that commits get checked against the schema of their entry type
+    fn test_commit_checks_schema() {
+        let wasm = test_zome_api_function_wasm(ZomeApiFunction::CommitAppEntry.as_str());
+        let mut dna = test_utils::create_test_dna_with_wasm(
+            &test_zome_name(),
+            &test_capability(),
+            wasm.clone(),
+        );
+        dna.zomes
+            .get_mut(&test_zome_name())
+            .unwrap()
+            .entry_types
+            .get_mut(&test_entry_type().to_string())
+            .unwrap()
+            .schema = Some(EntrySchema(json!({
+            "type": "object",
+            "properties": {"title": {"type": "string"}}
+        })));
+        let dna_name = dna.name.clone();
+        let (instance, context) = test_instance_and_context(dna).unwrap();
+        let top_header = context.state().unwrap().agent().top_chain_header();
+
+        let args = CommitEntryArgs {
+            entry_type_name: test_entry_type().to_string(),
+            entry_value: r#"{"title":1}"#.to_string(),
+        };
+        let call_result = test_zome_api_function_call(
+            &dna_name,
+            context.clone(),
+            &instance,
+            &wasm,
+            serde_json::to_string(&args).unwrap().into_bytes(),
+        );
+
+        let violation = SchemaViolation {
+            instance_path: "/title".to_string(),
+            schema_path: "/properties/title/type".to_string(),
+            message: "should be of type \"string\", not number".to_string(),
+        };
+        let expected = CommitEntryResult::failure(serde_json::to_string(&violation).unwrap());
+        assert_eq!(
+            call_result,
+            serde_json::to_string(&expected).unwrap() + "\u{0}",
+        );
+        // nothing got committed
+        assert_eq!(
+            context.state().unwrap().agent().top_chain_header(),
+            top_header
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use nucleus::ribosome::Runtime;
+use logger::{LogLevel, LogRecord};
+use nucleus::ribosome::{Runtime, RuntimeError};
 use wasmi::{RuntimeArgs, RuntimeValue, Trap};
 
 /// ZomeApiFunction::Debug function code
@@ -9,13 +10,14 @@ pub fn invoke_debug(
     runtime: &mut Runtime,
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
-    let payload = runtime.load_utf8_from_args(args);
-    println!("{}", payload);
-    // TODO #502 - log in logger as DEBUG log-level
+    let payload = runtime.load_utf8_from_args(args)?;
     runtime
         .context
-        .log(&format!("zome_log:DEBUG: '{}'", payload))
-        .expect("Logger should work");
+        .log_record(
+            LogRecord::new(LogLevel::Debug, "zome", payload)
+                .zome(runtime.zome_call.zome_name.clone())
+                .function(runtime.zome_call.fn_name.clone()),
+        ).map_err(|error| RuntimeError::trap(format!("Could not log debug message: {}", error)))?;
 
     // Return Ribosome Success Code
     Ok(Some(RuntimeValue::I32(0 as i32)))
@@ -45,7 +47,7 @@ pub mod tests {
             test_zome_api_function(ZomeApiFunction::Debug.as_str(), test_args_bytes());
         assert!(call_result.is_empty());
         assert_eq!(
-            "[\"zome_log:DEBUG: \\\'foo\\\'\", \"Zome Function \\\'test\\\' returned: Success\"]",
+            "[\"foo\", \"Zome Function \\\'test\\\' returned: Success\"]",
             format!("{}", (*context.logger.lock().unwrap()).dump()),
         );
     }
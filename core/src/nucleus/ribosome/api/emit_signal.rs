@@ -0,0 +1,177 @@
+use holochain_core_types::error::{DnaError, HolochainError};
+use holochain_wasm_utils::api_serialization::emit_signal::{EmitSignalArgs, EmitSignalResult};
+use nucleus::ribosome::Runtime;
+use serde_json;
+use signal::{AppSignal, Signal};
+use wasmi::{RuntimeArgs, RuntimeValue, Trap};
+
+/// ZomeApiFunction::EmitSignal function code
+/// args: [0] encoded MemoryAllocation as u32
+/// Expected complex argument: EmitSignalArgs
+/// Returns a serialized EmitSignalResult
+pub fn invoke_emit_signal(
+    runtime: &mut Runtime,
+    args: &RuntimeArgs,
+) -> Result<Option<RuntimeValue>, Trap> {
+    // deserialize args
+    let args_str = runtime.load_utf8_from_args(&args)?;
+    let input: EmitSignalArgs = match serde_json::from_str(&args_str) {
+        Ok(input) => input,
+        // Exit on error
+        Err(_) => return ribosome_error_code!(ArgumentDeserializationFailed),
+    };
+
+    let emit_result = emit_signal(runtime, &input);
+    let result = EmitSignalResult {
+        ok: emit_result.is_ok(),
+        error: emit_result
+            .map_err(|holochain_error| holochain_error.to_string())
+            .err()
+            .unwrap_or(String::from("")),
+    };
+
+    let json = serde_json::to_string(&result).expect("Could not serialize EmitSignalResult");
+
+    runtime.store_utf8(&json)
+}
+
+/// Emits the signal for every capability of the calling zome that declares it.
+fn emit_signal(runtime: &Runtime, input: &EmitSignalArgs) -> Result<(), HolochainError> {
+    let zome_name = runtime.zome_call.zome_name.clone();
+    let cap_names: Vec<String> = {
+        let state = runtime
+            .context
+            .state()
+            .ok_or_else(|| HolochainError::new("Context has no state"))?;
+        let dna = state.nucleus().dna().ok_or(HolochainError::DnaMissing)?;
+        let zome = dna.get_zome(&zome_name).ok_or_else(|| {
+            HolochainError::Dna(DnaError::ZomeNotFound(format!(
+                "Zome '{}' not found",
+                zome_name
+            )))
+        })?;
+        zome.capabilities
+            .iter()
+            .filter(|(_, capability)| capability.get_signal(&input.name).is_some())
+            .map(|(cap_name, _)| cap_name.clone())
+            .collect()
+    };
+    if cap_names.is_empty() {
+        return Err(HolochainError::Dna(DnaError::SignalNotFound(format!(
+            "Signal '{}' is not declared in Zome '{}'",
+            input.name, zome_name
+        ))));
+    }
+
+    let arguments = serde_json::to_string(&input.arguments)?;
+    for cap_name in cap_names {
+        runtime
+            .context
+            .signal_registry
+            .emit(&Signal::App(AppSignal {
+                zome_name: zome_name.clone(),
+                cap_name,
+                name: input.name.clone(),
+                arguments: arguments.clone(),
+            }));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate test_utils;
+
+    use super::*;
+    use holochain_dna::zome::capabilities::SignalDeclaration;
+    use instance::tests::test_instance_and_context;
+    use nucleus::ribosome::{
+        api::{tests::*, ZomeApiFunction},
+        Defn,
+    };
+    use signal::SignalFilter;
+    use std::sync::{mpsc::channel, Mutex};
+
+    /// dummy emit_signal args for the signal "Post"
+    pub fn test_emit_signal_args_bytes(name: &str) -> Vec<u8> {
+        let args = EmitSignalArgs {
+            name: name.to_string(),
+            arguments: json!({"hash": "QmHash"}),
+        };
+        serde_json::to_string(&args)
+            .expect("args should serialize")
+            .into_bytes()
+    }
+
+    /// calls hc_emit_signal in a zome that declares the signal "Post" and returns the call
+    /// result together with the signals that reached a listener
+    fn test_emit_signal(name: &str) -> (String, Vec<Signal>) {
+        let wasm = test_zome_api_function_wasm(ZomeApiFunction::EmitSignal.as_str());
+        let mut dna =
+            test_utils::create_test_dna_with_wasm(&test_zome_name(), "test_cap", wasm.clone());
+        let mut signal_declaration = SignalDeclaration::new();
+        signal_declaration.name = "Post".to_string();
+        dna.zomes
+            .get_mut(&test_zome_name())
+            .unwrap()
+            .capabilities
+            .get_mut("test_cap")
+            .unwrap()
+            .signals
+            .push(signal_declaration);
+
+        let dna_name = dna.name.clone();
+        let (instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+        let (tx, rx) = channel();
+        let tx = Mutex::new(tx);
+        context.signal_registry.listen(
+            SignalFilter::App {
+                zome_name: test_zome_name(),
+                cap_name: "test_cap".to_string(),
+                name: "Post".to_string(),
+            },
+            move |signal| tx.lock().unwrap().send(signal.clone()).unwrap(),
+        );
+
+        let mut call_result = test_zome_api_function_call(
+            &dna_name,
+            context.clone(),
+            &instance,
+            &wasm,
+            test_emit_signal_args_bytes(name),
+        );
+        call_result.pop(); // Remove trailing character
+        (call_result, rx.try_iter().collect())
+    }
+
+    #[test]
+    /// test that a declared signal reaches its listeners
+    fn test_emit_declared_signal() {
+        let (call_result, signals) = test_emit_signal("Post");
+
+        assert_eq!(call_result, r#"{"ok":true,"error":""}"#);
+        assert_eq!(
+            signals,
+            vec![Signal::App(AppSignal {
+                zome_name: test_zome_name(),
+                cap_name: "test_cap".to_string(),
+                name: "Post".to_string(),
+                arguments: r#"{"hash":"QmHash"}"#.to_string(),
+            })]
+        );
+    }
+
+    #[test]
+    /// test that zomes can't emit signals they didn't declare
+    fn test_emit_undeclared_signal() {
+        let (call_result, signals) = test_emit_signal("Comment");
+
+        let result: EmitSignalResult = serde_json::from_str(&call_result).unwrap();
+        assert!(!result.ok);
+        assert!(result
+            .error
+            .contains("Signal 'Comment' is not declared in Zome 'test_zome'"));
+        assert!(signals.is_empty());
+    }
+}
@@ -13,7 +13,7 @@ pub fn invoke_get_entry(
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
     // deserialize args
-    let args_str = runtime.load_utf8_from_args(&args);
+    let args_str = runtime.load_utf8_from_args(&args)?;
     let res_entry: Result<GetEntryArgs, _> = serde_json::from_str(&args_str);
     // Exit on error
     if res_entry.is_err() {
@@ -1,6 +1,6 @@
 use holochain_core_types::cas::content::Address;
 use holochain_wasm_utils::api_serialization::get_links::{GetLinksArgs, GetLinksResult};
-use nucleus::ribosome::Runtime;
+use nucleus::ribosome::{Runtime, RuntimeError};
 use serde_json;
 use std::collections::HashSet;
 use wasmi::{RuntimeArgs, RuntimeValue, Trap};
@@ -14,7 +14,7 @@ pub fn invoke_get_links(
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
     // deserialize args
-    let args_str = runtime.load_utf8_from_args(&args);
+    let args_str = runtime.load_utf8_from_args(&args)?;
     let input: GetLinksArgs = match serde_json::from_str(&args_str) {
         Ok(input) => input,
         Err(_) => return ribosome_error_code!(ArgumentDeserializationFailed),
@@ -23,7 +23,7 @@ pub fn invoke_get_links(
     let get_links_result = runtime
         .context
         .state()
-        .unwrap()
+        .ok_or_else(|| RuntimeError::trap("Context has no state to get links from"))?
         .dht()
         .get_links(input.entry_address, input.tag);
 
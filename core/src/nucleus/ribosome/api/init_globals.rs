@@ -26,9 +26,8 @@ pub fn invoke_init_globals(
     // Update fields
     if let Some(state) = runtime.context.state() {
         // Update dna_hash
-        if let Some(dna) = state.nucleus().dna() {
-            globals.dna_hash =
-                HashString::encode_from_serializable(dna.to_json(), Multihash::SHA2256);
+        if let Some(dna_hash) = state.nucleus().dna_hash() {
+            globals.dna_hash = dna_hash;
         }
         // Update agent hashes
         let maybe_top = state.agent().top_chain_header();
@@ -65,11 +64,13 @@ pub mod tests {
     /// test that bytes passed to debug end up in the log
     fn test_init_globals() {
         let input: Vec<u8> = vec![];
-        let (mut call_result, _) =
+        let (mut call_result, context) =
             test_zome_api_function(ZomeApiFunction::InitGlobals.as_str(), input);
         call_result.pop(); // Remove trailing character
         let globals: ZomeApiGlobals = serde_json::from_str(&call_result).unwrap();
         assert_eq!(globals.dna_name, "TestApp");
+        let dna = context.state().unwrap().nucleus().dna().unwrap();
+        assert_eq!(globals.dna_hash, dna.content_hash());
         // TODO #233 - Implement agent pub key hash
         // assert_eq!(obj.agent_key_hash, "QmScgMGDzP3d9kmePsXP7ZQ2MXis38BNRpCZBJEBveqLjD");
         assert_eq!(globals.agent_id_str, "jane");
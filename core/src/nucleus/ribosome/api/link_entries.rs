@@ -15,7 +15,7 @@ pub fn invoke_link_entries(
     args: &RuntimeArgs,
 ) -> Result<Option<RuntimeValue>, Trap> {
     // deserialize args
-    let args_str = runtime.load_utf8_from_args(&args);
+    let args_str = runtime.load_utf8_from_args(&args)?;
     let input: LinkEntriesArgs = match serde_json::from_str(&args_str) {
         Ok(entry_input) => entry_input,
         // Exit on error
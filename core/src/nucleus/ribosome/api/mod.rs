@@ -1,20 +1,24 @@
 //! Module for ZomeApiFunctions
 //! ZomeApiFunctions are the functions provided by the ribosome that are callable by Zomes.
 
+pub mod abort;
 pub mod call;
 pub mod commit;
 pub mod debug;
+pub mod emit_signal;
 pub mod get_entry;
 pub mod get_links;
 pub mod init_globals;
 pub mod link_entries;
+pub mod property;
 
 use holochain_dna::zome::capabilities::ReservedCapabilityNames;
 use nucleus::ribosome::{
     api::{
-        call::invoke_call, commit::invoke_commit_app_entry, debug::invoke_debug,
-        get_entry::invoke_get_entry, get_links::invoke_get_links,
-        init_globals::invoke_init_globals, link_entries::invoke_link_entries,
+        abort::invoke_abort, call::invoke_call, commit::invoke_commit_app_entry,
+        debug::invoke_debug, emit_signal::invoke_emit_signal, get_entry::invoke_get_entry,
+        get_links::invoke_get_links, init_globals::invoke_init_globals,
+        link_entries::invoke_link_entries, property::invoke_property,
     },
     Defn, Runtime,
 };
@@ -36,7 +40,7 @@ pub enum ZomeApiFunction {
     MissingNo = 0,
 
     /// Abort is a way to receive useful debug info from
-    /// assemblyscript memory allocators
+    /// assemblyscript memory allocators, it traps the zome
     /// message: mem address in the wasm memory for an error message
     /// filename: mem address in the wasm memory for a filename
     /// line: line number
@@ -67,6 +71,14 @@ pub enum ZomeApiFunction {
 
     LinkEntries,
     GetLinks,
+
+    /// Emit a signal declared in the DNA to the listeners of the instance
+    /// hc_emit_signal(name: String, arguments: Value)
+    EmitSignal,
+
+    /// Get the value of a property set in the DNA's "properties" object
+    /// hc_property(key: String) -> Value
+    Property,
 }
 
 impl Defn for ZomeApiFunction {
@@ -81,6 +93,8 @@ impl Defn for ZomeApiFunction {
             ZomeApiFunction::Call => "hc_call",
             ZomeApiFunction::LinkEntries => "hc_link_entries",
             ZomeApiFunction::GetLinks => "hc_get_links",
+            ZomeApiFunction::EmitSignal => "hc_emit_signal",
+            ZomeApiFunction::Property => "hc_property",
         }
     }
 
@@ -118,6 +132,8 @@ impl FromStr for ZomeApiFunction {
             "hc_call" => Ok(ZomeApiFunction::Call),
             "hc_link_entries" => Ok(ZomeApiFunction::LinkEntries),
             "hc_get_links" => Ok(ZomeApiFunction::GetLinks),
+            "hc_emit_signal" => Ok(ZomeApiFunction::EmitSignal),
+            "hc_property" => Ok(ZomeApiFunction::Property),
             _ => Err("Cannot convert string to ZomeApiFunction"),
         }
     }
@@ -133,12 +149,9 @@ impl ZomeApiFunction {
     // cannot test this because PartialEq is not implemented for fns
     #[cfg_attr(tarpaulin, skip)]
     pub fn as_fn(&self) -> (fn(&mut Runtime, &RuntimeArgs) -> Result<Option<RuntimeValue>, Trap>) {
-        // TODO Implement a proper "abort" function for handling assemblyscript aborts
-        // @see: https://github.com/holochain/holochain-rust/issues/324
-
         match *self {
             ZomeApiFunction::MissingNo => noop,
-            ZomeApiFunction::Abort => noop,
+            ZomeApiFunction::Abort => invoke_abort,
             ZomeApiFunction::Debug => invoke_debug,
             ZomeApiFunction::CommitAppEntry => invoke_commit_app_entry,
             ZomeApiFunction::GetAppEntry => invoke_get_entry,
@@ -146,6 +159,8 @@ impl ZomeApiFunction {
             ZomeApiFunction::Call => invoke_call,
             ZomeApiFunction::LinkEntries => invoke_link_entries,
             ZomeApiFunction::GetLinks => invoke_get_links,
+            ZomeApiFunction::EmitSignal => invoke_emit_signal,
+            ZomeApiFunction::Property => invoke_property,
         }
     }
 }
@@ -158,9 +173,10 @@ pub mod tests {
     extern crate test_utils;
     use super::ZomeApiFunction;
     use context::Context;
+    use holochain_core_types::error::HolochainError;
     use instance::{tests::test_instance_and_context, Instance};
     use nucleus::{
-        ribosome::{self, Defn},
+        ribosome::{self, metering::ExecutionLimits, Defn},
         ZomeFnCall,
     };
     use std::{str::FromStr, sync::Arc};
@@ -346,6 +362,34 @@ pub mod tests {
         (call_result, context)
     }
 
+    /// Given a zome written in WAT, calls one of its functions with the given input
+    /// under the given execution limits.
+    /// Returns the call result and the context it ran in.
+    pub fn run_wat(
+        wat: &str,
+        fn_name: &str,
+        input: &str,
+        limits: ExecutionLimits,
+    ) -> (Result<String, HolochainError>, Arc<Context>) {
+        let dna = test_utils::create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
+        let wasm = dna.zomes["test_zome"].code.code.clone();
+        let dna_name = dna.name.clone();
+        let (_instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+        let mut context: Context = (*context).clone();
+        context.execution_limits = limits;
+        let context = Arc::new(context);
+
+        let result = ribosome::run_dna(
+            &dna_name,
+            context.clone(),
+            wasm,
+            &ZomeFnCall::new("test_zome", "test_cap", fn_name, input),
+            Some(input.as_bytes().to_vec()),
+        );
+        (result, context)
+    }
+
     #[test]
     /// test the FromStr implementation for ZomeApiFunction
     fn test_from_str() {
@@ -358,6 +402,8 @@ pub mod tests {
             ("hc_call", ZomeApiFunction::Call),
             ("hc_link_entries", ZomeApiFunction::LinkEntries),
             ("hc_get_links", ZomeApiFunction::GetLinks),
+            ("hc_emit_signal", ZomeApiFunction::EmitSignal),
+            ("hc_property", ZomeApiFunction::Property),
         ] {
             assert_eq!(ZomeApiFunction::from_str(input).unwrap(), output);
         }
@@ -382,6 +428,8 @@ pub mod tests {
             (ZomeApiFunction::Call, "hc_call"),
             (ZomeApiFunction::LinkEntries, "hc_link_entries"),
             (ZomeApiFunction::GetLinks, "hc_get_links"),
+            (ZomeApiFunction::EmitSignal, "hc_emit_signal"),
+            (ZomeApiFunction::Property, "hc_property"),
         ] {
             assert_eq!(output, input.as_str());
         }
@@ -397,6 +445,8 @@ pub mod tests {
             ("hc_call", 6),
             ("hc_link_entries", 7),
             ("hc_get_links", 8),
+            ("hc_emit_signal", 9),
+            ("hc_property", 10),
         ] {
             assert_eq!(output, ZomeApiFunction::str_to_index(input));
         }
@@ -412,6 +462,8 @@ pub mod tests {
             (6, ZomeApiFunction::Call),
             (7, ZomeApiFunction::LinkEntries),
             (8, ZomeApiFunction::GetLinks),
+            (9, ZomeApiFunction::EmitSignal),
+            (10, ZomeApiFunction::Property),
         ] {
             assert_eq!(output, ZomeApiFunction::from_index(input));
         }
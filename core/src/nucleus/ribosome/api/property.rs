@@ -0,0 +1,128 @@
+use holochain_core_types::error::{DnaError, HolochainError};
+use holochain_wasm_utils::api_serialization::property::{PropertyArgs, PropertyResult};
+use nucleus::ribosome::Runtime;
+use serde_json::{self, Value};
+use wasmi::{RuntimeArgs, RuntimeValue, Trap};
+
+/// ZomeApiFunction::Property function code
+/// args: [0] encoded MemoryAllocation as u32
+/// Expected complex argument: PropertyArgs
+/// Returns a serialized PropertyResult
+pub fn invoke_property(
+    runtime: &mut Runtime,
+    args: &RuntimeArgs,
+) -> Result<Option<RuntimeValue>, Trap> {
+    // deserialize args
+    let args_str = runtime.load_utf8_from_args(&args)?;
+    let input: PropertyArgs = match serde_json::from_str(&args_str) {
+        Ok(input) => input,
+        // Exit on error
+        Err(_) => return ribosome_error_code!(ArgumentDeserializationFailed),
+    };
+
+    let result = match property(runtime, &input.key) {
+        Ok(value) => PropertyResult {
+            ok: true,
+            value,
+            error: String::from(""),
+        },
+        Err(holochain_error) => PropertyResult {
+            ok: false,
+            value: Value::Null,
+            error: holochain_error.to_string(),
+        },
+    };
+
+    let json = serde_json::to_string(&result).expect("Could not serialize PropertyResult");
+
+    runtime.store_utf8(&json)
+}
+
+/// Looks up the value of the given key in the "properties" object of the instance's DNA.
+fn property(runtime: &Runtime, key: &str) -> Result<Value, HolochainError> {
+    let state = runtime
+        .context
+        .state()
+        .ok_or_else(|| HolochainError::new("Context has no state"))?;
+    let dna = state.nucleus().dna().ok_or(HolochainError::DnaMissing)?;
+    dna.properties.get(key).cloned().ok_or_else(|| {
+        HolochainError::Dna(DnaError::PropertyNotFound(format!(
+            "Property '{}' is not set in the DNA",
+            key
+        )))
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate test_utils;
+
+    use super::*;
+    use instance::tests::test_instance_and_context;
+    use nucleus::ribosome::{
+        api::{tests::*, ZomeApiFunction},
+        Defn,
+    };
+
+    /// dummy property args for the given key
+    pub fn test_property_args_bytes(key: &str) -> Vec<u8> {
+        let args = PropertyArgs {
+            key: key.to_string(),
+        };
+        serde_json::to_string(&args)
+            .expect("args should serialize")
+            .into_bytes()
+    }
+
+    /// calls hc_property in a DNA that has some properties set
+    fn test_property(key: &str) -> PropertyResult {
+        let wasm = test_zome_api_function_wasm(ZomeApiFunction::Property.as_str());
+        let mut dna = test_utils::create_test_dna_with_wasm(
+            &test_zome_name(),
+            &test_capability(),
+            wasm.clone(),
+        );
+        dna.properties = json!({
+            "max_post_length": 280,
+            "moderators": ["alice", "bob"],
+        });
+
+        let dna_name = dna.name.clone();
+        let (instance, context) =
+            test_instance_and_context(dna).expect("Could not create test instance");
+
+        let mut call_result = test_zome_api_function_call(
+            &dna_name,
+            context,
+            &instance,
+            &wasm,
+            test_property_args_bytes(key),
+        );
+        call_result.pop(); // Remove trailing character
+        serde_json::from_str(&call_result).expect("result should deserialize")
+    }
+
+    #[test]
+    /// test that zomes can read the properties set in the DNA
+    fn test_property_set_in_dna() {
+        let result = test_property("max_post_length");
+        assert!(result.ok);
+        assert_eq!(result.value, json!(280));
+        assert_eq!(result.error, "");
+
+        let result = test_property("moderators");
+        assert!(result.ok);
+        assert_eq!(result.value, json!(["alice", "bob"]));
+    }
+
+    #[test]
+    /// test that reading a property the DNA doesn't set fails
+    fn test_property_not_set_in_dna() {
+        let result = test_property("min_post_length");
+        assert!(!result.ok);
+        assert_eq!(result.value, Value::Null);
+        assert!(result
+            .error
+            .contains("Property 'min_post_length' is not set in the DNA"));
+    }
+}
@@ -7,7 +7,9 @@ pub mod validate_entry;
 pub mod validation_package;
 
 use context::Context;
-use holochain_core_types::{entry::Entry, json::ToJson, validation::ValidationPackageDefinition};
+use holochain_core_types::{
+    entry::Entry, error::HolochainError, json::ToJson, validation::ValidationPackageDefinition,
+};
 use holochain_dna::{wasm::DnaWasm, zome::capabilities::ReservedCapabilityNames, Dna};
 use nucleus::{
     ribosome::{
@@ -169,10 +171,8 @@ pub fn get_dna(context: &Arc<Context>) -> Option<Dna> {
     let mut tries = 0;
     while !done {
         {
-            let state = context
-                .state()
-                .expect("Callback called without application state!");
-            dna = state.nucleus().dna();
+            // Without application state there is no DNA either
+            dna = context.state().and_then(|state| state.nucleus().dna());
         }
         match dna {
             Some(_) => done = true,
@@ -190,9 +190,10 @@ pub fn get_dna(context: &Arc<Context>) -> Option<Dna> {
 }
 
 pub fn get_wasm(context: &Arc<Context>, zome: &str) -> Option<DnaWasm> {
-    let dna = get_dna(context).expect("Callback called without DNA set!");
-    dna.get_wasm_from_zome_name(zome)
-        .and_then(|wasm| Some(wasm.clone()).filter(|_| !wasm.code.is_empty()))
+    get_dna(context).and_then(|dna| {
+        dna.get_wasm_from_zome_name(zome)
+            .and_then(|wasm| Some(wasm.clone()).filter(|_| !wasm.code.is_empty()))
+    })
 }
 
 pub fn call(
@@ -208,7 +209,10 @@ pub fn call(
         &params.to_string(),
     );
 
-    let dna = get_dna(&context).expect("Callback called without DNA set!");
+    let dna = match get_dna(&context) {
+        Some(dna) => dna,
+        None => return CallbackResult::Fail(HolochainError::DnaMissing.to_string()),
+    };
 
     match dna.get_wasm_from_zome_name(zome) {
         None => CallbackResult::NotImplemented,
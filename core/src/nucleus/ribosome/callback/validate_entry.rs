@@ -37,7 +37,7 @@ fn validate_app_entry(
     validation_data: ValidationData,
     context: Arc<Context>,
 ) -> Result<CallbackResult, HolochainError> {
-    let dna = get_dna(&context).expect("Callback called without DNA set!");
+    let dna = get_dna(&context).ok_or(HolochainError::DnaMissing)?;
     let zome_name = dna.get_zome_name_for_entry_type(&app_entry_type);
     if zome_name.is_none() {
         return Ok(CallbackResult::NotImplemented);
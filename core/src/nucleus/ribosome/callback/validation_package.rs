@@ -16,7 +16,7 @@ pub fn get_validation_package_definition(
 ) -> Result<CallbackResult, HolochainError> {
     match entry_type {
         EntryType::App(app_entry_type) => {
-            let dna = get_dna(&context).expect("Callback called without DNA set!");
+            let dna = get_dna(&context).ok_or(HolochainError::DnaMissing)?;
             let zome_name = dna.get_zome_name_for_entry_type(&app_entry_type);
             if zome_name.is_none() {
                 return Ok(CallbackResult::NotImplemented);
@@ -1,7 +1,156 @@
-use holochain_core_types::error::RibosomeErrorCode;
-use holochain_wasm_utils::memory_allocation::{SinglePageAllocation, SinglePageStack, U16_MAX};
+use holochain_core_types::error::{HolochainError, RibosomeErrorCode, RibosomeReturnCode};
+use holochain_wasm_utils::memory_allocation::{
+    decode_encoded_allocation, decode_encoded_multi_page_allocation, encode_multi_page_return_code,
+    MultiPageAllocation, SinglePageAllocation, SinglePageStack, U16_MAX, U32_MAX,
+};
 
-use wasmi::{MemoryRef, ModuleRef};
+use wasmi::{
+    memory_units::Pages, MemoryRef, ModuleRef, RuntimeValue, Signature, ValueType,
+    LINEAR_MEMORY_PAGE_SIZE,
+};
+
+/// Get the memory a wasm module exports.
+/// All modules compiled with rustc export it under the name 'memory',
+/// but a hand-written one might not.
+fn wasm_memory(wasm_instance: &ModuleRef) -> Result<MemoryRef, HolochainError> {
+    let export = wasm_instance.export_by_name("memory").ok_or_else(|| {
+        HolochainError::RibosomeFailed("Zome doesn't export a memory named 'memory'".to_string())
+    })?;
+    let memory = export.as_memory().cloned().ok_or_else(|| {
+        HolochainError::RibosomeFailed("Zome export named 'memory' is not a memory".to_string())
+    })?;
+    Ok(memory)
+}
+
+//--------------------------------------------------------------------------------------------------
+// Memory ABI
+//--------------------------------------------------------------------------------------------------
+
+/// The two ways a zome and the Ribosome can pass data to each other through wasm memory.
+/// A zome is told apart by the signature of its exported functions:
+/// (i32) -> i32 for the single-page ABI, (i64) -> i64 for the multi-page ABI.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MemoryAbi {
+    /// SinglePageAllocations encoded in an i32, capped at 64KiB
+    SinglePage,
+    /// MultiPageAllocations encoded in an i64
+    MultiPage,
+}
+
+impl MemoryAbi {
+    /// The ABI a function with the given signature is built against, if any.
+    pub fn of_signature(signature: &Signature) -> Option<Self> {
+        match (signature.params(), signature.return_type()) {
+            (&[ValueType::I32], Some(ValueType::I32)) => Some(MemoryAbi::SinglePage),
+            (&[ValueType::I64], Some(ValueType::I64)) => Some(MemoryAbi::MultiPage),
+            _ => None,
+        }
+    }
+
+    /// The type encoded allocations have in this ABI.
+    pub fn value_type(self) -> ValueType {
+        match self {
+            MemoryAbi::SinglePage => ValueType::I32,
+            MemoryAbi::MultiPage => ValueType::I64,
+        }
+    }
+
+    /// The signature of zome functions and Zome API functions in this ABI.
+    pub fn signature(self) -> Signature {
+        Signature::new(&[self.value_type()][..], Some(self.value_type()))
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// Memory Manager
+//--------------------------------------------------------------------------------------------------
+
+/// Manages the wasm memory of a zome call in the ABI of the called zome function.
+/// Allocations go in and out as RuntimeValues of the type of that ABI.
+#[derive(Clone, Debug)]
+pub enum MemoryManager {
+    SinglePage(SinglePageManager),
+    MultiPage(MultiPageManager),
+}
+
+impl MemoryManager {
+    pub fn new(
+        abi: MemoryAbi,
+        wasm_instance: &ModuleRef,
+        max_memory_pages: u32,
+    ) -> Result<Self, HolochainError> {
+        Ok(match abi {
+            MemoryAbi::SinglePage => {
+                MemoryManager::SinglePage(SinglePageManager::new(wasm_instance)?)
+            }
+            MemoryAbi::MultiPage => {
+                MemoryManager::MultiPage(MultiPageManager::new(wasm_instance, max_memory_pages)?)
+            }
+        })
+    }
+
+    pub fn abi(&self) -> MemoryAbi {
+        match *self {
+            MemoryManager::SinglePage(_) => MemoryAbi::SinglePage,
+            MemoryManager::MultiPage(_) => MemoryAbi::MultiPage,
+        }
+    }
+
+    /// Read bytes anywhere in wasm memory, for zomes that pass raw pointers
+    /// instead of allocations, like AssemblyScript calling abort.
+    pub fn read_bytes(&self, offset: u32, length: usize) -> Result<Vec<u8>, RibosomeErrorCode> {
+        let wasm_memory = match *self {
+            MemoryManager::SinglePage(ref manager) => &manager.wasm_memory,
+            MemoryManager::MultiPage(ref manager) => &manager.wasm_memory,
+        };
+        wasm_memory
+            .get(offset, length)
+            .map_err(|_| RibosomeErrorCode::NotAnAllocation)
+    }
+
+    /// Write data in wasm memory and return its encoded allocation
+    pub fn write(&mut self, data: &[u8]) -> Result<RuntimeValue, RibosomeErrorCode> {
+        match *self {
+            MemoryManager::SinglePage(ref mut manager) => manager
+                .write(data)
+                .map(|allocation| RuntimeValue::I32(allocation.encode() as i32)),
+            MemoryManager::MultiPage(ref mut manager) => manager
+                .write(data)
+                .map(|allocation| RuntimeValue::I64(allocation.encode() as i64)),
+        }
+    }
+
+    /// Read the data of an encoded allocation.
+    /// Fails with the return code an encoded allocation of zero length holds,
+    /// or NotAnAllocation if the encoded allocation doesn't belong to the ABI or the memory.
+    pub fn read(&self, encoded_allocation: RuntimeValue) -> Result<Vec<u8>, RibosomeReturnCode> {
+        let not_an_allocation = RibosomeReturnCode::Failure(RibosomeErrorCode::NotAnAllocation);
+        match (self, encoded_allocation) {
+            (&MemoryManager::SinglePage(ref manager), RuntimeValue::I32(encoded)) => {
+                let allocation = decode_encoded_allocation(encoded as u32)?;
+                manager.read(allocation).map_err(|_| not_an_allocation)
+            }
+            (&MemoryManager::MultiPage(ref manager), RuntimeValue::I64(encoded)) => {
+                let allocation = decode_encoded_multi_page_allocation(encoded as u64)?;
+                manager.read(allocation).map_err(|_| not_an_allocation)
+            }
+            _ => Err(not_an_allocation),
+        }
+    }
+
+    /// Encode a return code the way the ABI passes it around, i.e. as a zero length allocation
+    pub fn encode_return_code(&self, return_code: RibosomeReturnCode) -> RuntimeValue {
+        match *self {
+            MemoryManager::SinglePage(_) => RuntimeValue::I32(match return_code {
+                RibosomeReturnCode::Success => 0,
+                RibosomeReturnCode::Failure(err_code) => err_code as i32,
+            }),
+            MemoryManager::MultiPage(_) => {
+                RuntimeValue::I64(encode_multi_page_return_code(return_code) as i64)
+            }
+        }
+    }
+}
 
 //--------------------------------------------------------------------------------------------------
 // WASM Memory Manager
@@ -27,24 +176,15 @@ pub struct SinglePageManager {
 /// using the i16 'offset' as return code and i16 'length' set to zero
 /// to indicate its a return code.
 /// Return code of 0 means success, while any other value means a failure and gives the error code.
-/// In the future, to handle bigger memory needs, we could do same with an i64 instead
-/// and handle multiple memory Pages.
+/// Zomes with bigger memory needs use the MultiPageManager instead.
 #[allow(unknown_lints)]
 #[allow(cast_lossless)]
 impl SinglePageManager {
-    pub fn new(wasm_instance: &ModuleRef) -> Self {
-        // get wasm memory reference from module
-        let wasm_memory = wasm_instance
-            .export_by_name("memory")
-            .expect("all modules compiled with rustc should have an export named 'memory'; qed")
-            .as_memory()
-            .expect("in module generated by rustc export named 'memory' should be a memory; qed")
-            .clone();
-
-        return SinglePageManager {
+    pub fn new(wasm_instance: &ModuleRef) -> Result<Self, HolochainError> {
+        Ok(SinglePageManager {
             stack: SinglePageStack::default(),
-            wasm_memory: wasm_memory.clone(),
-        };
+            wasm_memory: wasm_memory(wasm_instance)?,
+        })
     }
 
     /// Allocate on stack without writing in it
@@ -77,15 +217,237 @@ impl SinglePageManager {
 
         self.wasm_memory
             .set(mem_buf.offset() as u32, &data)
-            .expect("memory should be writable");
+            .map_err(|_| RibosomeErrorCode::OutOfMemory)?;
         Ok(mem_buf)
     }
 
     /// Read data somewhere in stack
-    pub fn read(&self, allocation: SinglePageAllocation) -> Vec<u8> {
-        return self
-            .wasm_memory
+    pub fn read(&self, allocation: SinglePageAllocation) -> Result<Vec<u8>, RibosomeErrorCode> {
+        self.wasm_memory
             .get(allocation.offset() as u32, allocation.length() as usize)
-            .expect("Successfully retrieve the result");
+            .map_err(|_| RibosomeErrorCode::NotAnAllocation)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+// WASM Multi Page Memory Manager
+//--------------------------------------------------------------------------------------------------
+
+#[derive(Clone, Debug)]
+/// Struct for managing a WASM Memory Instance with the multi-page ABI
+pub struct MultiPageManager {
+    /// where the next write goes
+    top: u32,
+    /// end of the pages the manager appended to the memory
+    end: u32,
+    max_memory_pages: u32,
+    wasm_memory: MemoryRef,
+}
+
+/// A Memory Manager for zomes that pass i64 encoded MultiPageAllocations around,
+/// with a u32 'offset' in the upper bits and a u32 'length' in the lower bits.
+/// The Ribosome doesn't share a stack with the zome: it writes in pages it appends to the memory
+/// itself, so they never collide with the data, stack or heap of the zome,
+/// while the zome hands over allocations anywhere in its memory, e.g. on its heap.
+/// The pages only live as long as the memory of the zome call.
+/// Return codes follow the same convention as with the SinglePageManager.
+impl MultiPageManager {
+    pub fn new(wasm_instance: &ModuleRef, max_memory_pages: u32) -> Result<Self, HolochainError> {
+        let wasm_memory = wasm_memory(wasm_instance)?;
+        let end = memory_size(&wasm_memory);
+        Ok(MultiPageManager {
+            top: end,
+            end,
+            max_memory_pages,
+            wasm_memory,
+        })
+    }
+
+    /// Write data on the pages of the manager, appending more if needed
+    pub fn write(&mut self, data: &[u8]) -> Result<MultiPageAllocation, RibosomeErrorCode> {
+        if data.is_empty() {
+            return Err(RibosomeErrorCode::ZeroSizedAllocation);
+        }
+        if data.len() as u64 > U32_MAX {
+            return Err(RibosomeErrorCode::OutOfMemory);
+        }
+        let length = data.len() as u32;
+        if u64::from(self.top) + u64::from(length) > u64::from(self.end) {
+            self.append_pages(length)?;
+        }
+        let allocation = MultiPageAllocation::new(self.top, length)?;
+
+        self.wasm_memory
+            .set(allocation.offset(), data)
+            .map_err(|_| RibosomeErrorCode::OutOfMemory)?;
+        self.top += length;
+        Ok(allocation)
+    }
+
+    /// Read data anywhere in memory
+    pub fn read(&self, allocation: MultiPageAllocation) -> Result<Vec<u8>, RibosomeErrorCode> {
+        self.wasm_memory
+            .get(allocation.offset(), allocation.length() as usize)
+            .map_err(|_| RibosomeErrorCode::NotAnAllocation)
+    }
+
+    /// Grow the memory by enough pages to hold the given length on top.
+    /// If the zome grew the memory since the last pages got appended,
+    /// the writes start over at the new end of the memory.
+    fn append_pages(&mut self, length: u32) -> Result<(), RibosomeErrorCode> {
+        let page_size = LINEAR_MEMORY_PAGE_SIZE.0 as u64;
+        let memory_end = memory_size(&self.wasm_memory);
+        let start = if memory_end == self.end {
+            self.top
+        } else {
+            memory_end
+        };
+        let missing = u64::from(start) + u64::from(length) - u64::from(memory_end);
+        let pages = (missing + page_size - 1) / page_size;
+        let current_pages = self.wasm_memory.current_size().0 as u64;
+        if current_pages + pages > u64::from(self.max_memory_pages) {
+            return Err(RibosomeErrorCode::OutOfMemory);
+        }
+        self.wasm_memory
+            .grow(Pages(pages as usize))
+            .map_err(|_| RibosomeErrorCode::OutOfMemory)?;
+        self.top = start;
+        self.end = memory_size(&self.wasm_memory);
+        Ok(())
+    }
+}
+
+/// Size of a wasm memory in bytes, capped at the 4GiB a u32 can address.
+fn memory_size(wasm_memory: &MemoryRef) -> u32 {
+    let bytes = wasm_memory.current_size().0 as u64 * LINEAR_MEMORY_PAGE_SIZE.0 as u64;
+    if bytes > U32_MAX {
+        return <u32>::max_value();
+    }
+    bytes as u32
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use holochain_core_types::error::HolochainError;
+    use nucleus::ribosome::{api::tests::run_wat, metering::ExecutionLimits};
+
+    const MULTI_PAGE_TEST_WAT: &str = r#"
+(module
+    (import "env" "hc_init_globals"
+        (func $init_globals (param i64) (result i64))
+    )
+    (import "env" "hc_property"
+        (func $property (param i64) (result i64))
+    )
+
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func (export "echo") (param $allocation i64) (result i64)
+        (get_local $allocation)
+    )
+
+    (func (export "init_globals") (param $allocation i64) (result i64)
+        (call $init_globals (get_local $allocation))
+    )
+
+    (func (export "property") (param $allocation i64) (result i64)
+        (call $property (get_local $allocation))
+    )
+
+    (func (export "single_page_echo") (param $allocation i32) (result i32)
+        (get_local $allocation)
+    )
+)
+"#;
+
+    fn call_with_limits(
+        fn_name: &str,
+        input: &str,
+        limits: ExecutionLimits,
+    ) -> Result<String, HolochainError> {
+        run_wat(MULTI_PAGE_TEST_WAT, fn_name, input, limits).0
+    }
+
+    fn call(fn_name: &str, input: &str) -> Result<String, HolochainError> {
+        call_with_limits(fn_name, input, ExecutionLimits::default())
+    }
+
+    #[test]
+    fn memory_abi_of_signature() {
+        assert_eq!(
+            Some(MemoryAbi::SinglePage),
+            MemoryAbi::of_signature(&MemoryAbi::SinglePage.signature())
+        );
+        assert_eq!(
+            Some(MemoryAbi::MultiPage),
+            MemoryAbi::of_signature(&MemoryAbi::MultiPage.signature())
+        );
+        assert_eq!(
+            None,
+            MemoryAbi::of_signature(&Signature::new(&[ValueType::I64][..], Some(ValueType::I32)))
+        );
+        assert_eq!(
+            None,
+            MemoryAbi::of_signature(&Signature::new(&[][..], Some(ValueType::I32)))
+        );
+    }
+
+    #[test]
+    /// test that multi-page zomes get and give back data bigger than a memory page
+    fn multi_page_round_trip() {
+        let input = "x".repeat(200_000);
+        assert_eq!(Ok(input.clone()), call("echo", &input));
+    }
+
+    #[test]
+    /// test that single-page zomes still work, and are still capped at 64KiB
+    fn single_page_compatibility() {
+        assert_eq!(Ok("fish".to_string()), call("single_page_echo", "fish"));
+        assert_eq!(
+            Err(HolochainError::RibosomeFailed(
+                RibosomeErrorCode::OutOfMemory.to_string()
+            )),
+            call("single_page_echo", &"x".repeat(200_000))
+        );
+    }
+
+    #[test]
+    /// test that multi-page zomes can call Zome API functions
+    fn multi_page_zome_api_function() {
+        let mut result = call("init_globals", "").expect("init_globals should be callable");
+        result.pop(); // Remove trailing character
+        let globals: ::serde_json::Value =
+            ::serde_json::from_str(&result).expect("globals should deserialize");
+        assert!(globals["dna_name"].is_string());
+
+        // error codes of Zome API functions come back in the multi-page encoding
+        assert_eq!(
+            Err(HolochainError::RibosomeFailed(
+                RibosomeErrorCode::ArgumentDeserializationFailed.to_string()
+            )),
+            call("property", "not json")
+        );
+    }
+
+    #[test]
+    /// test that the pages the ribosome appends count towards the memory limit
+    fn multi_page_memory_limit() {
+        let limits = ExecutionLimits {
+            max_memory_pages: 3,
+            ..Default::default()
+        };
+        let input = "x".repeat(100_000);
+        assert_eq!(
+            Ok(input.clone()),
+            call_with_limits("echo", &input, limits.clone())
+        );
+        assert_eq!(
+            Err(HolochainError::RibosomeFailed(
+                RibosomeErrorCode::OutOfMemory.to_string()
+            )),
+            call_with_limits("echo", &"x".repeat(200_000), limits)
+        );
     }
 }
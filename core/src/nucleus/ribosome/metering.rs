@@ -0,0 +1,535 @@
+//! Metering of the execution of zome wasm.
+//! Before a module gets instantiated it is instrumented with counters that keep it within
+//! the ExecutionLimits of the instance:
+//! - a budget of instructions, charged at the start of every straight-line piece of code,
+//! - the depth of calls between its functions,
+//! - the number of memory pages it may grow its memory to.
+//!
+//! The counters live in globals the instrumentation adds. When one of them exceeds its limit
+//! the module calls the host function `__hc_limit_exceeded`, which traps with the limit,
+//! so a zome call fails with a HolochainError::ExecutionLimitExceeded instead of hanging.
+
+use holochain_core_types::error::HolochainError;
+use parity_wasm::elements::{
+    BlockType, External, FunctionType, GlobalEntry, GlobalSection, GlobalType, ImportEntry,
+    ImportSection, InitExpr, Instruction, Internal, Module, Section, Type, TypeSection, ValueType,
+};
+use std::{fmt, i64};
+use wasmi::HostError;
+
+/// Name of the host function instrumented modules import from "env".
+pub const LIMIT_EXCEEDED_IMPORT: &str = "__hc_limit_exceeded";
+/// Index the host function gets resolved to, outside of the range of the Zome API functions.
+pub const LIMIT_EXCEEDED_INDEX: usize = 0xffff;
+
+const INSTRUCTIONS_LIMIT_KIND: i32 = 0;
+const CALL_DEPTH_LIMIT_KIND: i32 = 1;
+const MEMORY_PAGES_LIMIT_KIND: i32 = 2;
+
+/// Resources a single zome call may use.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionLimits {
+    /// wasm instructions a single call into a zome may execute
+    pub max_instructions: u64,
+    /// 64KiB pages of memory a zome may have
+    pub max_memory_pages: u32,
+    /// depth of nested calls between the functions of a zome
+    pub max_call_depth: u32,
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        ExecutionLimits {
+            max_instructions: 1_000_000_000,
+            max_memory_pages: 1024,
+            max_call_depth: 1024,
+        }
+    }
+}
+
+/// The limit a zome call exceeded, gets raised as a trap by the host function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LimitExceeded {
+    Instructions(u64),
+    CallDepth(u32),
+    MemoryPages(u32),
+}
+
+impl LimitExceeded {
+    /// The limit the host function got called for by an instrumented module.
+    pub fn from_kind(kind: i32, limits: &ExecutionLimits) -> Option<Self> {
+        match kind {
+            INSTRUCTIONS_LIMIT_KIND => Some(LimitExceeded::Instructions(limits.max_instructions)),
+            CALL_DEPTH_LIMIT_KIND => Some(LimitExceeded::CallDepth(limits.max_call_depth)),
+            MEMORY_PAGES_LIMIT_KIND => Some(LimitExceeded::MemoryPages(limits.max_memory_pages)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LimitExceeded::Instructions(limit) => write!(f, "the limit of {} instructions", limit),
+            LimitExceeded::CallDepth(limit) => write!(f, "the call depth limit of {}", limit),
+            LimitExceeded::MemoryPages(limit) => {
+                write!(f, "the limit of {} memory pages", limit)
+            }
+        }
+    }
+}
+
+impl HostError for LimitExceeded {}
+
+/// Adds the counters for the given limits to a module.
+/// Fails if the module starts out with more memory than the limit allows.
+pub fn instrument(mut module: Module, limits: &ExecutionLimits) -> Result<Module, HolochainError> {
+    let initial_pages = module
+        .memory_section()
+        .and_then(|memories| memories.entries().first())
+        .map(|memory| memory.limits().initial())
+        .unwrap_or(0);
+    if initial_pages > limits.max_memory_pages {
+        return Err(HolochainError::ExecutionLimitExceeded(format!(
+            "wasm needs {} memory pages, more than {}",
+            initial_pages,
+            LimitExceeded::MemoryPages(limits.max_memory_pages)
+        )));
+    }
+
+    // the import of the host function takes the index after the imported functions,
+    // so all functions defined in the module move up by one
+    let imported_functions = count_imports(&module, |external| match *external {
+        External::Function(_) => true,
+        _ => false,
+    });
+    let limit_fn = imported_functions;
+    let shift = |index: u32| {
+        if index >= imported_functions {
+            index + 1
+        } else {
+            index
+        }
+    };
+
+    let globals = count_imports(&module, |external| match *external {
+        External::Global(_) => true,
+        _ => false,
+    }) + module
+        .global_section()
+        .map(|section| section.entries().len() as u32)
+        .unwrap_or(0);
+    let counters = Counters {
+        gas: globals,
+        depth: globals + 1,
+        scratch: globals + 2,
+        limit_fn,
+        limits: limits.clone(),
+    };
+
+    for section in module.sections_mut() {
+        match *section {
+            Section::Code(ref mut code) => {
+                for body in code.bodies_mut() {
+                    let instructions = body.code_mut().elements_mut();
+                    let metered = counters.meter(instructions.drain(..), imported_functions);
+                    *instructions = metered;
+                }
+            }
+            Section::Export(ref mut exports) => {
+                for export in exports.entries_mut() {
+                    if let Internal::Function(ref mut index) = *export.internal_mut() {
+                        *index = shift(*index);
+                    }
+                }
+            }
+            Section::Element(ref mut elements) => {
+                for segment in elements.entries_mut() {
+                    for index in segment.members_mut() {
+                        *index = shift(*index);
+                    }
+                }
+            }
+            Section::Start(ref mut index) => *index = shift(*index),
+            _ => {}
+        }
+    }
+
+    let limit_fn_type = add_type(&mut module, FunctionType::new(vec![ValueType::I32], None));
+    add_import(
+        &mut module,
+        ImportEntry::new(
+            "env".to_string(),
+            LIMIT_EXCEEDED_IMPORT.to_string(),
+            External::Function(limit_fn_type),
+        ),
+    );
+
+    let max_instructions = if limits.max_instructions > i64::MAX as u64 {
+        i64::MAX
+    } else {
+        limits.max_instructions as i64
+    };
+    add_globals(
+        &mut module,
+        vec![
+            mutable_global(ValueType::I64, Instruction::I64Const(max_instructions)),
+            mutable_global(ValueType::I32, Instruction::I32Const(0)),
+            mutable_global(ValueType::I32, Instruction::I32Const(0)),
+        ],
+    );
+
+    Ok(module)
+}
+
+/// Indexes of the things the instrumentation adds to a module.
+struct Counters {
+    /// global holding the instructions left
+    gas: u32,
+    /// global holding the current call depth
+    depth: u32,
+    /// global to keep the operand of memory.grow in while checking it
+    scratch: u32,
+    /// the imported host function
+    limit_fn: u32,
+    limits: ExecutionLimits,
+}
+
+impl Counters {
+    /// Instruments the code of a function.
+    fn meter<I: Iterator<Item = Instruction>>(
+        &self,
+        instructions: I,
+        imported_functions: u32,
+    ) -> Vec<Instruction> {
+        let mut metered = Vec::new();
+        let mut segment = Vec::new();
+        let mut segment_cost = 0;
+        for instruction in instructions {
+            segment_cost += 1;
+            let ends_segment = ends_segment(&instruction);
+            match instruction {
+                Instruction::Call(index) if index < imported_functions => {
+                    segment.push(Instruction::Call(index))
+                }
+                Instruction::Call(index) => {
+                    self.enter_call(&mut segment);
+                    segment.push(Instruction::Call(index + 1));
+                    self.leave_call(&mut segment);
+                }
+                Instruction::CallIndirect(type_index, reserved) => {
+                    self.enter_call(&mut segment);
+                    segment.push(Instruction::CallIndirect(type_index, reserved));
+                    self.leave_call(&mut segment);
+                }
+                Instruction::GrowMemory(reserved) => {
+                    self.check_grow_memory(&mut segment);
+                    segment.push(Instruction::GrowMemory(reserved));
+                }
+                instruction => segment.push(instruction),
+            }
+            if ends_segment {
+                self.charge(&mut metered, segment_cost);
+                metered.append(&mut segment);
+                segment_cost = 0;
+            }
+        }
+        if !segment.is_empty() {
+            self.charge(&mut metered, segment_cost);
+            metered.append(&mut segment);
+        }
+        metered
+    }
+
+    /// Subtracts the cost of the following code from the instructions left.
+    fn charge(&self, code: &mut Vec<Instruction>, cost: i64) {
+        code.extend(vec![
+            Instruction::GetGlobal(self.gas),
+            Instruction::I64Const(cost),
+            Instruction::I64Sub,
+            Instruction::SetGlobal(self.gas),
+            Instruction::GetGlobal(self.gas),
+            Instruction::I64Const(0),
+            Instruction::I64LtS,
+        ]);
+        self.exceeded_if_true(code, INSTRUCTIONS_LIMIT_KIND);
+    }
+
+    fn enter_call(&self, code: &mut Vec<Instruction>) {
+        code.extend(vec![
+            Instruction::GetGlobal(self.depth),
+            Instruction::I32Const(1),
+            Instruction::I32Add,
+            Instruction::SetGlobal(self.depth),
+            Instruction::GetGlobal(self.depth),
+            Instruction::I32Const(as_i32(self.limits.max_call_depth)),
+            Instruction::I32GtU,
+        ]);
+        self.exceeded_if_true(code, CALL_DEPTH_LIMIT_KIND);
+    }
+
+    fn leave_call(&self, code: &mut Vec<Instruction>) {
+        code.extend(vec![
+            Instruction::GetGlobal(self.depth),
+            Instruction::I32Const(1),
+            Instruction::I32Sub,
+            Instruction::SetGlobal(self.depth),
+        ]);
+    }
+
+    /// Expects the number of pages to grow by on the stack and leaves it there.
+    fn check_grow_memory(&self, code: &mut Vec<Instruction>) {
+        code.extend(vec![
+            Instruction::SetGlobal(self.scratch),
+            Instruction::CurrentMemory(0),
+            Instruction::GetGlobal(self.scratch),
+            Instruction::I32Add,
+            Instruction::I32Const(as_i32(self.limits.max_memory_pages)),
+            Instruction::I32GtU,
+        ]);
+        self.exceeded_if_true(code, MEMORY_PAGES_LIMIT_KIND);
+        code.push(Instruction::GetGlobal(self.scratch));
+    }
+
+    fn exceeded_if_true(&self, code: &mut Vec<Instruction>, kind: i32) {
+        code.extend(vec![
+            Instruction::If(BlockType::NoResult),
+            Instruction::I32Const(kind),
+            Instruction::Call(self.limit_fn),
+            Instruction::End,
+        ]);
+    }
+}
+
+/// Whether the instruction is the last one that surely gets executed
+/// once the instruction before it got executed.
+fn ends_segment(instruction: &Instruction) -> bool {
+    match *instruction {
+        Instruction::Block(_)
+        | Instruction::Loop(_)
+        | Instruction::If(_)
+        | Instruction::Else
+        | Instruction::End
+        | Instruction::Br(_)
+        | Instruction::BrIf(_)
+        | Instruction::BrTable(..)
+        | Instruction::Return
+        | Instruction::Unreachable => true,
+        _ => false,
+    }
+}
+
+/// Unsigned limits get compared unsigned, so they may use all 32 bits.
+fn as_i32(limit: u32) -> i32 {
+    limit as i32
+}
+
+fn mutable_global(value_type: ValueType, init: Instruction) -> GlobalEntry {
+    GlobalEntry::new(
+        GlobalType::new(value_type, true),
+        InitExpr::new(vec![init, Instruction::End]),
+    )
+}
+
+fn count_imports<F: Fn(&External) -> bool>(module: &Module, is_counted: F) -> u32 {
+    module
+        .import_section()
+        .map(|imports| {
+            imports
+                .entries()
+                .iter()
+                .filter(|entry| is_counted(entry.external()))
+                .count() as u32
+        }).unwrap_or(0)
+}
+
+/// Adds a function type and returns its index.
+fn add_type(module: &mut Module, function_type: FunctionType) -> u32 {
+    if module.type_section().is_none() {
+        insert_section(module, Section::Type(TypeSection::default()));
+    }
+    for section in module.sections_mut() {
+        if let Section::Type(ref mut types) = *section {
+            types.types_mut().push(Type::Function(function_type));
+            return (types.types().len() - 1) as u32;
+        }
+    }
+    unreachable!("type section was just added")
+}
+
+fn add_import(module: &mut Module, entry: ImportEntry) {
+    if module.import_section().is_none() {
+        insert_section(module, Section::Import(ImportSection::default()));
+    }
+    for section in module.sections_mut() {
+        if let Section::Import(ref mut imports) = *section {
+            imports.entries_mut().push(entry);
+            return;
+        }
+    }
+}
+
+fn add_globals(module: &mut Module, entries: Vec<GlobalEntry>) {
+    if module.global_section().is_none() {
+        insert_section(module, Section::Global(GlobalSection::default()));
+    }
+    for section in module.sections_mut() {
+        if let Section::Global(ref mut globals) = *section {
+            globals.entries_mut().extend(entries);
+            return;
+        }
+    }
+}
+
+/// Position of a section in the order wasm requires, custom sections may go anywhere.
+fn section_order(section: &Section) -> Option<u8> {
+    match *section {
+        Section::Type(_) => Some(1),
+        Section::Import(_) => Some(2),
+        Section::Function(_) => Some(3),
+        Section::Table(_) => Some(4),
+        Section::Memory(_) => Some(5),
+        Section::Global(_) => Some(6),
+        Section::Export(_) => Some(7),
+        Section::Start(_) => Some(8),
+        Section::Element(_) => Some(9),
+        Section::Code(_) => Some(10),
+        Section::Data(_) => Some(11),
+        _ => None,
+    }
+}
+
+fn insert_section(module: &mut Module, section: Section) {
+    let order = section_order(&section);
+    let sections = module.sections_mut();
+    let position = sections
+        .iter()
+        .position(|existing| match section_order(existing) {
+            Some(existing_order) => Some(existing_order) > order,
+            None => false,
+        }).unwrap_or_else(|| sections.len());
+    sections.insert(position, section);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use nucleus::ribosome::api::tests::run_wat;
+    use serde_json;
+
+    const METERING_TEST_WAT: &str = r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+
+    (func (export "loop_forever") (param $allocation i32) (result i32)
+        (loop $forever
+            (br $forever)
+        )
+        (i32.const 0)
+    )
+
+    (func $count_down (param $n i32) (result i32)
+        (if (result i32)
+            (i32.eqz (get_local $n))
+            (then (i32.const 0))
+            (else (call $count_down (i32.sub (get_local $n) (i32.const 1))))
+        )
+    )
+
+    (func (export "recurse_10") (param $allocation i32) (result i32)
+        (call $count_down (i32.const 10))
+    )
+
+    (func (export "recurse_forever") (param $allocation i32) (result i32)
+        (call $count_down (i32.const -1))
+    )
+
+    (func (export "grow_4") (param $allocation i32) (result i32)
+        (drop (grow_memory (i32.const 4)))
+        (i32.const 0)
+    )
+)
+"#;
+
+    fn call_with_limits(fn_name: &str, limits: ExecutionLimits) -> Result<String, HolochainError> {
+        run_wat(METERING_TEST_WAT, fn_name, "", limits).0
+    }
+
+    fn assert_exceeds(fn_name: &str, limits: ExecutionLimits, expected: &str) {
+        match call_with_limits(fn_name, limits) {
+            Err(HolochainError::ExecutionLimitExceeded(message)) => {
+                assert!(
+                    message.contains(expected),
+                    "unexpected message: {}",
+                    message
+                )
+            }
+            result => panic!("expected {} to exceed a limit, got {:?}", fn_name, result),
+        }
+    }
+
+    #[test]
+    fn calls_within_limits_succeed() {
+        let limits = ExecutionLimits {
+            max_instructions: 10_000,
+            max_memory_pages: 5,
+            max_call_depth: 11,
+        };
+        assert_eq!(
+            call_with_limits("recurse_10", limits.clone()),
+            Ok(String::new())
+        );
+        assert_eq!(call_with_limits("grow_4", limits), Ok(String::new()));
+    }
+
+    #[test]
+    fn infinite_loops_exhaust_the_instructions() {
+        let limits = ExecutionLimits {
+            max_instructions: 10_000,
+            ..Default::default()
+        };
+        assert_exceeds("loop_forever", limits, "the limit of 10000 instructions");
+    }
+
+    #[test]
+    fn deep_recursion_exceeds_the_call_depth() {
+        let limits = ExecutionLimits {
+            max_call_depth: 10,
+            ..Default::default()
+        };
+        assert_exceeds("recurse_10", limits.clone(), "the call depth limit of 10");
+        assert_exceeds("recurse_forever", limits, "the call depth limit of 10");
+    }
+
+    #[test]
+    fn growing_memory_beyond_the_limit_fails() {
+        let limits = ExecutionLimits {
+            max_memory_pages: 4,
+            ..Default::default()
+        };
+        assert_exceeds("grow_4", limits, "the limit of 4 memory pages");
+    }
+
+    #[test]
+    fn modules_starting_with_too_much_memory_fail() {
+        let limits = ExecutionLimits {
+            max_memory_pages: 0,
+            ..Default::default()
+        };
+        assert_exceeds("recurse_10", limits, "wasm needs 1 memory pages");
+    }
+
+    #[test]
+    fn limits_default_for_missing_fields() {
+        let limits: ExecutionLimits = serde_json::from_str(r#"{"max_call_depth": 64}"#).unwrap();
+        assert_eq!(
+            limits,
+            ExecutionLimits {
+                max_call_depth: 64,
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Cache of the parsed, metered and validated wasm modules of the zomes of an instance,
+//! so that calls into a zome only have to instantiate its module with fresh memory.
+
+use holochain_core_types::{error::HolochainError, hash::HashString};
+use multihash::Hash as Multihash;
+use nucleus::ribosome::metering::{self, ExecutionLimits};
+use parity_wasm::elements::{deserialize_buffer, Module as ParityModule};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use wasmi::Module;
+
+#[derive(Default)]
+struct CachedModules {
+    /// hash of the DNA the cached modules belong to
+    dna_hash: Option<HashString>,
+    /// limits the cached modules are metered for
+    limits: ExecutionLimits,
+    /// by zome name and hash of the wasm
+    modules: HashMap<(String, HashString), Arc<Module>>,
+}
+
+/// Holds the modules of the zomes of one DNA.
+/// Gets emptied as soon as it is asked for a module of a different DNA or different limits.
+#[derive(Default)]
+pub struct ModuleCache {
+    cached: Mutex<CachedModules>,
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the module of the given zome wasm, parsing it only if it isn't cached yet.
+    pub fn get_or_load(
+        &self,
+        dna_hash: Option<&HashString>,
+        limits: &ExecutionLimits,
+        zome_name: &str,
+        wasm: &[u8],
+    ) -> Result<Arc<Module>, HolochainError> {
+        let key = (
+            zome_name.to_string(),
+            HashString::encode_from_bytes(wasm, Multihash::SHA2256),
+        );
+        {
+            let mut cached = self.cached();
+            if cached.dna_hash.as_ref() != dna_hash || &cached.limits != limits {
+                cached.dna_hash = dna_hash.cloned();
+                cached.limits = limits.clone();
+                cached.modules.clear();
+            }
+            if let Some(module) = cached.modules.get(&key) {
+                return Ok(module.clone());
+            }
+        }
+
+        // parse outside of the lock so calls into other zomes don't have to wait for it
+        let module = Arc::new(load(limits, zome_name, wasm)?);
+
+        let mut cached = self.cached();
+        if cached.dna_hash.as_ref() != dna_hash || &cached.limits != limits {
+            // the DNA or the limits changed while parsing, don't mix them up
+            return Ok(module);
+        }
+        let module = cached.modules.entry(key).or_insert(module).clone();
+        Ok(module)
+    }
+
+    /// Number of cached modules.
+    pub fn len(&self) -> usize {
+        self.cached().modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops all cached modules.
+    pub fn clear(&self) {
+        let mut cached = self.cached();
+        cached.dna_hash = None;
+        cached.modules.clear();
+    }
+
+    fn cached(&self) -> MutexGuard<CachedModules> {
+        // parsed modules are only ever inserted whole, so the map stays consistent
+        self.cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Parses and meters the wasm of a zome.
+fn load(limits: &ExecutionLimits, zome_name: &str, wasm: &[u8]) -> Result<Module, HolochainError> {
+    let invalid_wasm = |error: String| {
+        HolochainError::RibosomeFailed(format!("Invalid wasm in zome '{}': {}", zome_name, error))
+    };
+    let module: ParityModule =
+        deserialize_buffer(wasm).map_err(|error| invalid_wasm(error.to_string()))?;
+    let metered = metering::instrument(module, limits).map_err(|error| match error {
+        HolochainError::ExecutionLimitExceeded(message) => {
+            HolochainError::ExecutionLimitExceeded(format!("Zome '{}': {}", zome_name, message))
+        }
+        error => error,
+    })?;
+    Module::from_parity_wasm_module(metered).map_err(|error| invalid_wasm(error.to_string()))
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate wabt;
+
+    use self::wabt::Wat2Wasm;
+    use super::*;
+    use nucleus::ribosome::{
+        api::{tests::test_zome_api_function, ZomeApiFunction},
+        Defn,
+    };
+
+    fn test_wasm(answer: i32) -> Vec<u8> {
+        Wat2Wasm::new()
+            .canonicalize_lebs(false)
+            .write_debug_names(true)
+            .convert(format!(
+                r#"(module (func (export "answer") (result i32) (i32.const {})))"#,
+                answer
+            )).unwrap()
+            .as_ref()
+            .to_vec()
+    }
+
+    #[test]
+    fn reuses_modules_of_the_same_zome_and_wasm() {
+        let cache = ModuleCache::new();
+        let limits = ExecutionLimits::default();
+        let dna_hash = HashString::from("QmDna");
+        let wasm = test_wasm(42);
+
+        let module1 = cache
+            .get_or_load(Some(&dna_hash), &limits, "zome", &wasm)
+            .unwrap();
+        let module2 = cache
+            .get_or_load(Some(&dna_hash), &limits, "zome", &wasm)
+            .unwrap();
+        assert!(Arc::ptr_eq(&module1, &module2));
+        assert_eq!(cache.len(), 1);
+
+        let other_zome = cache
+            .get_or_load(Some(&dna_hash), &limits, "other", &wasm)
+            .unwrap();
+        assert!(!Arc::ptr_eq(&module1, &other_zome));
+        let other_wasm = cache
+            .get_or_load(Some(&dna_hash), &limits, "zome", &test_wasm(23))
+            .unwrap();
+        assert!(!Arc::ptr_eq(&module1, &other_wasm));
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn drops_modules_when_the_dna_or_the_limits_change() {
+        let cache = ModuleCache::new();
+        let limits = ExecutionLimits::default();
+        let wasm = test_wasm(42);
+
+        let module1 = cache
+            .get_or_load(Some(&HashString::from("QmDna1")), &limits, "zome", &wasm)
+            .unwrap();
+        cache
+            .get_or_load(Some(&HashString::from("QmDna1")), &limits, "other", &wasm)
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+
+        let module2 = cache
+            .get_or_load(Some(&HashString::from("QmDna2")), &limits, "zome", &wasm)
+            .unwrap();
+        assert!(!Arc::ptr_eq(&module1, &module2));
+        assert_eq!(cache.len(), 1);
+
+        let other_limits = ExecutionLimits {
+            max_instructions: 1000,
+            ..Default::default()
+        };
+        let module3 = cache
+            .get_or_load(
+                Some(&HashString::from("QmDna2")),
+                &other_limits,
+                "zome",
+                &wasm,
+            ).unwrap();
+        assert!(!Arc::ptr_eq(&module2, &module3));
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn zome_calls_use_the_cache_of_the_context() {
+        let (_, context) = test_zome_api_function(ZomeApiFunction::InitGlobals.as_str(), vec![]);
+        assert_eq!(context.module_cache.len(), 1);
+    }
+
+    #[test]
+    fn fails_on_invalid_wasm() {
+        let cache = ModuleCache::new();
+        let limits = ExecutionLimits::default();
+        let result = cache.get_or_load(None, &limits, "zome", &[0, 1, 2, 3]);
+        match result {
+            Err(HolochainError::RibosomeFailed(message)) => {
+                assert!(message.starts_with("Invalid wasm in zome 'zome'"))
+            }
+            _ => panic!("expected the invalid wasm to fail"),
+        }
+        assert!(cache.is_empty());
+    }
+}
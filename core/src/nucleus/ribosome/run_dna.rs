@@ -2,20 +2,33 @@ use context::Context;
 use holochain_core_types::error::{
     HcResult, HolochainError, RibosomeErrorCode, RibosomeReturnCode,
 };
-use holochain_wasm_utils::memory_allocation::decode_encoded_allocation;
+use logger::{LogLevel, LogRecord};
 use nucleus::{
-    ribosome::{api::ZomeApiFunction, memory::SinglePageManager, Runtime},
+    ribosome::{
+        api::{abort::ZomeAborted, ZomeApiFunction},
+        memory::{MemoryAbi, MemoryManager},
+        metering::{LimitExceeded, LIMIT_EXCEEDED_IMPORT, LIMIT_EXCEEDED_INDEX},
+        Runtime, RuntimeError,
+    },
     ZomeFnCall, ZomeFnResult,
 };
 use std::{str::FromStr, sync::Arc};
 use wasmi::{
-    self, Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, ModuleImportResolver,
-    ModuleInstance, NopExternals, RuntimeValue, Signature, ValueType,
+    Error as InterpreterError, FuncInstance, FuncRef, ImportsBuilder, ModuleImportResolver,
+    ModuleInstance, NopExternals, RuntimeValue, Signature, TrapKind, ValueType,
 };
 
 /// Executes an exposed zome function in a wasm binary.
 /// Multithreaded function
-/// panics if wasm binary isn't valid.
+/// The parsed wasm module gets taken from the module cache of the context, so every call
+/// only instantiates it with fresh memory.
+/// Fails with HolochainError::ExecutionLimitExceeded if the call exceeds the execution limits
+/// of the context.
+/// Zome functions of the signature (i64) -> i64 get called with the multi-page memory ABI,
+/// any other with the single-page ABI zomes used to be built against.
+/// A zome that can't be instantiated or that traps fails the call with
+/// HolochainError::RibosomeFailed telling why, the host never panics on its behalf.
+/// A zome that calls abort fails it with HolochainError::ZomeAborted reporting where.
 pub fn run_dna(
     dna_name: &str,
     context: Arc<Context>,
@@ -23,22 +36,36 @@ pub fn run_dna(
     zome_call: &ZomeFnCall,
     parameters: Option<Vec<u8>>,
 ) -> ZomeFnResult {
-    // Create wasm module from wasm binary
-    let module = wasmi::Module::from_buffer(wasm).expect("wasm binary should be valid");
+    // Get wasm module of wasm binary, parsing it if it isn't cached yet
+    let dna_hash = context.state().and_then(|state| state.nucleus().dna_hash());
+    let module = context.module_cache.get_or_load(
+        dna_hash.as_ref(),
+        &context.execution_limits,
+        &zome_call.zome_name,
+        &wasm,
+    )?;
 
     // invoke_index and resolve_func work together to enable callable host functions
     // within WASM modules, which is how the core API functions
     // read about the Externals trait for more detail
 
     // Correlate the names of the core ZomeApiFunction's with their indexes
-    // and declare its function signature (which is always the same)
+    // and declare its function signature (which is the same but for the memory ABI)
     struct RuntimeModuleImportResolver;
     impl ModuleImportResolver for RuntimeModuleImportResolver {
         fn resolve_func(
             &self,
             field_name: &str,
-            _signature: &Signature,
+            signature: &Signature,
         ) -> Result<FuncRef, InterpreterError> {
+            // imported by the metering instrumentation
+            if field_name == LIMIT_EXCEEDED_IMPORT {
+                return Ok(FuncInstance::alloc_host(
+                    Signature::new(&[ValueType::I32][..], None),
+                    LIMIT_EXCEEDED_INDEX,
+                ));
+            }
+
             let api_fn = match ZomeApiFunction::from_str(&field_name) {
                 Ok(api_fn) => api_fn,
                 Err(_) => {
@@ -64,9 +91,11 @@ pub fn run_dna(
                     ),
                     api_fn as usize,
                 )),
-                // All of our Zome API Functions have the same signature
+                // All of our Zome API Functions have the same signature in a memory ABI
                 _ => Ok(FuncInstance::alloc_host(
-                    Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                    MemoryAbi::of_signature(signature)
+                        .unwrap_or(MemoryAbi::SinglePage)
+                        .signature(),
                     api_fn as usize,
                 )),
             }
@@ -79,16 +108,39 @@ pub fn run_dna(
 
     // Create module instance from wasm module, and start it if start is defined
     let wasm_instance = ModuleInstance::new(&module, &imports)
-        .expect("Failed to instantiate module")
+        .map_err(|error| {
+            HolochainError::RibosomeFailed(format!(
+                "Could not instantiate zome '{}': {}",
+                zome_call.zome_name, error
+            ))
+        })?
         .run_start(&mut NopExternals)
         .map_err(|_| HolochainError::RibosomeFailed("Module failed to start".to_string()))?;
 
     // write input arguments for module call in memory Buffer
     let input_parameters: Vec<_> = parameters.unwrap_or_default();
 
+    // the called function tells which memory ABI the zome is built against
+    let abi = wasm_instance
+        .export_by_name(&zome_call.fn_name)
+        .and_then(|export| {
+            export
+                .as_func()
+                .and_then(|func| MemoryAbi::of_signature(func.signature()))
+        }).unwrap_or(MemoryAbi::SinglePage);
+    let max_memory_pages = context.execution_limits.max_memory_pages;
+
+    let memory_manager =
+        MemoryManager::new(abi, &wasm_instance, max_memory_pages).map_err(|error| {
+            HolochainError::RibosomeFailed(format!(
+                "Could not instantiate zome '{}': {}",
+                zome_call.zome_name, error
+            ))
+        })?;
+
     // instantiate runtime struct for passing external state data over wasm but not to wasm
     let mut runtime = Runtime {
-        memory_manager: SinglePageManager::new(&wasm_instance),
+        memory_manager,
         context,
         zome_call: zome_call.clone(),
         dna_name: dna_name.to_string(),
@@ -96,24 +148,26 @@ pub fn run_dna(
 
     // Write input arguments in wasm memory
     // scope for mutable borrow of runtime
-    let encoded_allocation_of_input: u32;
+    let encoded_allocation_of_input: RuntimeValue;
     {
         let mut_runtime = &mut runtime;
         let maybe_allocation_of_input = mut_runtime.memory_manager.write(&input_parameters);
         encoded_allocation_of_input = match maybe_allocation_of_input {
             // No allocation to write is ok
-            Err(RibosomeErrorCode::ZeroSizedAllocation) => 0,
+            Err(RibosomeErrorCode::ZeroSizedAllocation) => mut_runtime
+                .memory_manager
+                .encode_return_code(RibosomeReturnCode::Success),
             // Any other error is memory related
             Err(err) => {
                 return Err(HolochainError::RibosomeFailed(err.to_string()));
             }
-            // Write successful, encode allocation
-            Ok(allocation_of_input) => allocation_of_input.encode(),
+            // Write successful, allocation is already encoded
+            Ok(encoded_allocation_of_input) => encoded_allocation_of_input,
         }
     }
 
     // scope for mutable borrow of runtime
-    let returned_encoded_allocation: u32;
+    let returned_encoded_allocation: RuntimeValue;
     {
         let mut_runtime = &mut runtime;
 
@@ -123,20 +177,23 @@ pub fn run_dna(
         returned_encoded_allocation = wasm_instance
             .invoke_export(
                 zome_call.fn_name.clone().as_str(),
-                &[RuntimeValue::I32(encoded_allocation_of_input as i32)],
+                &[encoded_allocation_of_input],
                 mut_runtime,
             )
-            .map_err(|err| HolochainError::RibosomeFailed(err.to_string()))?
-            .unwrap()
-            .try_into()
-            .unwrap();
+            .map_err(|err| execution_error(&err, zome_call))?
+            .ok_or_else(|| {
+                HolochainError::RibosomeFailed(format!(
+                    "Zome function '{}' of zome '{}' returned no value",
+                    zome_call.fn_name, zome_call.zome_name
+                ))
+            })?;
     }
 
     // Handle result returned by called zome function
-    let maybe_allocation = decode_encoded_allocation(returned_encoded_allocation);
+    let maybe_result = runtime.memory_manager.read(returned_encoded_allocation);
     let return_log_msg: String;
     let return_result: HcResult<String>;
-    match maybe_allocation {
+    match maybe_result {
         // Nothing in memory, return result depending on return_code received.
         Err(return_code) => {
             return_log_msg = return_code.to_string();
@@ -147,9 +204,8 @@ pub fn run_dna(
                 }
             };
         }
-        // Something in memory, try to read and return it
-        Ok(valid_allocation) => {
-            let result = runtime.memory_manager.read(valid_allocation);
+        // Something in memory, try to return it
+        Ok(result) => {
             let maybe_zome_result = String::from_utf8(result);
             match maybe_zome_result {
                 Err(err) => {
@@ -164,12 +220,226 @@ pub fn run_dna(
         }
     };
     // Log & done
-    runtime
-        .context
-        .log(&format!(
-            "Zome Function '{}' returned: {}",
-            zome_call.fn_name, return_log_msg,
-        ))
-        .expect("Logger should work");
+    runtime.context.log_record(
+        LogRecord::new(
+            LogLevel::Info,
+            "nucleus::ribosome",
+            format!(
+                "Zome Function '{}' returned: {}",
+                zome_call.fn_name, return_log_msg,
+            ),
+        ).zome(zome_call.zome_name.clone())
+        .function(zome_call.fn_name.clone()),
+    )?;
     return return_result;
 }
+
+/// Tells zome calls that exceeded their execution limits apart from those that failed.
+/// Calls the host trapped keep the reason it gave, aborted ones the report of the zome.
+fn execution_error(error: &InterpreterError, zome_call: &ZomeFnCall) -> HolochainError {
+    if let Some(aborted) = error
+        .as_host_error()
+        .and_then(|host_error| host_error.downcast_ref::<ZomeAborted>())
+    {
+        return HolochainError::ZomeAborted(aborted.0.clone());
+    }
+    if let Some(runtime_error) = error
+        .as_host_error()
+        .and_then(|host_error| host_error.downcast_ref::<RuntimeError>())
+    {
+        return HolochainError::RibosomeFailed(format!(
+            "Zome function '{}' of zome '{}' trapped: {}",
+            zome_call.fn_name, zome_call.zome_name, runtime_error
+        ));
+    }
+    let exceeded = error
+        .as_host_error()
+        .and_then(|host_error| host_error.downcast_ref::<LimitExceeded>())
+        .map(|limit| limit.to_string())
+        .or_else(|| match *error {
+            InterpreterError::Trap(ref trap) => match *trap.kind() {
+                TrapKind::StackOverflow => Some("the call stack limit".to_string()),
+                _ => None,
+            },
+            _ => None,
+        });
+    match exceeded {
+        Some(limit) => HolochainError::ExecutionLimitExceeded(format!(
+            "Zome function '{}' of zome '{}' exceeded {}",
+            zome_call.fn_name, zome_call.zome_name, limit
+        )),
+        None => HolochainError::RibosomeFailed(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use nucleus::ribosome::{api::tests::run_wat, metering::ExecutionLimits};
+
+    /// Calls main of a zome written in the given WAT.
+    fn call_wat(wat: &str) -> Result<String, HolochainError> {
+        run_wat(wat, "main", "", ExecutionLimits::default()).0
+    }
+
+    fn assert_fails(wat: &str, expected: &str) {
+        match call_wat(wat) {
+            Err(HolochainError::RibosomeFailed(message)) => assert!(
+                message.contains(expected),
+                "unexpected message: {}",
+                message
+            ),
+            result => panic!("expected the zome to fail, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn zome_without_memory_fails() {
+        assert_fails(
+            r#"
+(module
+    (func (export "main") (param $allocation i32) (result i32)
+        (i32.const 0)
+    )
+)
+"#,
+            "Could not instantiate zome 'test_zome': Zome doesn't export a memory",
+        );
+    }
+
+    #[test]
+    fn zome_exporting_something_else_as_memory_fails() {
+        assert_fails(
+            r#"
+(module
+    (memory 1)
+    (func $main (param $allocation i32) (result i32)
+        (i32.const 0)
+    )
+    (export "memory" (func $main))
+    (export "main" (func $main))
+)
+"#,
+            "Zome export named 'memory' is not a memory",
+        );
+    }
+
+    #[test]
+    fn zome_importing_unknown_function_fails() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_unknown" (func $unknown (param i32) (result i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i32) (result i32)
+        (call $unknown (get_local $allocation))
+    )
+)
+"#,
+            "hc_unknown",
+        );
+    }
+
+    #[test]
+    fn zome_importing_function_with_wrong_signature_fails() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i32 i32) (result i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i32) (result i32)
+        (call $debug (i32.const 0) (i32.const 0))
+    )
+)
+"#,
+            "Could not instantiate zome 'test_zome'",
+        );
+    }
+
+    #[test]
+    fn zome_passing_error_code_as_allocation_traps() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i32) (result i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i32) (result i32)
+        (call $debug (i32.const 131072))
+    )
+)
+"#,
+            "Zome function 'main' of zome 'test_zome' trapped: \
+             Zome API function received no valid encoded allocation",
+        );
+    }
+
+    #[test]
+    fn zome_passing_invalid_utf8_traps() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i32) (result i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (data (i32.const 0) "\ff")
+    (func (export "main") (param $allocation i32) (result i32)
+        (call $debug (i32.const 1))
+    )
+)
+"#,
+            "Zome API function received invalid utf8",
+        );
+    }
+
+    #[test]
+    fn zome_passing_allocation_out_of_memory_traps() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i64) (result i64)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i64) (result i64)
+        (call $debug (i64.const 0x7FFF000000000010))
+    )
+)
+"#,
+            "Zome API function received no valid encoded allocation",
+        );
+    }
+
+    #[test]
+    fn zome_mixing_memory_abis_traps() {
+        assert_fails(
+            r#"
+(module
+    (import "env" "hc_debug" (func $debug (param i32) (result i32)))
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i64) (result i64)
+        (drop (call $debug (i32.const 0)))
+        (i64.const 0)
+    )
+)
+"#,
+            "Zome called hc_debug with arguments of another memory ABI than MultiPage",
+        );
+    }
+
+    #[test]
+    fn zome_function_returning_nothing_fails() {
+        assert_fails(
+            r#"
+(module
+    (memory 1)
+    (export "memory" (memory 0))
+    (func (export "main") (param $allocation i32))
+)
+"#,
+            "Zome function 'main' of zome 'test_zome' returned no value",
+        );
+    }
+}
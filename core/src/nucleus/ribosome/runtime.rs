@@ -2,17 +2,47 @@ use context::Context;
 use holochain_core_types::error::RibosomeReturnCode;
 use holochain_wasm_utils::memory_allocation::decode_encoded_allocation;
 use nucleus::{
-    ribosome::{api::ZomeApiFunction, memory::SinglePageManager, Defn},
+    ribosome::{
+        api::ZomeApiFunction,
+        memory::MemoryManager,
+        metering::{LimitExceeded, LIMIT_EXCEEDED_INDEX},
+        Defn,
+    },
     ZomeFnCall,
 };
-use std::sync::Arc;
-use wasmi::{Externals, RuntimeArgs, RuntimeValue, Trap, TrapKind};
+use std::{fmt, sync::Arc};
+use wasmi::{Externals, HostError, RuntimeArgs, RuntimeValue, Trap, TrapKind, ValueType};
+
+/// Reason why the host side of a zome call trapped the zome,
+/// e.g. because the zome passed an invalid allocation to a Zome API function.
+/// The zome call fails with it instead of the whole process going down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuntimeError(String);
+
+impl RuntimeError {
+    pub fn new<S: Into<String>>(reason: S) -> Self {
+        RuntimeError(reason.into())
+    }
+
+    /// A trap of the zome with the given reason.
+    pub fn trap<S: Into<String>>(reason: S) -> Trap {
+        Trap::new(TrapKind::Host(Box::new(RuntimeError::new(reason))))
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl HostError for RuntimeError {}
 
 /// Object holding data to pass around to invoked Zome API functions
 #[derive(Clone)]
 pub struct Runtime {
-    /// Memory state tracker between ribosome and wasm.
-    pub memory_manager: SinglePageManager,
+    /// Memory state tracker between ribosome and wasm, in the memory ABI of the zome.
+    pub memory_manager: MemoryManager,
     /// Context of Holochain. Required for operating.
     pub context: Arc<Context>,
     /// Name of the DNA that is being hosted.
@@ -24,31 +54,39 @@ pub struct Runtime {
 impl Runtime {
     /// Load a string stored in wasm memory.
     /// Input RuntimeArgs should only have one input which is the encoded allocation holding
-    /// the complex data as an utf8 string.
-    /// Returns the utf8 string.
-    pub fn load_utf8_from_args(&self, args: &RuntimeArgs) -> String {
-        // @TODO don't panic in WASM
-        // @see https://github.com/holochain/holochain-rust/issues/159
-        assert_eq!(1, args.len());
+    /// the complex data as an utf8 string, in the memory ABI of the zome.
+    /// Returns the utf8 string, or a trap if the zome didn't pass one.
+    pub fn load_utf8_from_args(&self, args: &RuntimeArgs) -> Result<String, Trap> {
+        if args.len() != 1 {
+            return Err(RuntimeError::trap(format!(
+                "Zome API function expects a single encoded allocation but got {} arguments",
+                args.len()
+            )));
+        }
 
         // Read complex argument serialized in memory
-        let encoded_allocation: u32 = args.nth(0);
-        let maybe_allocation = decode_encoded_allocation(encoded_allocation);
-        let allocation = match maybe_allocation {
+        let encoded_allocation = args.nth_value_checked(0)?;
+        let bin_arg = match self.memory_manager.read(encoded_allocation) {
             // Handle empty allocation edge case
-            Err(RibosomeReturnCode::Success) => return String::new(),
+            Err(RibosomeReturnCode::Success) => return Ok(String::new()),
             // Handle error code
-            Err(_) => panic!("received error code instead of valid encoded allocation"),
+            Err(return_code) => {
+                return Err(RuntimeError::trap(format!(
+                    "Zome API function received no valid encoded allocation: {}",
+                    return_code.to_string()
+                )))
+            }
             // Handle normal allocation
-            Ok(allocation) => allocation,
+            Ok(bin_arg) => bin_arg,
         };
-        let bin_arg = self.memory_manager.read(allocation);
 
         // convert complex argument
-        String::from_utf8(bin_arg)
-            // @TODO don't panic in WASM
-            // @see https://github.com/holochain/holochain-rust/issues/159
-            .unwrap()
+        String::from_utf8(bin_arg).map_err(|error| {
+            RuntimeError::trap(format!(
+                "Zome API function received invalid utf8: {}",
+                error
+            ))
+        })
     }
 
     /// Store a string in wasm memory.
@@ -59,14 +97,15 @@ impl Runtime {
         let mut s_bytes: Vec<_> = json_str.to_string().into_bytes();
         s_bytes.push(0); // Add string terminate character (important)
 
-        let allocation_of_result = self.memory_manager.write(&s_bytes);
-        if allocation_of_result.is_err() {
-            return Err(Trap::new(TrapKind::MemoryAccessOutOfBounds));
-        }
-        let encoded_allocation = allocation_of_result.unwrap().encode();
+        let allocation_of_result = self.memory_manager.write(&s_bytes).map_err(|err_code| {
+            RuntimeError::trap(format!(
+                "Could not store the result of a Zome API function in wasm memory: {}",
+                err_code.to_string()
+            ))
+        })?;
 
-        // Return success in i32 format
-        Ok(Some(RuntimeValue::I32(encoded_allocation as i32)))
+        // Return success in the format of the memory ABI
+        Ok(Some(allocation_of_result))
     }
 }
 
@@ -78,11 +117,53 @@ impl Externals for Runtime {
         index: usize,
         args: RuntimeArgs,
     ) -> Result<Option<RuntimeValue>, Trap> {
+        // called by metered zome code that exceeded one of its limits
+        if index == LIMIT_EXCEEDED_INDEX {
+            let kind: i32 = args.nth_checked(0)?;
+            return Err(
+                match LimitExceeded::from_kind(kind, &self.context.execution_limits) {
+                    Some(limit) => Trap::new(TrapKind::Host(Box::new(limit))),
+                    None => Trap::new(TrapKind::UnexpectedSignature),
+                },
+            );
+        }
         let zf = ZomeApiFunction::from_index(index);
         match zf {
-            ZomeApiFunction::MissingNo => panic!("unknown function index"),
-            // convert the function to its callable form and call it with the given arguments
-            _ => zf.as_fn()(self, &args),
+            ZomeApiFunction::MissingNo => Err(RuntimeError::trap(format!(
+                "Zome called the unknown host function index {}",
+                index
+            ))),
+            // Abort has a signature of its own
+            ZomeApiFunction::Abort => zf.as_fn()(self, &args),
+            _ => {
+                // a zome must not mix up the memory ABIs
+                let abi = self.memory_manager.abi();
+                let abi_type = abi.value_type();
+                if args.as_ref().iter().any(|arg| arg.value_type() != abi_type) {
+                    return Err(RuntimeError::trap(format!(
+                        "Zome called {} with arguments of another memory ABI than {:?}",
+                        zf.as_str(),
+                        abi
+                    )));
+                }
+                // convert the function to its callable form and call it with the given arguments
+                match zf.as_fn()(self, &args)? {
+                    // Zome API functions give back error codes in the single-page encoding
+                    Some(RuntimeValue::I32(encoded)) if abi_type == ValueType::I64 => {
+                        let return_code = match decode_encoded_allocation(encoded as u32) {
+                            Err(return_code) => return_code,
+                            Ok(_) => {
+                                return Err(RuntimeError::trap(format!(
+                                    "{} returned a single-page allocation to a multi-page zome",
+                                    zf.as_str()
+                                )))
+                            }
+                        };
+                        Ok(Some(self.memory_manager.encode_return_code(return_code)))
+                    }
+                    result => Ok(result),
+                }
+            }
         }
     }
 }
@@ -1,12 +1,12 @@
 use holochain_core_types::{
-    cas::content::Address, error::HolochainError, validation::ValidationPackage,
+    cas::content::Address, error::HolochainError, hash::HashString, validation::ValidationPackage,
 };
 use holochain_dna::Dna;
 use nucleus::ZomeFnCall;
-use snowflake;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum NucleusStatus {
     New,
     Initializing,
@@ -27,23 +27,28 @@ pub type ValidationResult = Result<(), String>;
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct NucleusState {
     pub dna: Option<Dna>,
+    /// canonical hash of the DNA, computed once when the DNA gets set
+    pub dna_hash: Option<HashString>,
     pub status: NucleusStatus,
     // @TODO eventually drop stale calls
     // @see https://github.com/holochain/holochain-rust/issues/166
     // @TODO should this use the standard ActionWrapper/ActionResponse format?
     // @see https://github.com/holochain/holochain-rust/issues/196
     pub zome_calls: HashMap<ZomeFnCall, Option<Result<String, HolochainError>>>,
-    pub validation_results: HashMap<(snowflake::ProcessUniqueId, Address), ValidationResult>,
-    pub validation_packages:
-        HashMap<snowflake::ProcessUniqueId, Result<ValidationPackage, HolochainError>>,
+    /// calls nobody waits for anymore (e.g. after a timeout), their late results get dropped
+    pub abandoned_zome_calls: HashSet<ZomeFnCall>,
+    pub validation_results: HashMap<(Uuid, Address), ValidationResult>,
+    pub validation_packages: HashMap<Uuid, Result<ValidationPackage, HolochainError>>,
 }
 
 impl NucleusState {
     pub fn new() -> Self {
         NucleusState {
             dna: None,
+            dna_hash: None,
             status: NucleusStatus::New,
             zome_calls: HashMap::new(),
+            abandoned_zome_calls: HashSet::new(),
             validation_results: HashMap::new(),
             validation_packages: HashMap::new(),
         }
@@ -74,6 +79,9 @@ impl NucleusState {
     pub fn dna(&self) -> Option<Dna> {
         self.dna.clone()
     }
+    pub fn dna_hash(&self) -> Option<HashString> {
+        self.dna_hash.clone()
+    }
     pub fn status(&self) -> NucleusStatus {
         self.status.clone()
     }
@@ -1,14 +1,18 @@
+use context::Context;
 use holochain_core_types::error::HolochainError;
-use state::State;
+use serde_json;
+use state::{State, StateSnapshot};
+use std::{
+    fs::{self, File},
+    io::{prelude::*, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 /// trait that defines the persistence functionality that holochain_core requires
 pub trait Persister: Send {
-    // @TODO how does save/load work with snowflake IDs?
-    // snowflake is only unique across a single process, not a reboot save/load round trip
-    // we'd need real UUIDs for persistant uniqueness
-    // @see https://github.com/holochain/holochain-rust/issues/203
-    fn save(&mut self, state: State);
-    fn load(&self) -> Result<Option<State>, HolochainError>;
+    fn save(&mut self, state: &State) -> Result<(), HolochainError>;
+    fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError>;
 }
 
 #[derive(Default, Clone, PartialEq)]
@@ -17,10 +21,11 @@ pub struct SimplePersister {
 }
 
 impl Persister for SimplePersister {
-    fn save(&mut self, state: State) {
-        self.state = Some(state);
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
+        self.state = Some(state.clone());
+        Ok(())
     }
-    fn load(&self) -> Result<Option<State>, HolochainError> {
+    fn load(&self, _context: Arc<Context>) -> Result<Option<State>, HolochainError> {
         Ok(self.state.clone())
     }
 }
@@ -31,16 +36,129 @@ impl SimplePersister {
     }
 }
 
+/// Persister that writes a StateSnapshot as json into a directory on disk.
+/// The source chain and DHT content is not part of the snapshot since it already lives in the
+/// CAS and EAV storages of the context, so those need to be persistent as well (e.g. file based).
+#[derive(Clone, PartialEq, Debug)]
+pub struct FilePersister {
+    file_path: PathBuf,
+    last_snapshot: Option<StateSnapshot>,
+}
+
+impl FilePersister {
+    pub fn new<P: AsRef<Path>>(dir_path: P) -> Self {
+        FilePersister {
+            file_path: dir_path.as_ref().join(Self::file_name()),
+            last_snapshot: None,
+        }
+    }
+
+    pub fn file_name() -> &'static str {
+        "state.json"
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+}
+
+impl Persister for FilePersister {
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
+        let snapshot = StateSnapshot::from(state);
+        // e.g. the final save when stopping the instance, only hit the disk if the snapshot
+        // actually changed
+        if self.last_snapshot.as_ref() == Some(&snapshot) {
+            return Ok(());
+        }
+        let json = serde_json::to_string(&snapshot)?;
+        // write to a temporary file first so a crash can't leave a truncated snapshot behind
+        let tmp_path = self.file_path.with_extension("json.tmp");
+        {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(json.as_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp_path, &self.file_path)?;
+        self.last_snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    fn load(&self, context: Arc<Context>) -> Result<Option<State>, HolochainError> {
+        let mut file = match File::open(&self.file_path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut json = String::new();
+        file.read_to_string(&mut json)?;
+        let snapshot: StateSnapshot = serde_json::from_str(&json)?;
+        Ok(Some(State::from_snapshot(context, snapshot)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    extern crate tempfile;
+    use self::tempfile::tempdir;
     use super::*;
+    use dht::dht_store::Network;
+    use holochain_dna::Dna;
+    use instance::tests::test_context;
+    use nucleus::state::NucleusStatus;
 
     #[test]
     fn can_instantiate() {
         let store = SimplePersister::new();
 
-        assert_eq!(store.load(), Ok(None));
+        assert_eq!(store.load(test_context("bob")), Ok(None));
     }
 
-    // TODO write a persister.save() test
+    #[test]
+    fn can_save_and_load_simple_persister() {
+        let context = test_context("bob");
+        let mut store = SimplePersister::new();
+        let state = State::new(context.clone());
+
+        store.save(&state).unwrap();
+
+        assert_eq!(store.load(context), Ok(Some(state)));
+    }
+
+    #[test]
+    fn file_persister_loads_none_without_file() {
+        let dir = tempdir().unwrap();
+        let store = FilePersister::new(dir.path());
+
+        assert_eq!(store.load(test_context("bob")), Ok(None));
+    }
+
+    #[test]
+    fn file_persister_round_trip() {
+        let dir = tempdir().unwrap();
+        let context = test_context("bob");
+        let mut store = FilePersister::new(dir.path());
+
+        let mut dna = Dna::new();
+        dna.name = "persisted".to_string();
+        let state = State::from_snapshot(
+            context.clone(),
+            StateSnapshot {
+                dna: Some(dna.clone()),
+                nucleus_status: NucleusStatus::Initialized,
+                top_chain_header: None,
+                dht_network: Network::default(),
+            },
+        );
+
+        store.save(&state).unwrap();
+        assert!(store.file_path().exists());
+
+        // a fresh persister on the same directory sees the saved state
+        let loaded = FilePersister::new(dir.path())
+            .load(context)
+            .unwrap()
+            .expect("state should have been persisted");
+        assert_eq!(loaded.nucleus().dna(), Some(dna));
+        assert!(loaded.nucleus().has_initialized());
+    }
 }
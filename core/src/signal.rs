@@ -0,0 +1,324 @@
+//! Signals let clients of an instance listen to what happens inside of it instead of polling.
+//! App signals are declared in the DNA and emitted by zome code through hc_emit_signal,
+//! system signals are emitted by core itself.
+//! @see doc/architecture/decisions/0013-signals-listeners-model-and-api.md
+
+use action::{Action, ActionWrapper};
+use agent::state::ActionResponse;
+use holochain_core_types::{cas::content::Address, entry_type::EntryType, links_entry::Link};
+use nucleus::state::NucleusStatus;
+use state::State;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+/// Something that happened inside an instance that clients can listen to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Signal {
+    App(AppSignal),
+    System(SystemSignal),
+}
+
+/// A signal declared in a capability of the DNA and emitted by zome code.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AppSignal {
+    pub zome_name: String,
+    pub cap_name: String,
+    pub name: String,
+    /// json encoded arguments as declared in the DNA
+    pub arguments: String,
+}
+
+/// Signals core emits by itself.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SystemSignal {
+    /// an entry got committed to the source chain
+    EntryCommitted {
+        entry_type: EntryType,
+        address: Address,
+    },
+    /// a link got added to the DHT
+    LinkAdded(Link),
+    /// genesis started, succeeded or failed
+    NucleusStatusChanged(NucleusStatus),
+    /// the instance got started and accepts zome calls
+    InstanceStarted,
+    /// the instance got stopped
+    InstanceStopped,
+}
+
+/// Selects the signals a listener receives.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignalFilter {
+    /// the app signal with the given name, declared in the given zome and capability
+    App {
+        zome_name: String,
+        cap_name: String,
+        name: String,
+    },
+    /// all system signals
+    System,
+}
+
+impl SignalFilter {
+    pub fn matches(&self, signal: &Signal) -> bool {
+        match (self, signal) {
+            (
+                SignalFilter::App {
+                    zome_name,
+                    cap_name,
+                    name,
+                },
+                Signal::App(app_signal),
+            ) => {
+                *zome_name == app_signal.zome_name
+                    && *cap_name == app_signal.cap_name
+                    && *name == app_signal.name
+            }
+            (SignalFilter::System, Signal::System(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Identifies a registered listener so it can be removed again.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ListenerId(Uuid);
+
+/// Gets called with every signal that matches the filter it was registered with.
+pub type Listener = Arc<Fn(&Signal) + Send + Sync>;
+
+/// Listeners of an instance, keyed by the id they were registered with.
+/// Signals get delivered synchronously on the emitting thread (the action loop for system
+/// signals, the zome call for app signals), so listeners should hand them off quickly,
+/// e.g. through a channel.
+#[derive(Default)]
+pub struct SignalRegistry {
+    listeners: Mutex<HashMap<ListenerId, (SignalFilter, Listener)>>,
+}
+
+impl SignalRegistry {
+    pub fn new() -> Self {
+        SignalRegistry::default()
+    }
+
+    /// Registers a listener for all signals matching the given filter.
+    /// Checking whether the client may listen to these signals is up to the caller.
+    pub fn listen<F>(&self, filter: SignalFilter, listener: F) -> ListenerId
+    where
+        F: Fn(&Signal) + Send + Sync + 'static,
+    {
+        let id = ListenerId(Uuid::new_v4());
+        self.listeners()
+            .insert(id.clone(), (filter, Arc::new(listener)));
+        id
+    }
+
+    /// Removes a listener. Returns false if there was no listener with the given id.
+    pub fn unlisten(&self, id: &ListenerId) -> bool {
+        self.listeners().remove(id).is_some()
+    }
+
+    /// Delivers the signal to every listener whose filter matches it.
+    pub fn emit(&self, signal: &Signal) {
+        // don't hold the lock while calling listeners, so they can (un)register listeners
+        let listeners: Vec<Listener> = self
+            .listeners()
+            .values()
+            .filter(|(filter, _)| filter.matches(signal))
+            .map(|(_, listener)| listener.clone())
+            .collect();
+        for listener in listeners {
+            listener(signal);
+        }
+    }
+
+    /// Number of registered listeners.
+    pub fn len(&self) -> usize {
+        self.listeners().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.listeners().is_empty()
+    }
+
+    fn listeners(&self) -> MutexGuard<HashMap<ListenerId, (SignalFilter, Listener)>> {
+        // the map stays consistent even if a listener panicked while it was being registered
+        self.listeners
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The system signal the given action caused, if any.
+/// Expects the state right after the action has been reduced, while the response is still there.
+pub fn system_signal(action_wrapper: &ActionWrapper, state: &State) -> Option<Signal> {
+    let system_signal = match action_wrapper.action() {
+        Action::Commit(entry) => match state.agent().actions().get(action_wrapper) {
+            Some(ActionResponse::Commit(Ok(address))) => Some(SystemSignal::EntryCommitted {
+                entry_type: entry.entry_type().clone(),
+                address: address.clone(),
+            }),
+            _ => None,
+        },
+        Action::AddLink(link) => match state.dht().add_link_actions().get(action_wrapper) {
+            Some(Ok(())) => Some(SystemSignal::LinkAdded(link.clone())),
+            _ => None,
+        },
+        Action::InitApplication(_) | Action::ReturnInitializationResult(_) => {
+            Some(SystemSignal::NucleusStatusChanged(state.nucleus().status()))
+        }
+        _ => None,
+    };
+    system_signal.map(Signal::System)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use action::tests::{test_action_wrapper_commit, test_action_wrapper_get};
+    use holochain_core_types::{cas::content::AddressableContent, entry::test_entry};
+    use holochain_dna::Dna;
+    use instance::{tests::test_context, Instance, Observer};
+    use std::sync::mpsc::channel;
+
+    pub fn test_app_signal() -> Signal {
+        Signal::App(AppSignal {
+            zome_name: "test_zome".to_string(),
+            cap_name: "test_cap".to_string(),
+            name: "Post".to_string(),
+            arguments: "\"QmHash\"".to_string(),
+        })
+    }
+
+    pub fn test_app_signal_filter() -> SignalFilter {
+        SignalFilter::App {
+            zome_name: "test_zome".to_string(),
+            cap_name: "test_cap".to_string(),
+            name: "Post".to_string(),
+        }
+    }
+
+    #[test]
+    /// test that filters only select the signals they describe
+    fn filters_match() {
+        let system_signal = Signal::System(SystemSignal::LinkAdded(Link::new(
+            &test_entry().address(),
+            &test_entry().address(),
+            "test-tag",
+        )));
+
+        assert!(test_app_signal_filter().matches(&test_app_signal()));
+        assert!(!test_app_signal_filter().matches(&system_signal));
+        assert!(SignalFilter::System.matches(&system_signal));
+        assert!(!SignalFilter::System.matches(&test_app_signal()));
+
+        let other_cap = SignalFilter::App {
+            zome_name: "test_zome".to_string(),
+            cap_name: "other_cap".to_string(),
+            name: "Post".to_string(),
+        };
+        assert!(!other_cap.matches(&test_app_signal()));
+    }
+
+    #[test]
+    /// test that only matching listeners get the signal, and only until they unlisten
+    fn listen_and_unlisten() {
+        let registry = SignalRegistry::new();
+        let (app_tx, app_rx) = channel();
+        let (system_tx, system_rx) = channel();
+        let app_tx = Mutex::new(app_tx);
+        let system_tx = Mutex::new(system_tx);
+        let app_listener = registry.listen(test_app_signal_filter(), move |signal| {
+            app_tx.lock().unwrap().send(signal.clone()).unwrap()
+        });
+        registry.listen(SignalFilter::System, move |signal| {
+            system_tx.lock().unwrap().send(signal.clone()).unwrap()
+        });
+        assert_eq!(registry.len(), 2);
+
+        registry.emit(&test_app_signal());
+        assert_eq!(app_rx.try_recv(), Ok(test_app_signal()));
+        assert!(system_rx.try_recv().is_err());
+
+        assert!(registry.unlisten(&app_listener));
+        assert!(!registry.unlisten(&app_listener));
+        registry.emit(&test_app_signal());
+        assert!(app_rx.try_recv().is_err());
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    /// test that successful commits cause an EntryCommitted signal
+    fn entry_committed_signal() {
+        let context = test_context("jason");
+        let state = State::new(context.clone());
+        let commit = test_action_wrapper_commit();
+        let new_state = state.reduce(context, commit.clone());
+
+        assert_eq!(
+            system_signal(&commit, &new_state),
+            Some(Signal::System(SystemSignal::EntryCommitted {
+                entry_type: test_entry().entry_type().clone(),
+                address: test_entry().address(),
+            }))
+        );
+        assert_eq!(system_signal(&test_action_wrapper_get(), &new_state), None);
+    }
+
+    #[test]
+    /// test that genesis causes NucleusStatusChanged signals
+    fn nucleus_status_changed_signal() {
+        let context = test_context("jason");
+        let state = State::new(context.clone());
+        let init = ActionWrapper::new(Action::InitApplication(Dna::new()));
+        let state = state.reduce(context.clone(), init.clone());
+        assert_eq!(
+            system_signal(&init, &state),
+            Some(Signal::System(SystemSignal::NucleusStatusChanged(
+                NucleusStatus::Initializing
+            )))
+        );
+
+        let result = ActionWrapper::new(Action::ReturnInitializationResult(None));
+        let state = state.reduce(context, result.clone());
+        assert_eq!(
+            system_signal(&result, &state),
+            Some(Signal::System(SystemSignal::NucleusStatusChanged(
+                NucleusStatus::Initialized
+            )))
+        );
+    }
+
+    #[test]
+    /// test that the action loop emits system signals to listeners of the context
+    fn instance_emits_system_signals() {
+        let context = test_context("jason");
+        let (tx, rx) = channel();
+        let tx = Mutex::new(tx);
+        context
+            .signal_registry
+            .listen(SignalFilter::System, move |signal| {
+                tx.lock().unwrap().send(signal.clone()).unwrap()
+            });
+        let instance = Instance::new(context.clone());
+        let (_, rx_observer) = channel::<Observer>();
+
+        instance.process_action(
+            test_action_wrapper_commit(),
+            Vec::new(),
+            &rx_observer,
+            &context,
+        );
+
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![Signal::System(SystemSignal::EntryCommitted {
+                entry_type: test_entry().entry_type().clone(),
+                address: test_entry().address(),
+            })]
+        );
+    }
+}
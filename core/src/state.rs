@@ -1,10 +1,15 @@
 use action::ActionWrapper;
 use agent::{chain_store::ChainStore, state::AgentState};
 use context::Context;
-use dht::dht_store::DhtStore;
-use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
-use nucleus::state::NucleusState;
-use std::{collections::HashSet, sync::Arc};
+use dht::dht_store::{DhtStore, Network};
+use history::ActionHistory;
+use holochain_core_types::{
+    cas::storage::DynContentAddressableStorage, chain_header::ChainHeader,
+    eav::DynEntityAttributeValueStorage, error::HolochainError,
+};
+use holochain_dna::Dna;
+use nucleus::state::{NucleusState, NucleusStatus};
+use std::{sync::Arc, time::Instant};
 
 /// The Store of the Holochain instance Object, according to Redux pattern.
 /// It's composed of all sub-module's state slices.
@@ -13,10 +18,9 @@ use std::{collections::HashSet, sync::Arc};
 pub struct State {
     nucleus: Arc<NucleusState>,
     agent: Arc<AgentState>,
-    dht: Arc<DhtStore<FilesystemStorage, EavFileStorage>>,
-    // @TODO eventually drop stale history
-    // @see https://github.com/holochain/holochain-rust/issues/166
-    pub history: HashSet<ActionWrapper>,
+    dht: Arc<DhtStore<DynContentAddressableStorage, DynEntityAttributeValueStorage>>,
+    /// bounded by the RetentionPolicy of the context
+    pub history: ActionHistory,
 }
 
 impl State {
@@ -24,37 +28,107 @@ impl State {
         // @TODO file table
         // @see https://github.com/holochain/holochain-rust/pull/246
 
-        let cas = &(*context).file_storage;
+        let cas = &(*context).content_storage;
         let eav = &(*context).eav_storage;
         State {
             nucleus: Arc::new(NucleusState::new()),
             agent: Arc::new(AgentState::new(ChainStore::new(cas.clone()))),
             dht: Arc::new(DhtStore::new(cas.clone(), eav.clone())),
-            history: HashSet::new(),
+            history: ActionHistory::new(),
         }
     }
 
+    /// Rebuilds a State from a snapshot that was written by a Persister.
+    /// Content of the source chain and the DHT shard is not part of the snapshot,
+    /// it lives in the CAS and EAV storages the given context points to.
+    pub fn from_snapshot(context: Arc<Context>, snapshot: StateSnapshot) -> Self {
+        let cas = &(*context).content_storage;
+        let eav = &(*context).eav_storage;
+
+        let mut nucleus = NucleusState::new();
+        nucleus.dna_hash = snapshot.dna.as_ref().map(|dna| dna.content_hash());
+        nucleus.dna = snapshot.dna;
+        nucleus.status = snapshot.nucleus_status;
+
+        State {
+            nucleus: Arc::new(nucleus),
+            agent: Arc::new(AgentState::new_with_top_chain_header(
+                ChainStore::new(cas.clone()),
+                snapshot.top_chain_header,
+            )),
+            dht: Arc::new(DhtStore::new_with_network(
+                cas.clone(),
+                eav.clone(),
+                snapshot.dht_network,
+            )),
+            history: ActionHistory::new(),
+        }
+    }
+
+    /// True if the parts of the state a StateSnapshot holds differ from the other state,
+    /// so it has to be persisted again. The DNA gets compared by its hash.
+    pub fn snapshot_differs_from(&self, other: &State) -> bool {
+        self.nucleus.dna_hash != other.nucleus.dna_hash
+            || self.nucleus.status != other.nucleus.status
+            || self.agent.top_chain_header() != other.agent.top_chain_header()
+            || self.dht.network() != other.dht.network()
+    }
+
     pub fn reduce(&self, context: Arc<Context>, action_wrapper: ActionWrapper) -> Self {
+        let start = Instant::now();
+        let nucleus = ::nucleus::reduce(
+            Arc::clone(&context),
+            Arc::clone(&self.nucleus),
+            &action_wrapper,
+        );
+        context.metrics.record_reducer("nucleus", start.elapsed());
+
+        let start = Instant::now();
+        let agent = ::agent::state::reduce(
+            Arc::clone(&context),
+            Arc::clone(&self.agent),
+            &action_wrapper,
+        );
+        context.metrics.record_reducer("agent", start.elapsed());
+
+        let start = Instant::now();
+        let dht = ::dht::dht_reducers::reduce(
+            Arc::clone(&context),
+            Arc::clone(&self.dht),
+            &action_wrapper,
+        );
+        context.metrics.record_reducer("dht", start.elapsed());
+
         let mut new_state = State {
-            nucleus: ::nucleus::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.nucleus),
-                &action_wrapper,
-            ),
-            agent: ::agent::state::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.agent),
-                &action_wrapper,
-            ),
-            dht: ::dht::dht_reducers::reduce(
-                Arc::clone(&context),
-                Arc::clone(&self.dht),
-                &action_wrapper,
-            ),
+            nucleus,
+            agent,
+            dht,
             history: self.history.clone(),
         };
 
         new_state.history.insert(action_wrapper);
+        new_state.history.prune(&context.retention_policy);
+        new_state
+    }
+
+    /// Instead of reducing an action that middleware vetoed, resolves whoever waits for
+    /// its response with the reason for the veto.
+    /// The vetoed action still shows up in the history, so dispatch_action_and_wait returns.
+    pub fn reduce_veto(
+        &self,
+        context: &Arc<Context>,
+        action_wrapper: &ActionWrapper,
+        reason: &HolochainError,
+    ) -> Self {
+        let mut new_state = State {
+            nucleus: ::nucleus::reduce_veto(Arc::clone(&self.nucleus), action_wrapper, reason),
+            agent: ::agent::state::reduce_veto(Arc::clone(&self.agent), action_wrapper, reason),
+            dht: ::dht::dht_reducers::reduce_veto(Arc::clone(&self.dht), action_wrapper, reason),
+            history: self.history.clone(),
+        };
+
+        new_state.history.insert(action_wrapper.clone());
+        new_state.history.prune(&context.retention_policy);
         new_state
     }
 
@@ -66,11 +140,99 @@ impl State {
         Arc::clone(&self.agent)
     }
 
-    pub fn dht(&self) -> Arc<DhtStore<FilesystemStorage, EavFileStorage>> {
+    pub fn dht(
+        &self,
+    ) -> Arc<DhtStore<DynContentAddressableStorage, DynEntityAttributeValueStorage>> {
         Arc::clone(&self.dht)
     }
 }
 
+/// The serializable part of the State that needs to survive a restart of the instance.
+/// Transient data like pending zome calls, validation results and the action history
+/// is dropped.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub dna: Option<Dna>,
+    pub nucleus_status: NucleusStatus,
+    pub top_chain_header: Option<ChainHeader>,
+    /// setup of the network module of the DHT store
+    #[serde(default)]
+    pub dht_network: Network,
+}
+
+impl<'a> From<&'a State> for StateSnapshot {
+    fn from(state: &State) -> Self {
+        StateSnapshot {
+            dna: state.nucleus().dna(),
+            nucleus_status: state.nucleus().status(),
+            top_chain_header: state.agent().top_chain_header(),
+            dht_network: state.dht().network().clone(),
+        }
+    }
+}
+
 pub fn test_store(context: Arc<Context>) -> State {
     State::new(context)
 }
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use instance::tests::test_context;
+    use serde_json;
+
+    #[test]
+    /// test that a snapshot survives a round trip through json and back into a State
+    fn snapshot_round_trip() {
+        let context = test_context("bob");
+        let mut dna = Dna::new();
+        dna.name = "snapshot".to_string();
+        let snapshot = StateSnapshot {
+            dna: Some(dna.clone()),
+            nucleus_status: NucleusStatus::Initialized,
+            top_chain_header: None,
+            dht_network: Network::default(),
+        };
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: StateSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot, restored);
+
+        let state = State::from_snapshot(context, restored);
+        assert_eq!(state.nucleus().dna(), Some(dna));
+        assert!(state.nucleus().has_initialized());
+        assert_eq!(state.agent().top_chain_header(), None);
+        assert!(state.history.is_empty());
+        assert_eq!(snapshot, StateSnapshot::from(&state));
+    }
+
+    #[test]
+    /// test that snapshots written before the DHT store was part of them can still be read
+    fn snapshot_without_dht_network() {
+        let snapshot: StateSnapshot =
+            serde_json::from_str(r#"{"dna":null,"nucleus_status":"New","top_chain_header":null}"#)
+                .unwrap();
+        assert_eq!(snapshot.dht_network, Network::default());
+    }
+
+    #[test]
+    /// test that only changes to the persisted parts of the state call for persisting it
+    fn snapshot_differs_from() {
+        let context = test_context("bob");
+        let state = State::new(context.clone());
+        assert!(!state.snapshot_differs_from(&state.clone()));
+
+        let mut dna = Dna::new();
+        dna.name = "snapshot".to_string();
+        let initialized = State::from_snapshot(
+            context,
+            StateSnapshot {
+                dna: Some(dna),
+                nucleus_status: NucleusStatus::Initialized,
+                top_chain_header: None,
+                dht_network: Network::default(),
+            },
+        );
+        assert!(initialized.snapshot_differs_from(&state));
+    }
+}
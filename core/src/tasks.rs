@@ -0,0 +1,129 @@
+//! Bookkeeping of the threads an instance spawns for zome calls, validation and initialization,
+//! so that stopping the instance can wait for them to finish.
+
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Instant,
+};
+
+/// Counts the background tasks of an instance that are still running.
+#[derive(Default)]
+pub struct TaskTracker {
+    running: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl TaskTracker {
+    pub fn new() -> Self {
+        TaskTracker::default()
+    }
+
+    /// Registers a new running task.
+    /// The task counts as running until the returned guard gets dropped, so the guard has to
+    /// be created before spawning the thread and then moved into it.
+    pub fn start(tracker: &Arc<TaskTracker>) -> TaskGuard {
+        *tracker.running() += 1;
+        TaskGuard {
+            tracker: tracker.clone(),
+        }
+    }
+
+    /// Number of tasks that are still running.
+    pub fn count(&self) -> usize {
+        *self.running()
+    }
+
+    /// Blocks until all tasks have finished or the deadline has passed.
+    /// Returns true if all tasks have finished.
+    pub fn wait_until_idle(&self, deadline: Instant) -> bool {
+        let mut running = self.running();
+        while *running > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            running = self
+                .finished
+                .wait_timeout(running, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+        true
+    }
+
+    fn finish(&self) {
+        let mut running = self.running();
+        *running -= 1;
+        if *running == 0 {
+            self.finished.notify_all();
+        }
+    }
+
+    fn running(&self) -> MutexGuard<usize> {
+        // a counter can't be left inconsistent by a panicking thread
+        self.running
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Keeps a task registered as running until it gets dropped,
+/// which also happens if the task panics.
+pub struct TaskGuard {
+    tracker: Arc<TaskTracker>,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.tracker.finish();
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::{thread, time::Duration};
+
+    #[test]
+    /// test that waiting returns once all tasks are done
+    fn waits_for_tasks() {
+        let tracker = Arc::new(TaskTracker::new());
+        assert!(tracker.wait_until_idle(Instant::now()));
+
+        let task = TaskTracker::start(&tracker);
+        assert_eq!(tracker.count(), 1);
+        let handle = thread::spawn(move || {
+            let _task = task;
+            thread::sleep(Duration::from_millis(20));
+        });
+
+        assert!(tracker.wait_until_idle(Instant::now() + Duration::from_secs(10)));
+        assert_eq!(tracker.count(), 0);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    /// test that waiting gives up at the deadline
+    fn times_out() {
+        let tracker = Arc::new(TaskTracker::new());
+        let _task = TaskTracker::start(&tracker);
+
+        assert!(!tracker.wait_until_idle(Instant::now() + Duration::from_millis(10)));
+        assert_eq!(tracker.count(), 1);
+    }
+
+    #[test]
+    /// test that a panicking task still counts as finished
+    fn panicking_task_finishes() {
+        let tracker = Arc::new(TaskTracker::new());
+        let task = TaskTracker::start(&tracker);
+        let result = thread::spawn(move || {
+            let _task = task;
+            panic!("task failed");
+        })
+        .join();
+
+        assert!(result.is_err());
+        assert_eq!(tracker.count(), 0);
+    }
+}
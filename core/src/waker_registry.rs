@@ -0,0 +1,242 @@
+//! Connects futures that wait for a response in the state to the action loop,
+//! so they only get polled again once their response has been reduced
+//! instead of spinning on the state.
+
+use action::{Action, ActionWrapper};
+use context::Context;
+use futures::{
+    task::{self, Waker},
+    Async,
+};
+use holochain_core_types::error::HolochainError;
+use instance::{action_loop_stopped, dispatch_action};
+use state::State;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use uuid::Uuid;
+
+/// Wakers of pending futures, keyed by the id of the response they are waiting for.
+/// That is the ActionWrapper id for responses to an action (Commit, AddLink),
+/// the id of the validation for validation results and packages
+/// and the id of the call for zome function results.
+#[derive(Default)]
+pub struct WakerRegistry {
+    wakers: Mutex<HashMap<Uuid, Waker>>,
+}
+
+impl WakerRegistry {
+    pub fn new() -> Self {
+        WakerRegistry::default()
+    }
+
+    /// Registers the waker of a pending future.
+    /// Futures have to register before they look at the state, otherwise a response that gets
+    /// reduced in between would never wake them.
+    pub fn register(&self, key: &Uuid, waker: &Waker) {
+        self.wakers().insert(key.clone(), waker.clone());
+    }
+
+    /// Drops the waker of a future that has resolved.
+    pub fn unregister(&self, key: &Uuid) {
+        self.wakers().remove(key);
+    }
+
+    /// Wakes the future waiting for the given response, if there is one.
+    pub fn wake(&self, key: &Uuid) {
+        // don't hold the lock while waking
+        let maybe_waker = self.wakers().remove(key);
+        if let Some(waker) = maybe_waker {
+            waker.wake();
+        }
+    }
+
+    /// Wakes all pending futures, e.g. because the action loop stopped
+    /// and they have to find out that their response will never come.
+    pub fn wake_all(&self) {
+        // don't hold the lock while waking
+        let wakers: Vec<Waker> = self.wakers().drain().map(|(_, waker)| waker).collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Number of futures currently waiting for a response.
+    pub fn len(&self) -> usize {
+        self.wakers().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wakers().is_empty()
+    }
+
+    fn wakers(&self) -> MutexGuard<HashMap<Uuid, Waker>> {
+        // the map stays consistent even if a thread panicked while holding the lock
+        self.wakers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The key of the response the given action delivers to the state, if any.
+pub fn response_key(action_wrapper: &ActionWrapper) -> Option<Uuid> {
+    match action_wrapper.action() {
+        Action::Commit(_) | Action::AddLink(_) => Some(action_wrapper.id().clone()),
+        Action::ReturnValidationResult(((id, _), _)) => Some(id.clone()),
+        Action::ReturnValidationPackage((id, _)) => Some(id.clone()),
+        Action::ReturnZomeFunctionResult(response) => Some(response.call().id().clone()),
+        _ => None,
+    }
+}
+
+/// The key of the future waiting for the given action to be reduced, if any.
+/// That is its response key, or the id of the call for actions that start a zome call,
+/// since the future of the call waits for its result.
+pub fn waiting_key(action_wrapper: &ActionWrapper) -> Option<Uuid> {
+    match action_wrapper.action() {
+        Action::ExecuteZomeFunction(call) | Action::Call(call) => Some(call.id().clone()),
+        _ => response_key(action_wrapper),
+    }
+}
+
+/// Polls for the response a future waits for under the given key, which `lookup` reads from
+/// the state. Once it is there, `clear` gets dispatched to drop it from the state,
+/// since the future is its only reader.
+/// Fails if the action loop has been stopped before delivering the response.
+pub fn poll_response<T, F>(
+    context: &Arc<Context>,
+    key: &Uuid,
+    clear: Action,
+    cx: &mut task::Context,
+    lookup: F,
+) -> Result<Async<T>, HolochainError>
+where
+    F: FnOnce(&State) -> Option<T>,
+{
+    // register before looking at the state so we can't miss the response
+    context.waker_registry.register(key, cx.waker());
+    // a stopped action loop delivers nothing anymore, look before reading the state
+    // so a response that got delivered right before it stopped doesn't get missed
+    let stopped = context.action_channel.is_stopped();
+    // don't hold the state lock while dispatching below
+    let maybe_response = match context.state() {
+        Some(state) => lookup(&state),
+        None => None,
+    };
+    match maybe_response {
+        Some(response) => {
+            context.waker_registry.unregister(key);
+            // there is no state left to clear up once the action loop has been stopped
+            let _ = dispatch_action(&context.action_channel, ActionWrapper::new(clear));
+            Ok(Async::Ready(response))
+        }
+        None if stopped => {
+            context.waker_registry.unregister(key);
+            Err(action_loop_stopped())
+        }
+        None => Ok(Async::Pending),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use action::tests::{
+        test_action_wrapper, test_action_wrapper_commit, test_action_wrapper_rzfr,
+    };
+    use futures::task::Wake;
+    use holochain_core_types::error::HolochainError;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// counts how often it got woken
+    pub struct CountingWake {
+        count: AtomicUsize,
+    }
+
+    impl Wake for CountingWake {
+        fn wake(arc_self: &Arc<Self>) {
+            arc_self.count.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWake>, Waker) {
+        let wake = Arc::new(CountingWake {
+            count: AtomicUsize::new(0),
+        });
+        (wake.clone(), Waker::from(wake))
+    }
+
+    #[test]
+    /// test that only the future waiting for a response gets woken, and only once
+    fn wakes_registered_waker() {
+        let registry = WakerRegistry::new();
+        let (wake, waker) = counting_waker();
+        let commit = test_action_wrapper_commit();
+        let key = response_key(&commit).expect("commits deliver a response");
+
+        registry.register(&key, &waker);
+        assert_eq!(registry.len(), 1);
+
+        registry.wake(&Uuid::new_v4());
+        assert_eq!(wake.count.load(Ordering::SeqCst), 0);
+
+        registry.wake(&key);
+        registry.wake(&key);
+        assert_eq!(wake.count.load(Ordering::SeqCst), 1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    /// test that resolved futures can drop their waker
+    fn unregister() {
+        let registry = WakerRegistry::new();
+        let (wake, waker) = counting_waker();
+        let key = Uuid::new_v4();
+
+        registry.register(&key, &waker);
+        registry.unregister(&key);
+        registry.wake(&key);
+
+        assert_eq!(wake.count.load(Ordering::SeqCst), 0);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    /// test that all pending futures get woken once the action loop stopped
+    fn wake_all() {
+        let registry = WakerRegistry::new();
+        let (wake, waker) = counting_waker();
+
+        registry.register(&Uuid::new_v4(), &waker);
+        registry.register(&Uuid::new_v4(), &waker);
+        registry.wake_all();
+
+        assert_eq!(wake.count.load(Ordering::SeqCst), 2);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    /// test which actions deliver a response that futures wait for
+    fn response_keys() {
+        let commit = test_action_wrapper_commit();
+        assert_eq!(response_key(&commit), Some(commit.id().clone()));
+
+        let id = Uuid::new_v4();
+        let validation_package = ActionWrapper::new(Action::ReturnValidationPackage((
+            id.clone(),
+            Err(HolochainError::new("no package")),
+        )));
+        assert_eq!(response_key(&validation_package), Some(id));
+
+        let zome_fn_result = test_action_wrapper_rzfr();
+        let call = unwrap_to!(zome_fn_result.action() => Action::ReturnZomeFunctionResult).call();
+        assert_eq!(response_key(&zome_fn_result), Some(call.id().clone()));
+
+        // no future waits for GetEntry
+        assert_eq!(response_key(&test_action_wrapper()), None);
+    }
+}
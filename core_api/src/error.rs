@@ -1,4 +1,5 @@
 use holochain_core_types::error::HolochainError;
+use holochain_dna::diagnostics::DnaDiagnostic;
 use std::{error::Error, fmt};
 
 pub type HolochainResult<T> = Result<T, HolochainInstanceError>;
@@ -9,6 +10,8 @@ pub enum HolochainInstanceError {
     InternalFailure(HolochainError),
     InstanceNotActiveYet,
     InstanceAlreadyActive,
+    Timeout(String),
+    InvalidDna(Vec<DnaDiagnostic>),
 }
 
 impl Error for HolochainInstanceError {
@@ -19,6 +22,8 @@ impl Error for HolochainInstanceError {
             HolochainInstanceError::InstanceAlreadyActive => {
                 "Holochain instance is already active."
             }
+            HolochainInstanceError::Timeout(ref msg) => msg,
+            HolochainInstanceError::InvalidDna(_) => "The DNA is invalid.",
         }
     }
 
@@ -28,18 +33,29 @@ impl Error for HolochainInstanceError {
             HolochainInstanceError::InternalFailure(ref err)  => Some(err),
             HolochainInstanceError::InstanceNotActiveYet => None,
             HolochainInstanceError::InstanceAlreadyActive => None,
+            HolochainInstanceError::Timeout(_)           => None,
+            HolochainInstanceError::InvalidDna(_)        => None,
         }
     }
 }
 
 impl fmt::Display for HolochainInstanceError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Holochain Instance Error: {}", self.description())
+        write!(f, "Holochain Instance Error: {}", self.description())?;
+        if let HolochainInstanceError::InvalidDna(ref diagnostics) = self {
+            for diagnostic in diagnostics {
+                write!(f, "\n  {}", diagnostic)?;
+            }
+        }
+        Ok(())
     }
 }
 
 impl From<HolochainError> for HolochainInstanceError {
     fn from(error: HolochainError) -> Self {
-        HolochainInstanceError::InternalFailure(error)
+        match error {
+            HolochainError::Timeout(msg) => HolochainInstanceError::Timeout(msg),
+            error => HolochainInstanceError::InternalFailure(error),
+        }
     }
 }
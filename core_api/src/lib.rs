@@ -21,14 +21,16 @@
 //!        cas::file::FilesystemStorage, eav::file::EavFileStorage,
 //! };
 //! use tempfile::tempdir;
+//! # use holochain_dna::package::DnaPackage;
+//! # use std::collections::BTreeMap;
+//! # let package_dir = tempdir().unwrap();
+//! # let package_file = package_dir.path().join("mydna.hcpkg");
+//! # DnaPackage::from_dna(&Dna::new(), BTreeMap::new())
+//! #     .save(&package_file)
+//! #     .unwrap();
 //!
 //! // instantiate a new holochain instance
-//!
-//! // need to get to something like this:
-//! //let dna = holochain_dna::from_package_file("mydna.hcpkg");
-//!
-//! // but for now:
-//! let dna = Dna::new();
+//! let dna = Dna::from_package_file(&package_file).expect("couldn't load the DNA package");
 //! let agent = Agent::from("bob".to_string());
 //! let context = Context::new(
 //!     agent,
@@ -73,25 +75,41 @@ use error::{HolochainInstanceError, HolochainResult};
 use futures::executor::block_on;
 use holochain_core::{
     context::Context,
-    instance::Instance,
-    nucleus::{actions::initialize::initialize_application, call_and_wait_for_result, ZomeFnCall},
+    instance::{Instance, STOP_DEFAULT_TIMEOUT_MS},
+    metrics::MetricsSnapshot,
+    nucleus::{
+        actions::{
+            call_zome_function::{call_zome_function, ZomeCallFuture},
+            initialize::initialize_application,
+        },
+        call_and_wait_for_result, call_and_wait_for_result_with_timeout, ZomeFnCall,
+    },
+    persister::{FilePersister, Persister},
+    signal::{ListenerId, Signal, SignalFilter, SystemSignal},
     state::State,
 };
-use holochain_core_types::error::HolochainError;
-use holochain_dna::Dna;
-use std::sync::Arc;
+use holochain_core_types::error::{DnaError, HolochainError};
+use holochain_dna::{zome::capabilities::Membrane, Dna};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 /// contains a Holochain application instance
 pub struct Holochain {
     instance: Instance,
-    #[allow(dead_code)]
     context: Arc<Context>,
     active: bool,
 }
 
 impl Holochain {
     /// create a new Holochain instance
+    /// fails with all diagnostics if the dna is invalid, without initializing anything
     pub fn new(dna: Dna, context: Arc<Context>) -> HolochainResult<Self> {
+        let diagnostics = dna.validate();
+        if !diagnostics.is_empty() {
+            return Err(HolochainInstanceError::InvalidDna(diagnostics));
+        }
         let mut instance = Instance::new(context.clone());
         let name = dna.name.clone();
         instance.start_action_loop(context.clone());
@@ -112,8 +130,40 @@ impl Holochain {
         }
     }
 
-    pub fn load(_path: String, _context: Arc<Context>) -> Result<Self, HolochainError> {
-        Err(HolochainError::NotImplemented)
+    /// load a Holochain instance from the state that a FilePersister wrote into the directory
+    /// at `path`. Genesis is not run again.
+    /// The context's persister gets replaced by a FilePersister on that same directory so that
+    /// the loaded instance keeps its state up to date.
+    pub fn load(path: String, context: Arc<Context>) -> Result<Self, HolochainError> {
+        let persister = FilePersister::new(&path);
+        let loaded_state = persister.load(context.clone())?.ok_or_else(|| {
+            HolochainError::ErrorGeneric(format!("No persisted state found in '{}'", path))
+        })?;
+        if !loaded_state.nucleus().has_initialized() {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "Persisted state in '{}' was never initialized",
+                path
+            )));
+        }
+        let name = loaded_state
+            .nucleus()
+            .dna()
+            .map(|dna| dna.name)
+            .unwrap_or_default();
+
+        let mut context = (*context).clone();
+        context.persister = Arc::new(Mutex::new(persister));
+        let context = Arc::new(context);
+
+        let mut instance = Instance::from_state(loaded_state);
+        instance.start_action_loop(context.clone());
+        let context = instance.initialize_context(context);
+        context.log(&format!("{} loaded", name))?;
+        Ok(Holochain {
+            instance,
+            context,
+            active: false,
+        })
     }
 
     /// activate the Holochain instance
@@ -121,16 +171,35 @@ impl Holochain {
         if self.active {
             return Err(HolochainInstanceError::InstanceAlreadyActive);
         }
+        if !self.instance.action_loop_running() {
+            // a previous stop() has shut down the action loop
+            self.instance.start_action_loop(self.context.clone());
+            self.context = self.instance.initialize_context(self.context.clone());
+        }
         self.active = true;
+        self.context
+            .signal_registry
+            .emit(&Signal::System(SystemSignal::InstanceStarted));
         Ok(())
     }
 
-    /// deactivate the Holochain instance
+    /// deactivate the Holochain instance and shut down its action loop
     pub fn stop(&mut self) -> Result<(), HolochainInstanceError> {
+        self.stop_with_timeout(STOP_DEFAULT_TIMEOUT_MS)
+    }
+
+    /// deactivate the Holochain instance, waiting at most `timeout` for running zome calls
+    /// and validations to finish before shutting down the action loop.
+    /// The instance stays active if that fails, so stopping can be retried.
+    pub fn stop_with_timeout(&mut self, timeout: Duration) -> HolochainResult<()> {
         if !self.active {
             return Err(HolochainInstanceError::InstanceNotActiveYet);
         }
+        self.instance.stop_action_loop(&self.context, timeout)?;
         self.active = false;
+        self.context
+            .signal_registry
+            .emit(&Signal::System(SystemSignal::InstanceStopped));
         Ok(())
     }
 
@@ -149,6 +218,100 @@ impl Holochain {
         Ok(call_and_wait_for_result(zome_call, &mut self.instance)?)
     }
 
+    /// call a function in a zome without blocking.
+    /// The returned future resolves to the result of the zome function,
+    /// dropping it before that abandons the call.
+    pub fn call_async(
+        &mut self,
+        zome: &str,
+        cap: &str,
+        fn_name: &str,
+        params: &str,
+    ) -> HolochainResult<ZomeCallFuture> {
+        if !self.active {
+            return Err(HolochainInstanceError::InstanceNotActiveYet);
+        }
+        let zome_call = ZomeFnCall::new(&zome, &cap, &fn_name, &params);
+        Ok(call_zome_function(zome_call, &self.context))
+    }
+
+    /// call a function in a zome, waiting at most `timeout` for its result.
+    /// Returns a HolochainInstanceError::Timeout if the zome function takes longer,
+    /// its result gets dropped then.
+    pub fn call_with_timeout(
+        &mut self,
+        zome: &str,
+        cap: &str,
+        fn_name: &str,
+        params: &str,
+        timeout: Duration,
+    ) -> HolochainResult<String> {
+        if !self.active {
+            return Err(HolochainInstanceError::InstanceNotActiveYet);
+        }
+        let zome_call = ZomeFnCall::new(&zome, &cap, &fn_name, &params);
+        Ok(call_and_wait_for_result_with_timeout(
+            zome_call,
+            &mut self.instance,
+            timeout,
+        )?)
+    }
+
+    /// register a listener for the signals selected by the filter.
+    /// App signals can only be listened to if the DNA declares them
+    /// and the capability they belong to is accessible.
+    pub fn listen<F>(&mut self, filter: SignalFilter, listener: F) -> HolochainResult<ListenerId>
+    where
+        F: Fn(&Signal) + Send + Sync + 'static,
+    {
+        if let SignalFilter::App {
+            ref zome_name,
+            ref cap_name,
+            ref name,
+        } = filter
+        {
+            self.check_signal_access(zome_name, cap_name, name)?;
+        }
+        Ok(self.context.signal_registry.listen(filter, listener))
+    }
+
+    /// remove a listener that was registered with listen()
+    pub fn unlisten(&mut self, id: &ListenerId) -> HolochainResult<()> {
+        if self.context.signal_registry.unlisten(id) {
+            Ok(())
+        } else {
+            Err(HolochainError::new("No listener registered with this id").into())
+        }
+    }
+
+    fn check_signal_access(
+        &self,
+        zome_name: &str,
+        cap_name: &str,
+        name: &str,
+    ) -> HolochainResult<()> {
+        let dna = self
+            .instance
+            .state()
+            .nucleus()
+            .dna()
+            .ok_or(HolochainError::DnaMissing)?;
+        let capability = dna
+            .get_capability_with_zome_name(zome_name, cap_name)
+            .map_err(HolochainError::Dna)?;
+        if capability.get_signal(name).is_none() {
+            return Err(HolochainError::Dna(DnaError::SignalNotFound(format!(
+                "Signal '{}' not found in Capability '{}' of Zome '{}'",
+                name, cap_name, zome_name
+            ))).into());
+        }
+        match capability.cap_type.membrane {
+            Membrane::Public => Ok(()),
+            // TODO #301 - check the capability token of the listening client
+            _ => Err(HolochainError::DoesNotHaveCapabilityToken.into()),
+        }
+    }
+
     /// checks to see if an instance is active
     pub fn active(&self) -> bool {
         self.active
@@ -158,6 +321,12 @@ impl Holochain {
     pub fn state(&mut self) -> Result<State, HolochainInstanceError> {
         Ok(self.instance.state().clone())
     }
+
+    /// current runtime metrics of the instance,
+    /// MetricsSnapshot::to_prometheus() renders them for a Prometheus scraper
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.context.metrics.snapshot()
+    }
 }
 
 #[cfg(test)]
@@ -165,21 +334,26 @@ mod tests {
     extern crate holochain_cas_implementations;
 
     use self::holochain_cas_implementations::{
-        cas::file::FilesystemStorage, eav::file::EavFileStorage,
+        cas::{file::FilesystemStorage, memory::MemoryStorage},
+        eav::{file::EavFileStorage, memory::EavMemoryStorage},
     };
     use super::*;
     extern crate holochain_agent;
     use holochain_core::{
         context::Context,
-        nucleus::ribosome::{callback::Callback, Defn},
-        persister::SimplePersister,
+        nucleus::ribosome::{callback::Callback, metering::ExecutionLimits, Defn},
+        persister::{FilePersister, SimplePersister},
+    };
+    use holochain_dna::{diagnostics::DnaDiagnostic, zome::capabilities::SignalDeclaration, Dna};
+    use std::{
+        sync::{mpsc::channel, Arc, Mutex},
+        thread,
+        time::Instant,
     };
-    use holochain_dna::Dna;
-    use std::sync::{Arc, Mutex};
     use tempfile::tempdir;
     use test_utils::{
-        create_test_cap_with_fn_name, create_test_dna_with_cap, create_test_dna_with_wat,
-        create_wasm_from_file, hc_setup_and_call_zome_fn,
+        create_test_cap, create_test_cap_with_fn_name, create_test_dna_with_cap,
+        create_test_dna_with_wat, create_wasm_from_file, hc_setup_and_call_zome_fn,
     };
 
     // TODO: TestLogger duplicated in test_utils because:
@@ -222,13 +396,59 @@ mod tests {
         assert_eq!(format!("{:?}", *test_logger), "[\"TestApp instantiated\"]");
     }
 
-    #[test]
-    fn fails_instantiate_if_genesis_fails() {
-        let dna = create_test_dna_with_wat(
+    /// a dna whose lifecycle capability declares the genesis callback of the given wat
+    fn create_genesis_dna(wat: &str) -> Dna {
+        let mut dna = create_test_dna_with_wat(
             "test_zome",
             Callback::Genesis.capability().as_str(),
-            Some(
-                r#"
+            Some(wat),
+        );
+        dna.zomes
+            .get_mut("test_zome")
+            .unwrap()
+            .capabilities
+            .get_mut(Callback::Genesis.capability().as_str())
+            .unwrap()
+            .functions[0]
+            .name = Callback::Genesis.as_str().to_string();
+        dna
+    }
+
+    #[test]
+    fn fails_instantiate_if_dna_is_invalid() {
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        dna.zomes.get_mut("test_zome").unwrap().capabilities.insert(
+            "other_cap".to_string(),
+            create_test_cap_with_fn_name("missing"),
+        );
+        let (context, test_logger) = test_context("bob");
+        let result = Holochain::new(dna, context);
+
+        let diagnostics = vec![DnaDiagnostic::FunctionNotExported {
+            zome: "test_zome".to_string(),
+            capability: "other_cap".to_string(),
+            function: "missing".to_string(),
+        }];
+        match result {
+            Err(err) => {
+                assert_eq!(err, HolochainInstanceError::InvalidDna(diagnostics));
+                assert_eq!(
+                    err.to_string(),
+                    "Holochain Instance Error: The DNA is invalid.\n  \
+                     Zome 'test_zome': capability 'other_cap' declares 'missing', \
+                     which the wasm doesn't export"
+                );
+            }
+            Ok(_) => panic!("an invalid DNA should not get instantiated"),
+        }
+        // nothing got initialized
+        assert_eq!(format!("{:?}", *test_logger.lock().unwrap()), "[]");
+    }
+
+    #[test]
+    fn fails_instantiate_if_genesis_fails() {
+        let dna = create_genesis_dna(
+            r#"
             (module
                 (memory (;0;) 17)
                 (func (export "genesis") (param $p0 i32) (result i32)
@@ -240,7 +460,6 @@ mod tests {
                 (export "memory" (memory 0))
             )
         "#,
-            ),
         );
 
         let (context, _test_logger) = test_context("bob");
@@ -254,11 +473,8 @@ mod tests {
 
     #[test]
     fn fails_instantiate_if_genesis_times_out() {
-        let dna = create_test_dna_with_wat(
-            "test_zome",
-            Callback::Genesis.capability().as_str(),
-            Some(
-                r#"
+        let dna = create_genesis_dna(
+            r#"
             (module
                 (memory (;0;) 17)
                 (func (export "genesis") (param $p0 i32) (result i32)
@@ -268,7 +484,6 @@ mod tests {
                 (export "memory" (memory 0))
             )
         "#,
-            ),
         );
 
         let (context, _test_logger) = test_context("bob");
@@ -282,6 +497,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn can_load() {
+        let persist_dir = tempdir().unwrap();
+        let persist_path = persist_dir.path().to_str().unwrap().to_string();
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+
+        // an instance that persists its state
+        let (context, _) = test_context("bob");
+        let mut context = (*context).clone();
+        context.persister = Arc::new(Mutex::new(FilePersister::new(&persist_path)));
+        let hc = Holochain::new(dna.clone(), Arc::new(context.clone())).unwrap();
+        let top_chain_header = hc.instance.state().agent().top_chain_header();
+        assert!(top_chain_header.is_some());
+
+        // the restarted instance picks up DNA, status and chain from disk
+        let loaded = Holochain::load(persist_path, Arc::new(context)).unwrap();
+        assert_eq!(loaded.instance.state().nucleus().dna(), Some(dna));
+        assert!(loaded.instance.state().nucleus().has_initialized());
+        assert_eq!(
+            loaded.instance.state().agent().top_chain_header(),
+            top_chain_header
+        );
+        assert!(!loaded.active());
+    }
+
+    #[test]
+    fn load_fails_without_persisted_state() {
+        let persist_dir = tempdir().unwrap();
+        let (context, _) = test_context("bob");
+        let result = Holochain::load(persist_dir.path().to_str().unwrap().to_string(), context);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn can_start_and_stop() {
         let dna = Dna::new();
@@ -313,6 +562,23 @@ mod tests {
         assert!(!hc.active());
     }
 
+    #[test]
+    fn can_restart_after_stop() {
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+
+        hc.start().expect("couldn't start");
+        hc.stop().expect("couldn't stop");
+        assert!(!hc.instance.action_loop_running());
+
+        // starting again brings up a new action loop that processes calls
+        hc.start().expect("couldn't restart");
+        assert!(hc.instance.action_loop_running());
+        let result = hc.call("test_zome", "test_cap", "main", "");
+        assert_eq!(result, Ok("1337".to_string()));
+    }
+
     #[test]
     fn can_call() {
         let wat = r#"
@@ -358,6 +624,152 @@ mod tests {
         assert_eq!(result.unwrap().nucleus().dna(), Some(dna));
     }
 
+    #[test]
+    fn can_call_in_memory() {
+        let (_, logger) = test_context("bob");
+        let context = Arc::new(
+            Context::new(
+                holochain_agent::Agent::from("bob".to_string()),
+                logger,
+                Arc::new(Mutex::new(SimplePersister::new())),
+                MemoryStorage::new().unwrap(),
+                EavMemoryStorage::new().unwrap(),
+            ).unwrap(),
+        );
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "test_cap", "main", "");
+        assert_eq!(result, Ok("1337".to_string()));
+        // genesis committed the DNA and the agent to the in-memory source chain
+        let state = hc.state().unwrap();
+        let top_header = state.agent().top_chain_header();
+        assert_eq!(state.agent().chain().iter(&top_header).count(), 2);
+    }
+
+    #[test]
+    fn can_get_metrics() {
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "test_cap", "main", "");
+        assert_eq!(result, Ok("1337".to_string()));
+
+        let metrics = hc.metrics();
+        assert_eq!(metrics.actions.get("InitApplication"), Some(&1));
+        assert!(metrics.actions.get("ExecuteZomeFunction").is_some());
+        assert!(metrics.reducers.contains_key("nucleus"));
+        assert!(metrics.storage.contains_key("cas_add"));
+        let main_calls = metrics
+            .zome_calls
+            .iter()
+            .find(|call| call.fn_name == "main")
+            .expect("main should have been metered");
+        assert_eq!(main_calls.latency.count, 1);
+        assert_eq!(main_calls.errors, 0);
+        assert_eq!(metrics.action_queue.capacity, 100);
+        assert!(metrics.action_queue.max_depth >= 1);
+        assert!(metrics
+            .to_prometheus()
+            .contains("holochain_action_queue_capacity 100"));
+    }
+
+    #[test]
+    fn can_call_async() {
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+
+        let result = hc.call_async("test_zome", "test_cap", "main", "");
+        assert_eq!(
+            result.err().unwrap(),
+            HolochainInstanceError::InstanceNotActiveYet
+        );
+
+        hc.start().expect("couldn't start");
+
+        let future = hc
+            .call_async("test_zome", "test_cap", "main", "")
+            .expect("couldn't call");
+        assert_eq!(block_on(future), Ok("1337".to_string()));
+    }
+
+    #[test]
+    fn can_call_with_timeout() {
+        // counts down from 100 million, the instruction limit below cuts that short
+        let wat = r#"
+(module
+ (memory 1)
+ (export "memory" (memory 0))
+ (export "main" (func $func0))
+ (func $func0 (param $p0 i32) (result i32)
+       (local $i i32)
+       i32.const 100000000
+       set_local $i
+       block
+         loop
+           get_local $i
+           i32.const 1
+           i32.sub
+           tee_local $i
+           br_if 0
+         end
+       end
+       i32.const 0
+       )
+ )
+"#;
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", Some(wat));
+        let (context, _) = test_context("bob");
+        // don't leave the abandoned call looping while the other tests run
+        let mut context = (*context).clone();
+        context.execution_limits = ExecutionLimits {
+            max_instructions: 10_000_000,
+            ..ExecutionLimits::default()
+        };
+        let mut hc = Holochain::new(dna.clone(), Arc::new(context)).unwrap();
+        hc.start().expect("couldn't start");
+
+        let result = hc.call_with_timeout(
+            "test_zome",
+            "test_cap",
+            "main",
+            "",
+            Duration::from_millis(10),
+        );
+        match result {
+            Err(HolochainInstanceError::Timeout(_)) => {}
+            _ => assert!(false, "result = {:?}", result),
+        }
+        // the call got abandoned, so its result never shows up in the state
+        assert!(hc.state().unwrap().nucleus().zome_calls.is_empty());
+        // and it's forgotten about once the result that got dropped arrived
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !hc
+            .state()
+            .unwrap()
+            .nucleus()
+            .abandoned_zome_calls
+            .is_empty()
+        {
+            assert!(Instant::now() < deadline, "abandoned call never ended");
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert!(hc.state().unwrap().nucleus().zome_calls.is_empty());
+
+        let dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let result =
+            hc.call_with_timeout("test_zome", "test_cap", "main", "", Duration::from_secs(10));
+        assert_eq!(result, Ok("1337".to_string()));
+    }
+
     #[test]
     fn can_call_test() {
         let wasm = create_wasm_from_file(
@@ -400,7 +812,7 @@ mod tests {
         hc.start().expect("couldn't start");
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 4);
+        assert_eq!(hc.state().unwrap().history.len(), 6);
 
         // Call the exposed wasm function that calls the Commit API function
         let result = hc.call("test_zome", "test_cap", "test", r#"{}"#);
@@ -415,7 +827,7 @@ mod tests {
         // Check in holochain instance's history that the commit event has been processed
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 8);
+        assert_eq!(hc.state().unwrap().history.len(), 13);
     }
 
     #[test]
@@ -434,7 +846,7 @@ mod tests {
         hc.start().expect("couldn't start");
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 4);
+        assert_eq!(hc.state().unwrap().history.len(), 6);
 
         // Call the exposed wasm function that calls the Commit API function
         let result = hc.call("test_zome", "test_cap", "test_fail", r#"{}"#);
@@ -449,7 +861,7 @@ mod tests {
         // Check in holochain instance's history that the commit event has been processed
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 6);
+        assert_eq!(hc.state().unwrap().history.len(), 9);
     }
 
     #[test]
@@ -469,7 +881,7 @@ mod tests {
         hc.start().expect("couldn't start");
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 4);
+        assert_eq!(hc.state().unwrap().history.len(), 6);
 
         // Call the exposed wasm function that calls the Commit API function
         let result = hc.call("test_zome", "test_cap", "debug_hello", r#"{}"#);
@@ -477,13 +889,13 @@ mod tests {
 
         let test_logger = test_logger.lock().unwrap();
         assert_eq!(
-            "[\"TestApp instantiated\", \"zome_log:DEBUG: \\\'\\\"Hello world!\\\"\\\'\", \"Zome Function \\\'debug_hello\\\' returned: Success\"]",
+            "[\"TestApp instantiated\", \"\\\"Hello world!\\\"\", \"Zome Function \\\'debug_hello\\\' returned: Success\"]",
             format!("{:?}", test_logger.log),
         );
         // Check in holochain instance's history that the debug event has been processed
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 6);
+        assert_eq!(hc.state().unwrap().history.len(), 9);
     }
 
     #[test]
@@ -503,7 +915,7 @@ mod tests {
         hc.start().expect("couldn't start");
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 4);
+        assert_eq!(hc.state().unwrap().history.len(), 6);
 
         // Call the exposed wasm function that calls the Commit API function
         let result = hc.call("test_zome", "test_cap", "debug_multiple", r#"{}"#);
@@ -513,14 +925,14 @@ mod tests {
 
         let test_logger = test_logger.lock().unwrap();
         assert_eq!(
-            "[\"TestApp instantiated\", \"zome_log:DEBUG: \\\'\\\"Hello\\\"\\\'\", \"zome_log:DEBUG: \\\'\\\"world\\\"\\\'\", \"zome_log:DEBUG: \\\'\\\"!\\\"\\\'\", \"Zome Function \\\'debug_multiple\\\' returned: Success\"]",
+            "[\"TestApp instantiated\", \"\\\"Hello\\\"\", \"\\\"world\\\"\", \"\\\"!\\\"\", \"Zome Function \\\'debug_multiple\\\' returned: Success\"]",
             format!("{:?}", test_logger.log),
         );
 
         // Check in holochain instance's history that the deb event has been processed
         // @TODO don't use history length in tests
         // @see https://github.com/holochain/holochain-rust/issues/195
-        assert_eq!(hc.state().unwrap().history.len(), 6);
+        assert_eq!(hc.state().unwrap().history.len(), 9);
     }
 
     #[test]
@@ -532,4 +944,63 @@ mod tests {
         );
         assert_eq!("{\"value\":\"fish\"}", call_result.unwrap());
     }
+
+    #[test]
+    fn listen_checks_capabilities() {
+        let mut dna = create_test_dna_with_wat("test_zome", "test_cap", None);
+        {
+            let zome = dna.zomes.get_mut("test_zome").unwrap();
+            let mut signal = SignalDeclaration::new();
+            signal.name = "Post".to_string();
+            let mut public_cap = create_test_cap(Membrane::Public);
+            public_cap.signals.push(signal.clone());
+            zome.capabilities
+                .insert("public_cap".to_string(), public_cap);
+            let mut agent_cap = create_test_cap(Membrane::Agent);
+            agent_cap.signals.push(signal);
+            zome.capabilities.insert("agent_cap".to_string(), agent_cap);
+        }
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(dna, context).unwrap();
+        let filter = |cap_name: &str, name: &str| SignalFilter::App {
+            zome_name: "test_zome".to_string(),
+            cap_name: cap_name.to_string(),
+            name: name.to_string(),
+        };
+
+        let id = hc.listen(filter("public_cap", "Post"), |_| {}).unwrap();
+        assert_eq!(
+            hc.listen(filter("agent_cap", "Post"), |_| {}),
+            Err(HolochainInstanceError::InternalFailure(
+                HolochainError::DoesNotHaveCapabilityToken
+            ))
+        );
+        assert!(hc.listen(filter("public_cap", "Comment"), |_| {}).is_err());
+        assert!(hc.listen(filter("no_cap", "Post"), |_| {}).is_err());
+        assert!(hc.listen(SignalFilter::System, |_| {}).is_ok());
+
+        assert_eq!(hc.unlisten(&id), Ok(()));
+        assert!(hc.unlisten(&id).is_err());
+    }
+
+    #[test]
+    fn signals_instance_status_changes() {
+        let (context, _) = test_context("bob");
+        let mut hc = Holochain::new(Dna::new(), context).unwrap();
+        let (tx, rx) = channel();
+        let tx = Mutex::new(tx);
+        hc.listen(SignalFilter::System, move |signal| {
+            tx.lock().unwrap().send(signal.clone()).unwrap()
+        }).unwrap();
+
+        hc.start().unwrap();
+        hc.stop().unwrap();
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            vec![
+                Signal::System(SystemSignal::InstanceStarted),
+                Signal::System(SystemSignal::InstanceStopped),
+            ]
+        );
+    }
 }
@@ -1,26 +1,27 @@
 extern crate directories;
-extern crate holochain_agent;
-extern crate holochain_cas_implementations;
+extern crate holochain_container;
 extern crate holochain_core;
 extern crate holochain_core_api;
 extern crate holochain_core_types;
 extern crate holochain_dna;
 
-use holochain_cas_implementations::{
-    cas::file::FilesystemStorage, eav::file::EavFileStorage, path::create_path_if_not_exists,
+use holochain_container::{
+    config::{
+        AgentConfiguration, Configuration, DnaConfiguration, InstanceConfiguration,
+        StorageConfiguration,
+    },
+    container::instantiate_from_config,
 };
-use holochain_core::context::Context;
-use holochain_core_api::Holochain;
+use holochain_core::logger::Logger;
+use holochain_core_api::{error::HolochainResult, Holochain};
 use holochain_core_types::error::HolochainError;
 use holochain_dna::Dna;
-use std::sync::Arc;
-
-use holochain_agent::Agent;
-use holochain_core::{logger::Logger, persister::SimplePersister};
 use std::{
     ffi::{CStr, CString},
+    fs,
     os::raw::c_char,
-    sync::Mutex,
+    path::Path,
+    sync::{Arc, Mutex},
 };
 
 #[derive(Clone, Debug)]
@@ -33,16 +34,17 @@ impl Logger for NullLogger {
 #[no_mangle]
 pub unsafe extern "C" fn holochain_new(ptr: *mut Dna, storage_path: CStrPtr) -> *mut Holochain {
     let path = CStr::from_ptr(storage_path).to_string_lossy().into_owned();
-    let context = get_context(&path);
 
     assert!(!ptr.is_null());
     let dna = Box::from_raw(ptr);
 
-    match context {
-        Ok(con) => match Holochain::new(*dna, Arc::new(con)) {
-            Ok(hc) => Box::into_raw(Box::new(hc)),
-            Err(_) => std::ptr::null_mut(),
-        },
+    // the container loads the DNA from a file, so keep it next to the storage of the instance
+    let result: HolochainResult<Holochain> = fs::create_dir_all(&path)
+        .and_then(|_| fs::write(dna_path(&path), dna.to_json()))
+        .map_err(|error| HolochainError::from(error).into())
+        .and_then(|_| instantiate(&path));
+    match result {
+        Ok(hc) => Box::into_raw(Box::new(hc)),
         Err(_) => std::ptr::null_mut(),
     }
 }
@@ -50,30 +52,51 @@ pub unsafe extern "C" fn holochain_new(ptr: *mut Dna, storage_path: CStrPtr) ->
 #[no_mangle]
 pub unsafe extern "C" fn holochain_load(storage_path: CStrPtr) -> *mut Holochain {
     let path = CStr::from_ptr(storage_path).to_string_lossy().into_owned();
-    let context = get_context(&path);
 
-    match context {
-        Ok(con) => match Holochain::load(path, Arc::new(con)) {
-            Ok(hc) => Box::into_raw(Box::new(hc)),
-            Err(_) => std::ptr::null_mut(),
-        },
+    // only load instances that holochain_new created and persisted before
+    if !Path::new(&path).join("state").exists() {
+        return std::ptr::null_mut();
+    }
+    match instantiate(&path) {
+        Ok(hc) => Box::into_raw(Box::new(hc)),
         Err(_) => std::ptr::null_mut(),
     }
 }
 
-fn get_context(path: &String) -> Result<Context, HolochainError> {
-    let agent = Agent::from("c_bob".to_string());
-    let cas_path = format!("{}/cas", path);
-    let eav_path = format!("{}/eav", path);
-    create_path_if_not_exists(&cas_path)?;
-    create_path_if_not_exists(&eav_path)?;
-    Context::new(
-        agent,
-        Arc::new(Mutex::new(NullLogger {})),
-        Arc::new(Mutex::new(SimplePersister::new())),
-        FilesystemStorage::new(&cas_path).unwrap(),
-        EavFileStorage::new(eav_path).unwrap(),
-    )
+fn dna_path(path: &str) -> String {
+    format!("{}/dna.json", path)
+}
+
+/// The C API hands out a single instance, so it runs a container configuration
+/// with just that instance, storing everything below the given path.
+fn config(path: &str) -> Configuration {
+    Configuration {
+        agents: vec![AgentConfiguration {
+            id: "agent".to_string(),
+            name: "c_bob".to_string(),
+        }],
+        dnas: vec![DnaConfiguration {
+            id: "dna".to_string(),
+            file: dna_path(path),
+            hash: None,
+        }],
+        instances: vec![InstanceConfiguration {
+            id: "instance".to_string(),
+            dna: "dna".to_string(),
+            agent: "agent".to_string(),
+            storage: StorageConfiguration::File {
+                path: path.to_string(),
+            },
+            limits: Default::default(),
+        }],
+        interfaces: Vec::new(),
+    }
+}
+
+fn instantiate(path: &str) -> HolochainResult<Holochain> {
+    let config = config(path);
+    let logger: Arc<Mutex<Logger>> = Arc::new(Mutex::new(NullLogger {}));
+    instantiate_from_config(&config.instances[0], &config, &logger)
 }
 
 #[no_mangle]
@@ -3,8 +3,9 @@ use eav::{EntityAttributeValue, EntityAttributeValueStorage};
 use entry::{test_entry_unique, Entry};
 use error::HolochainError;
 use std::{
+    any::Any,
     collections::{HashMap, HashSet},
-    fmt::Debug,
+    fmt::{self, Debug},
     sync::{mpsc::channel, Arc, RwLock},
     thread,
 };
@@ -25,6 +26,108 @@ pub trait ContentAddressableStorage: Clone + Send + Sync {
     fn fetch<C: AddressableContent>(&self, address: &Address) -> Result<Option<C>, HolochainError>;
 }
 
+/// object safe part of ContentAddressableStorage, implemented for every CAS that can be wrapped
+/// in a DynContentAddressableStorage
+trait ObjectSafeContentAddressableStorage: Send + Sync + Debug {
+    fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError>;
+    fn contains(&self, address: &Address) -> Result<bool, HolochainError>;
+    fn fetch_content(&self, address: &Address) -> Result<Option<Content>, HolochainError>;
+    fn box_clone(&self) -> Box<ObjectSafeContentAddressableStorage>;
+    fn as_any(&self) -> &Any;
+    fn dyn_eq(&self, other: &ObjectSafeContentAddressableStorage) -> bool;
+}
+
+impl<CAS> ObjectSafeContentAddressableStorage for CAS
+where
+    CAS: ContentAddressableStorage + PartialEq + Debug + 'static,
+{
+    fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError> {
+        ContentAddressableStorage::add(self, content)
+    }
+
+    fn contains(&self, address: &Address) -> Result<bool, HolochainError> {
+        ContentAddressableStorage::contains(self, address)
+    }
+
+    fn fetch_content(&self, address: &Address) -> Result<Option<Content>, HolochainError> {
+        self.fetch::<Content>(address)
+    }
+
+    fn box_clone(&self) -> Box<ObjectSafeContentAddressableStorage> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &ObjectSafeContentAddressableStorage) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<CAS>()
+            .map_or(false, |other| self == other)
+    }
+}
+
+/// ContentAddressableStorage that hides the actual implementation behind a trait object,
+/// so a Holochain instance can be backed by any CAS (in memory, on disk, third-party)
+/// without being generic over it.
+/// Two of them are equal if they wrap equal storages of the same type.
+pub struct DynContentAddressableStorage {
+    storage: Box<ObjectSafeContentAddressableStorage>,
+}
+
+impl DynContentAddressableStorage {
+    pub fn new<CAS>(storage: CAS) -> Self
+    where
+        CAS: ContentAddressableStorage + PartialEq + Debug + 'static,
+    {
+        DynContentAddressableStorage {
+            storage: Box::new(storage),
+        }
+    }
+}
+
+impl Clone for DynContentAddressableStorage {
+    fn clone(&self) -> Self {
+        DynContentAddressableStorage {
+            storage: self.storage.box_clone(),
+        }
+    }
+}
+
+impl PartialEq for DynContentAddressableStorage {
+    fn eq(&self, other: &DynContentAddressableStorage) -> bool {
+        self.storage.dyn_eq(&*other.storage)
+    }
+}
+
+impl Debug for DynContentAddressableStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&*self.storage, f)
+    }
+}
+
+impl ContentAddressableStorage for DynContentAddressableStorage {
+    fn add(&mut self, content: &AddressableContent) -> Result<(), HolochainError> {
+        self.storage.add(content)
+    }
+
+    fn contains(&self, address: &Address) -> Result<bool, HolochainError> {
+        self.storage.contains(address)
+    }
+
+    fn fetch<AC: AddressableContent>(
+        &self,
+        address: &Address,
+    ) -> Result<Option<AC>, HolochainError> {
+        Ok(self
+            .storage
+            .fetch_content(address)?
+            .map(|content| AC::from_content(&content)))
+    }
+}
+
 #[derive(Clone)]
 /// some struct to show an example ContentAddressableStorage implementation
 /// this is a thread-safe wrapper around the non-thread-safe implementation below
@@ -3,7 +3,9 @@ use entry::{test_entry_a, test_entry_b, Entry};
 use error::{HcResult, HolochainError};
 use serde_json;
 use std::{
+    any::Any,
     collections::HashSet,
+    fmt::{self, Debug},
     sync::{Arc, RwLock},
 };
 
@@ -107,6 +109,107 @@ pub trait EntityAttributeValueStorage: Clone {
     ) -> Result<HashSet<EntityAttributeValue>, HolochainError>;
 }
 
+/// object safe part of EntityAttributeValueStorage, implemented for every EAV storage that can
+/// be wrapped in a DynEntityAttributeValueStorage
+trait ObjectSafeEntityAttributeValueStorage: Send + Sync + Debug {
+    fn add_eav(&mut self, eav: &EntityAttributeValue) -> Result<(), HolochainError>;
+    fn fetch_eav(
+        &self,
+        entity: Option<Entity>,
+        attribute: Option<Attribute>,
+        value: Option<Value>,
+    ) -> Result<HashSet<EntityAttributeValue>, HolochainError>;
+    fn box_clone(&self) -> Box<ObjectSafeEntityAttributeValueStorage>;
+    fn as_any(&self) -> &Any;
+    fn dyn_eq(&self, other: &ObjectSafeEntityAttributeValueStorage) -> bool;
+}
+
+impl<EAVS> ObjectSafeEntityAttributeValueStorage for EAVS
+where
+    EAVS: EntityAttributeValueStorage + Send + Sync + PartialEq + Debug + 'static,
+{
+    fn add_eav(&mut self, eav: &EntityAttributeValue) -> Result<(), HolochainError> {
+        EntityAttributeValueStorage::add_eav(self, eav)
+    }
+
+    fn fetch_eav(
+        &self,
+        entity: Option<Entity>,
+        attribute: Option<Attribute>,
+        value: Option<Value>,
+    ) -> Result<HashSet<EntityAttributeValue>, HolochainError> {
+        EntityAttributeValueStorage::fetch_eav(self, entity, attribute, value)
+    }
+
+    fn box_clone(&self) -> Box<ObjectSafeEntityAttributeValueStorage> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &ObjectSafeEntityAttributeValueStorage) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<EAVS>()
+            .map_or(false, |other| self == other)
+    }
+}
+
+/// EntityAttributeValueStorage that hides the actual implementation behind a trait object,
+/// so a Holochain instance can be backed by any EAV storage without being generic over it.
+/// Two of them are equal if they wrap equal storages of the same type.
+pub struct DynEntityAttributeValueStorage {
+    storage: Box<ObjectSafeEntityAttributeValueStorage>,
+}
+
+impl DynEntityAttributeValueStorage {
+    pub fn new<EAVS>(storage: EAVS) -> Self
+    where
+        EAVS: EntityAttributeValueStorage + Send + Sync + PartialEq + Debug + 'static,
+    {
+        DynEntityAttributeValueStorage {
+            storage: Box::new(storage),
+        }
+    }
+}
+
+impl Clone for DynEntityAttributeValueStorage {
+    fn clone(&self) -> Self {
+        DynEntityAttributeValueStorage {
+            storage: self.storage.box_clone(),
+        }
+    }
+}
+
+impl PartialEq for DynEntityAttributeValueStorage {
+    fn eq(&self, other: &DynEntityAttributeValueStorage) -> bool {
+        self.storage.dyn_eq(&*other.storage)
+    }
+}
+
+impl Debug for DynEntityAttributeValueStorage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Debug::fmt(&*self.storage, f)
+    }
+}
+
+impl EntityAttributeValueStorage for DynEntityAttributeValueStorage {
+    fn add_eav(&mut self, eav: &EntityAttributeValue) -> Result<(), HolochainError> {
+        self.storage.add_eav(eav)
+    }
+
+    fn fetch_eav(
+        &self,
+        entity: Option<Entity>,
+        attribute: Option<Attribute>,
+        value: Option<Value>,
+    ) -> Result<HashSet<EntityAttributeValue>, HolochainError> {
+        self.storage.fetch_eav(entity, attribute, value)
+    }
+}
+
 pub struct ExampleEntityAttributeValueStorageNonSync {
     storage: HashSet<EntityAttributeValue>,
 }
@@ -48,7 +48,7 @@ impl EntryType {
         // must not be empty
         entry_type_name.len() > 0
         // Must not have sys_prefix
-            && &entry_type_name[0..1] != "%"
+            && !entry_type_name.starts_with('%')
     }
 }
 
@@ -1,10 +1,13 @@
 use std::{error::Error, fmt};
 
-#[derive(Clone, Debug, PartialEq, Hash, Eq)]
+#[derive(Clone, Debug, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub enum DnaError {
     ZomeNotFound(String),
     CapabilityNotFound(String),
     ZomeFunctionNotFound(String),
+    SignalNotFound(String),
+    InvalidPackage(String),
+    PropertyNotFound(String),
 }
 
 impl Error for DnaError {
@@ -13,6 +16,9 @@ impl Error for DnaError {
             DnaError::ZomeNotFound(err_msg) => &err_msg,
             DnaError::CapabilityNotFound(err_msg) => &err_msg,
             DnaError::ZomeFunctionNotFound(err_msg) => &err_msg,
+            DnaError::SignalNotFound(err_msg) => &err_msg,
+            DnaError::InvalidPackage(err_msg) => &err_msg,
+            DnaError::PropertyNotFound(err_msg) => &err_msg,
         }
     }
 }
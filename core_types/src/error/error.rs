@@ -1,5 +1,5 @@
 use self::HolochainError::*;
-use error::DnaError;
+use error::{DnaError, RibosomeErrorReport};
 use futures::channel::oneshot::Canceled as FutureCanceled;
 use json::ToJson;
 use serde_json::Error as SerdeError;
@@ -10,7 +10,7 @@ use std::{
 };
 
 /// Enum holding all Holochain specific errors
-#[derive(Clone, Debug, PartialEq, Hash, Eq)]
+#[derive(Clone, Debug, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub enum HolochainError {
     ErrorGeneric(String),
     NotImplemented,
@@ -23,6 +23,11 @@ pub enum HolochainError {
     DoesNotHaveCapabilityToken,
     ValidationFailed(String),
     RibosomeFailed(String),
+    Timeout(String),
+    ConfigError(String),
+    ExecutionLimitExceeded(String),
+    /// a zome called abort, reporting where
+    ZomeAborted(RibosomeErrorReport),
 }
 
 pub type HcResult<T> = Result<T, HolochainError>;
@@ -63,6 +68,10 @@ impl Error for HolochainError {
             DoesNotHaveCapabilityToken => "Caller does not have Capability to make that call",
             ValidationFailed(fail_msg) => &fail_msg,
             RibosomeFailed(fail_msg) => &fail_msg,
+            Timeout(timeout_msg) => &timeout_msg,
+            ConfigError(err_msg) => &err_msg,
+            ExecutionLimitExceeded(err_msg) => &err_msg,
+            ZomeAborted(report) => &report.description,
         }
     }
 }
@@ -180,6 +189,8 @@ mod tests {
                 HolochainError::DoesNotHaveCapabilityToken,
                 "Caller does not have Capability to make that call",
             ),
+            (HolochainError::Timeout(String::from("foo")), "foo"),
+            (HolochainError::ConfigError(String::from("foo")), "foo"),
         ] {
             assert_eq!(output, input.description());
         }
@@ -1,11 +1,14 @@
 use self::{RibosomeErrorCode::*, RibosomeReturnCode::*};
 use std::fmt;
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Hash, Serialize)]
 pub struct RibosomeErrorReport {
     pub description: String,
     pub file_name: String,
     pub line: String,
+    /// empty if the reporter didn't tell
+    #[serde(default)]
+    pub column: String,
     // TODO #395 - Add advance error debugging info
     // pub stack_trace: Backtrace
 }
@@ -14,9 +17,13 @@ impl fmt::Display for RibosomeErrorReport {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Ribosome error: {}\n  --> {}:{}\n",
+            "Ribosome error: {}\n  --> {}:{}",
             self.description, self.file_name, self.line,
-        )
+        )?;
+        if !self.column.is_empty() {
+            write!(f, ":{}", self.column)?;
+        }
+        writeln!(f)
     }
 }
 
@@ -44,6 +51,7 @@ pub enum RibosomeErrorCode {
     ResponseSerializationFailed     = 7 << 16,
     NotAnAllocation                 = 8 << 16,
     ZeroSizedAllocation             = 9 << 16,
+    Timeout                         = 10 << 16,
 }
 
 impl ToString for RibosomeReturnCode {
@@ -68,6 +76,7 @@ impl ToString for RibosomeErrorCode {
             ResponseSerializationFailed     => "Response serialization failed",
             NotAnAllocation                 => "Not an allocation",
             ZeroSizedAllocation             => "Zero-sized allocation",
+            Timeout                         => "Timeout",
         }.to_string()
     }
 }
@@ -97,6 +106,7 @@ impl RibosomeErrorCode {
             7 => ResponseSerializationFailed,
             8 => NotAnAllocation,
             9 => ZeroSizedAllocation,
+            10 => Timeout,
             1 | _ => Unspecified,
         }
     }
@@ -136,6 +146,7 @@ pub mod tests {
             description: description.to_string(),
             file_name: file!().to_string(),
             line: line!().to_string(),
+            column: String::new(),
         };
 
         assert_ne!(
@@ -144,7 +155,31 @@ pub mod tests {
                 description: description.to_string(),
                 file_name: file!().to_string(),
                 line: line!().to_string(),
+                column: String::new(),
             }.to_string(),
         );
     }
+
+    #[test]
+    fn ribosome_error_report_with_column_to_string() {
+        let report = RibosomeErrorReport {
+            description: "oops".to_string(),
+            file_name: "a.ts".to_string(),
+            line: "12".to_string(),
+            column: "3".to_string(),
+        };
+        assert_eq!(
+            "Ribosome error: oops\n  --> a.ts:12:3\n",
+            report.to_string()
+        );
+    }
+
+    #[test]
+    fn ribosome_error_report_without_column_deserializes() {
+        let report: RibosomeErrorReport =
+            ::serde_json::from_str(r#"{"description":"oops","file_name":"a.ts","line":"12"}"#)
+                .unwrap();
+        assert_eq!("", report.column);
+        assert_eq!("Ribosome error: oops\n  --> a.ts:12\n", report.to_string());
+    }
 }
@@ -0,0 +1,443 @@
+//! holochain_dna::diagnostics is a module for checking the semantics of a dna, beyond what
+//! deserializing it already checks.
+//! @see Dna::validate
+
+use holochain_core_types::entry_type::EntryType;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use zome::{
+    capabilities::{ReservedCapabilityNames, RESERVED_CAPABILITY_PREFIX},
+    Zome,
+};
+use Dna;
+
+/// Link bases the dna manifest specification names, besides entry types.
+/// @see doc/specs/dna_manifest_specification.md
+pub const SYSTEM_LINK_BASES: [&str; 2] = ["__AGENT_HASH__", "__DNA_HASH__"];
+
+/// A problem with a dna, precise enough to point to the definition that causes it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DnaDiagnostic {
+    /// The name of an entry type is no valid app entry type name.
+    InvalidEntryTypeName { zome: String, entry_type: String },
+    /// The schema of an entry type is neither an object nor a boolean.
+    InvalidEntrySchema { zome: String, entry_type: String },
+    /// More than one zome defines an entry type with the same name.
+    DuplicateEntryType {
+        entry_type: String,
+        zomes: Vec<String>,
+    },
+    /// An entry type links to a type no zome defines.
+    UndefinedLinkTarget {
+        zome: String,
+        entry_type: String,
+        target_type: String,
+    },
+    /// An entry type gets linked from a type no zome defines.
+    UndefinedLinkBase {
+        zome: String,
+        entry_type: String,
+        base_type: String,
+    },
+    /// A capability has a reserved name core doesn't know.
+    UnknownReservedCapability { zome: String, capability: String },
+    /// A reserved capability declares a function that is none of its callbacks.
+    InvalidReservedCapabilityFunction {
+        zome: String,
+        capability: String,
+        function: String,
+    },
+    /// A capability declares a function the wasm of its zome doesn't export.
+    FunctionNotExported {
+        zome: String,
+        capability: String,
+        function: String,
+    },
+    /// A zome has no wasm.
+    EmptyWasm { zome: String },
+    /// The wasm of a zome can't be read.
+    InvalidWasm { zome: String, reason: String },
+}
+
+impl fmt::Display for DnaDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DnaDiagnostic::InvalidEntryTypeName { zome, entry_type } => write!(
+                f,
+                "Zome '{}': '{}' is not a valid entry type name",
+                zome, entry_type
+            ),
+            DnaDiagnostic::InvalidEntrySchema { zome, entry_type } => write!(
+                f,
+                "Zome '{}': the schema of entry type '{}' is neither an object nor a boolean",
+                zome, entry_type
+            ),
+            DnaDiagnostic::DuplicateEntryType { entry_type, zomes } => write!(
+                f,
+                "Entry type '{}' is defined by more than one zome: {}",
+                entry_type,
+                zomes.join(", ")
+            ),
+            DnaDiagnostic::UndefinedLinkTarget {
+                zome,
+                entry_type,
+                target_type,
+            } => write!(
+                f,
+                "Zome '{}': entry type '{}' links to the undefined entry type '{}'",
+                zome, entry_type, target_type
+            ),
+            DnaDiagnostic::UndefinedLinkBase {
+                zome,
+                entry_type,
+                base_type,
+            } => write!(
+                f,
+                "Zome '{}': entry type '{}' is linked from the undefined entry type '{}'",
+                zome, entry_type, base_type
+            ),
+            DnaDiagnostic::UnknownReservedCapability { zome, capability } => write!(
+                f,
+                "Zome '{}': capability '{}' uses the reserved prefix '{}'",
+                zome, capability, RESERVED_CAPABILITY_PREFIX
+            ),
+            DnaDiagnostic::InvalidReservedCapabilityFunction {
+                zome,
+                capability,
+                function,
+            } => write!(
+                f,
+                "Zome '{}': reserved capability '{}' can't declare '{}', which is none of its callbacks",
+                zome, capability, function
+            ),
+            DnaDiagnostic::FunctionNotExported {
+                zome,
+                capability,
+                function,
+            } => write!(
+                f,
+                "Zome '{}': capability '{}' declares '{}', which the wasm doesn't export",
+                zome, capability, function
+            ),
+            DnaDiagnostic::EmptyWasm { zome } => write!(f, "Zome '{}' has no wasm", zome),
+            DnaDiagnostic::InvalidWasm { zome, reason } => {
+                write!(f, "Zome '{}' has invalid wasm: {}", zome, reason)
+            }
+        }
+    }
+}
+
+/// All problems of the dna, zome by zome in the order of their names.
+pub(crate) fn validate_dna(dna: &Dna) -> Vec<DnaDiagnostic> {
+    let zomes: BTreeMap<&String, &Zome> = dna.zomes.iter().collect();
+
+    // names of the app entry types with the zomes defining them
+    let mut entry_types: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (zome_name, zome) in &zomes {
+        for entry_type in zome.entry_types.keys() {
+            entry_types
+                .entry(entry_type.as_str())
+                .or_insert_with(Vec::new)
+                .push(zome_name.to_string());
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for (zome_name, zome) in &zomes {
+        validate_entry_types(zome_name, zome, &entry_types, &mut diagnostics);
+        validate_capabilities(zome_name, zome, &mut diagnostics);
+    }
+    for (entry_type, zomes) in entry_types {
+        if zomes.len() > 1 {
+            diagnostics.push(DnaDiagnostic::DuplicateEntryType {
+                entry_type: entry_type.to_string(),
+                zomes,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Can links start or end at the given type?
+fn is_link_type(type_name: &str, entry_types: &BTreeMap<&str, Vec<String>>) -> bool {
+    entry_types.contains_key(type_name)
+        || SYSTEM_LINK_BASES.contains(&type_name)
+        || EntryType::from_str(type_name)
+            .map(|entry_type| entry_type.is_sys())
+            .unwrap_or(false)
+}
+
+fn validate_entry_types(
+    zome_name: &str,
+    zome: &Zome,
+    entry_types: &BTreeMap<&str, Vec<String>>,
+    diagnostics: &mut Vec<DnaDiagnostic>,
+) {
+    let zome_entry_types: BTreeMap<_, _> = zome.entry_types.iter().collect();
+    for (entry_type, entry_type_def) in zome_entry_types {
+        if !EntryType::has_valid_app_name(entry_type) {
+            diagnostics.push(DnaDiagnostic::InvalidEntryTypeName {
+                zome: zome_name.to_string(),
+                entry_type: entry_type.clone(),
+            });
+        }
+        if let Some(ref schema) = entry_type_def.schema {
+            if !schema.is_valid() {
+                diagnostics.push(DnaDiagnostic::InvalidEntrySchema {
+                    zome: zome_name.to_string(),
+                    entry_type: entry_type.clone(),
+                });
+            }
+        }
+        for link in &entry_type_def.links_to {
+            if !is_link_type(&link.target_type, entry_types) {
+                diagnostics.push(DnaDiagnostic::UndefinedLinkTarget {
+                    zome: zome_name.to_string(),
+                    entry_type: entry_type.clone(),
+                    target_type: link.target_type.clone(),
+                });
+            }
+        }
+        for link in &entry_type_def.linked_from {
+            if !is_link_type(&link.base_type, entry_types) {
+                diagnostics.push(DnaDiagnostic::UndefinedLinkBase {
+                    zome: zome_name.to_string(),
+                    entry_type: entry_type.clone(),
+                    base_type: link.base_type.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn validate_capabilities(zome_name: &str, zome: &Zome, diagnostics: &mut Vec<DnaDiagnostic>) {
+    // without readable wasm, there is no telling which functions exist
+    let exported_functions = if zome.code.code.is_empty() {
+        diagnostics.push(DnaDiagnostic::EmptyWasm {
+            zome: zome_name.to_string(),
+        });
+        None
+    } else {
+        match zome.code.exported_functions() {
+            Ok(functions) => Some(functions),
+            Err(reason) => {
+                diagnostics.push(DnaDiagnostic::InvalidWasm {
+                    zome: zome_name.to_string(),
+                    reason,
+                });
+                None
+            }
+        }
+    };
+
+    let capabilities: BTreeMap<_, _> = zome.capabilities.iter().collect();
+    for (capability_name, capability) in capabilities {
+        if capability_name.starts_with(RESERVED_CAPABILITY_PREFIX) {
+            match ReservedCapabilityNames::from_str(capability_name) {
+                Ok(reserved) => {
+                    for function in &capability.functions {
+                        if !reserved.callbacks().contains(&function.name.as_str()) {
+                            diagnostics.push(DnaDiagnostic::InvalidReservedCapabilityFunction {
+                                zome: zome_name.to_string(),
+                                capability: capability_name.clone(),
+                                function: function.name.clone(),
+                            });
+                        }
+                    }
+                }
+                Err(_) => diagnostics.push(DnaDiagnostic::UnknownReservedCapability {
+                    zome: zome_name.to_string(),
+                    capability: capability_name.clone(),
+                }),
+            }
+        }
+        if let Some(ref exported_functions) = exported_functions {
+            for function in &capability.functions {
+                if !exported_functions.contains(&function.name) {
+                    diagnostics.push(DnaDiagnostic::FunctionNotExported {
+                        zome: zome_name.to_string(),
+                        capability: capability_name.clone(),
+                        function: function.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use wasm::{tests::test_wasm, DnaWasm};
+    use zome::{
+        capabilities::{Capability, FnDeclaration},
+        entry_types::{EntryTypeDef, LinkedFrom, LinksTo},
+        schema::EntrySchema,
+    };
+
+    /// a zome with the given entry types and a capability "test_cap" declaring "main",
+    /// which the wasm exports
+    fn test_zome(entry_types: Vec<(&str, EntryTypeDef)>) -> Zome {
+        let mut zome = Zome::default();
+        zome.code = DnaWasm { code: test_wasm() };
+        for (name, entry_type_def) in entry_types {
+            zome.entry_types.insert(name.to_string(), entry_type_def);
+        }
+        zome.capabilities
+            .insert("test_cap".to_string(), capability(vec!["main"]));
+        zome
+    }
+
+    fn capability(functions: Vec<&str>) -> Capability {
+        let mut capability = Capability::new();
+        for name in functions {
+            let mut function = FnDeclaration::new();
+            function.name = name.to_string();
+            capability.functions.push(function);
+        }
+        capability
+    }
+
+    fn links_to(target_type: &str) -> LinksTo {
+        let mut link = LinksTo::new();
+        link.target_type = target_type.to_string();
+        link
+    }
+
+    fn linked_from(base_type: &str) -> LinkedFrom {
+        let mut link = LinkedFrom::new();
+        link.base_type = base_type.to_string();
+        link
+    }
+
+    #[test]
+    fn accepts_valid_dna() {
+        let mut post = EntryTypeDef::new();
+        post.links_to.push(links_to("handle"));
+        let mut handle = EntryTypeDef::new();
+        handle.links_to.push(links_to("post"));
+        handle.linked_from.push(linked_from("__AGENT_HASH__"));
+        handle.linked_from.push(linked_from("%agent_id"));
+
+        let mut dna = Dna::new();
+        dna.zomes
+            .insert("blog".to_string(), test_zome(vec![("post", post)]));
+        dna.zomes
+            .insert("users".to_string(), test_zome(vec![("handle", handle)]));
+        dna.zomes.get_mut("blog").unwrap().capabilities.insert(
+            ReservedCapabilityNames::LifeCycle.as_str().to_string(),
+            capability(vec![]),
+        );
+
+        assert_eq!(dna.validate(), Vec::new());
+        assert_eq!(Dna::new().validate(), Vec::new());
+    }
+
+    #[test]
+    fn finds_entry_type_problems() {
+        let mut post = EntryTypeDef::new();
+        post.schema = Some(EntrySchema(json!("object")));
+        post.links_to.push(links_to("comment"));
+        post.linked_from.push(linked_from("author"));
+
+        let mut dna = Dna::new();
+        dna.zomes.insert(
+            "blog".to_string(),
+            test_zome(vec![("post", post), ("%post", EntryTypeDef::new())]),
+        );
+        dna.zomes.insert(
+            "drafts".to_string(),
+            test_zome(vec![("post", EntryTypeDef::new())]),
+        );
+
+        assert_eq!(
+            dna.validate(),
+            vec![
+                DnaDiagnostic::InvalidEntryTypeName {
+                    zome: "blog".to_string(),
+                    entry_type: "%post".to_string(),
+                },
+                DnaDiagnostic::InvalidEntrySchema {
+                    zome: "blog".to_string(),
+                    entry_type: "post".to_string(),
+                },
+                DnaDiagnostic::UndefinedLinkTarget {
+                    zome: "blog".to_string(),
+                    entry_type: "post".to_string(),
+                    target_type: "comment".to_string(),
+                },
+                DnaDiagnostic::UndefinedLinkBase {
+                    zome: "blog".to_string(),
+                    entry_type: "post".to_string(),
+                    base_type: "author".to_string(),
+                },
+                DnaDiagnostic::DuplicateEntryType {
+                    entry_type: "post".to_string(),
+                    zomes: vec!["blog".to_string(), "drafts".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_capability_problems() {
+        let mut zome = test_zome(vec![]);
+        zome.capabilities
+            .insert("test_cap".to_string(), capability(vec!["main", "missing"]));
+        zome.capabilities
+            .insert("hc_lifecycle".to_string(), capability(vec!["main"]));
+        zome.capabilities
+            .insert("hc_mine".to_string(), capability(vec![]));
+        let mut dna = Dna::new();
+        dna.zomes.insert("blog".to_string(), zome);
+
+        assert_eq!(
+            dna.validate(),
+            vec![
+                DnaDiagnostic::InvalidReservedCapabilityFunction {
+                    zome: "blog".to_string(),
+                    capability: "hc_lifecycle".to_string(),
+                    function: "main".to_string(),
+                },
+                DnaDiagnostic::UnknownReservedCapability {
+                    zome: "blog".to_string(),
+                    capability: "hc_mine".to_string(),
+                },
+                DnaDiagnostic::FunctionNotExported {
+                    zome: "blog".to_string(),
+                    capability: "test_cap".to_string(),
+                    function: "missing".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            dna.validate()[2].to_string(),
+            "Zome 'blog': capability 'test_cap' declares 'missing', which the wasm doesn't export"
+        );
+    }
+
+    #[test]
+    fn finds_wasm_problems() {
+        let mut dna = Dna::new();
+        let mut empty = test_zome(vec![]);
+        empty.code = DnaWasm::new();
+        dna.zomes.insert("empty".to_string(), empty);
+        let mut invalid = test_zome(vec![]);
+        invalid.code = DnaWasm {
+            code: vec![1, 2, 3],
+        };
+        dna.zomes.insert("invalid".to_string(), invalid);
+
+        assert_eq!(
+            dna.validate(),
+            vec![
+                DnaDiagnostic::EmptyWasm {
+                    zome: "empty".to_string(),
+                },
+                DnaDiagnostic::InvalidWasm {
+                    zome: "invalid".to_string(),
+                    reason: "not a WebAssembly binary".to_string(),
+                },
+            ]
+        );
+    }
+}
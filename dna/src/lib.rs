@@ -26,21 +26,28 @@ extern crate serde;
 #[macro_use]
 extern crate serde_json;
 extern crate base64;
+extern crate multihash;
 extern crate uuid;
 
 use serde_json::Value;
 use std::hash::{Hash, Hasher};
 
+pub mod diagnostics;
+pub mod package;
 pub mod wasm;
 pub mod zome;
 
+use diagnostics::DnaDiagnostic;
 use holochain_core_types::{
     cas::content::AddressableContent,
     entry::{Entry, ToEntry},
     entry_type::EntryType,
-    error::DnaError,
+    error::{DnaError, HolochainError},
+    hash::HashString,
 };
-use std::collections::HashMap;
+use multihash::Hash as Multihash;
+use package::{canonical_json, DnaPackage};
+use std::{collections::HashMap, path::Path};
 use uuid::Uuid;
 use zome::{capabilities::Capability, entry_types::EntryTypeDef};
 
@@ -134,6 +141,14 @@ impl Dna {
         serde_json::from_str(dna)
     }
 
+    /// Load a dna from a `.hcpkg` package file, after verifying the hashes of the package.
+    /// @see package
+    pub fn from_package_file<P: AsRef<Path>>(path: P) -> Result<Self, HolochainError> {
+        let package = DnaPackage::from_file(path)?;
+        package.verify()?;
+        Ok(package.dna())
+    }
+
     /// Generate a json string from an in-memory dna struct.
     ///
     /// # Examples
@@ -149,6 +164,26 @@ impl Dna {
         serde_json::to_string(self).expect("DNA should serialize")
     }
 
+    /// Generate a json string from an in-memory dna struct that is the same for equal dnas,
+    /// with the keys of all objects sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use holochain_dna::Dna;
+    ///
+    /// let dna = Dna::new();
+    /// assert_eq!(dna.to_canonical_json(), dna.clone().to_canonical_json());
+    /// ```
+    pub fn to_canonical_json(&self) -> String {
+        canonical_json(self)
+    }
+
+    /// The hash that identifies the dna, computed from its canonical json.
+    pub fn content_hash(&self) -> HashString {
+        HashString::encode_from_str(&self.to_canonical_json(), Multihash::SHA2256)
+    }
+
     /// Generate a pretty-printed json string from an in-memory dna struct.
     ///
     /// # Examples
@@ -164,6 +199,21 @@ impl Dna {
         serde_json::to_string_pretty(self)
     }
 
+    /// Check the semantics of the dna, returning everything that is wrong with it.
+    /// A dna is only fit to run without any diagnostics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use holochain_dna::Dna;
+    ///
+    /// let dna = Dna::new();
+    /// assert!(dna.validate().is_empty());
+    /// ```
+    pub fn validate(&self) -> Vec<DnaDiagnostic> {
+        diagnostics::validate_dna(self)
+    }
+
     /// Return a Zome
     pub fn get_zome(&self, zome_name: &str) -> Option<&zome::Zome> {
         self.zomes.get(zome_name)
@@ -245,7 +295,7 @@ impl Dna {
 
 impl Hash for Dna {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        let s = self.to_json();
+        let s = self.to_canonical_json();
         s.hash(state);
     }
 }
@@ -253,7 +303,7 @@ impl Hash for Dna {
 impl PartialEq for Dna {
     fn eq(&self, other: &Dna) -> bool {
         // need to guarantee that PartialEq and Hash always agree
-        self.to_json() == other.to_json()
+        self.to_canonical_json() == other.to_canonical_json()
     }
 }
 
@@ -359,6 +409,13 @@ pub mod tests {
                                         "inputs": [],
                                         "outputs": []
                                     }
+                                ],
+                                "signals": [
+                                    {
+                                        "name": "test",
+                                        "description": "test",
+                                        "arguments": []
+                                    }
                                 ]
                             }
                         },
@@ -532,6 +589,26 @@ pub mod tests {
         ).unwrap();
     }
 
+    #[test]
+    fn content_hash_does_not_depend_on_zome_order() {
+        let mut dna1 = test_dna();
+        dna1.uuid = String::from(UNIT_UUID);
+        let mut dna2 = dna1.clone();
+        for name in &["zome1", "zome2", "zome3"] {
+            dna1.zomes.insert(name.to_string(), test_zome());
+        }
+        for name in &["zome3", "zome2", "zome1"] {
+            dna2.zomes.insert(name.to_string(), test_zome());
+        }
+
+        assert_eq!(dna1.to_canonical_json(), dna2.to_canonical_json());
+        assert_eq!(dna1.content_hash(), dna2.content_hash());
+        assert_eq!(dna1, dna2);
+
+        dna2.properties = json!({"max_post_length": 280});
+        assert_ne!(dna1.content_hash(), dna2.content_hash());
+    }
+
     #[test]
     fn parse_accepts_arbitrary_dna_properties() {
         let dna = Dna::from_json_str(
@@ -0,0 +1,521 @@
+//! holochain_dna::package is a module for bundling a dna with everything needed to run and
+//! present it into a single `.hcpkg` file:
+//!  - the manifest, i.e. the dna with the code of its zomes moved out
+//!  - the wasm of every zome, together with its hash
+//!  - optional UI assets
+//!  - a hash of all of the above, so loaders can tell the package is unaltered
+//!
+//! Packages are json files:
+//!
+//! ```json
+//! {
+//!     "format_version": 1,
+//!     "manifest": { "name": "...", "zomes": { "blog": { "code": { "code": "" }, ... } } },
+//!     "zomes": { "blog": { "code": "<base64 wasm>", "hash": "Qm..." } },
+//!     "ui": { "index.html": { "content": "<base64>" } },
+//!     "hash": "Qm..."
+//! }
+//! ```
+
+use holochain_core_types::{
+    error::{DnaError, HolochainError},
+    hash::HashString,
+};
+use multihash::Hash;
+use serde::Serialize;
+use serde_json::{self, Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs, mem,
+    path::{Path, PathBuf},
+};
+use wasm::DnaWasm;
+use Dna;
+
+/// Version of the package format this crate reads and writes.
+pub const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+/// File extension of dna packages.
+pub const PACKAGE_EXTENSION: &str = "hcpkg";
+
+/// File name suffix the builder looks for to find the manifest in a source directory.
+pub const MANIFEST_SUFFIX: &str = ".dna.json";
+
+/// Directory of a source directory the builder takes UI assets from by default.
+pub const UI_DIR: &str = "ui";
+
+/// Represents a dna package.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DnaPackage {
+    /// Which version of the package format does this represent?
+    pub format_version: u32,
+
+    /// The dna, without the code of its zomes.
+    pub manifest: Dna,
+
+    /// The wasm of every zome of the manifest, by zome name.
+    pub zomes: BTreeMap<String, PackagedWasm>,
+
+    /// UI assets, by their path relative to the UI directory, separated with '/'.
+    #[serde(default)]
+    pub ui: BTreeMap<String, UiAsset>,
+
+    /// Hash of the rest of the package.
+    pub hash: String,
+}
+
+/// The wasm of a zome, with the hash it gets verified against.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PackagedWasm {
+    #[serde(
+        serialize_with = "::wasm::_vec_u8_to_b64_str",
+        deserialize_with = "::wasm::_b64_str_to_vec_u8"
+    )]
+    pub code: Vec<u8>,
+    pub hash: String,
+}
+
+impl PackagedWasm {
+    pub fn new(code: Vec<u8>) -> Self {
+        PackagedWasm {
+            hash: PackagedWasm::hash_code(&code),
+            code,
+        }
+    }
+
+    /// The hash wasm code gets declared with in packages.
+    pub fn hash_code(code: &[u8]) -> String {
+        HashString::encode_from_bytes(code, Hash::SHA2256).to_string()
+    }
+
+    pub fn matches_hash(&self) -> bool {
+        PackagedWasm::hash_code(&self.code) == self.hash
+    }
+}
+
+/// A file of the UI that comes with a dna.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UiAsset {
+    #[serde(
+        serialize_with = "::wasm::_vec_u8_to_b64_str",
+        deserialize_with = "::wasm::_b64_str_to_vec_u8"
+    )]
+    pub content: Vec<u8>,
+}
+
+/// The parts of a package its hash covers.
+#[derive(Serialize)]
+struct PackageContent<'a> {
+    format_version: u32,
+    manifest: &'a Dna,
+    zomes: &'a BTreeMap<String, PackagedWasm>,
+    ui: &'a BTreeMap<String, UiAsset>,
+}
+
+fn package_error<T: Into<String>>(message: T) -> HolochainError {
+    HolochainError::Dna(DnaError::InvalidPackage(message.into()))
+}
+
+impl DnaPackage {
+    /// Create a package of a dna and the given UI assets, with all hashes filled in.
+    pub fn from_dna(dna: &Dna, ui: BTreeMap<String, UiAsset>) -> Self {
+        let mut manifest = dna.clone();
+        let mut zomes = BTreeMap::new();
+        for (zome_name, zome) in manifest.zomes.iter_mut() {
+            let code = mem::replace(&mut zome.code, DnaWasm::new()).code;
+            zomes.insert(zome_name.clone(), PackagedWasm::new(code));
+        }
+        let mut package = DnaPackage {
+            format_version: PACKAGE_FORMAT_VERSION,
+            manifest,
+            zomes,
+            ui,
+            hash: String::new(),
+        };
+        package.hash = package.content_hash();
+        package
+    }
+
+    /// Create a package from its json representation, without verifying it.
+    pub fn from_json_str(json: &str) -> Result<Self, HolochainError> {
+        serde_json::from_str(json)
+            .map_err(|e| package_error(format!("Could not parse dna package: {}", e)))
+    }
+
+    /// Read a package from a file, without verifying it.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, HolochainError> {
+        DnaPackage::from_json_str(&fs::read_to_string(path)?)
+    }
+
+    pub fn to_json(&self) -> Result<String, HolochainError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Write the package to a file, conventionally named `<name>.hcpkg`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), HolochainError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// The hash of everything but the hash field, independent of the order of json objects.
+    pub fn content_hash(&self) -> String {
+        let content = PackageContent {
+            format_version: self.format_version,
+            manifest: &self.manifest,
+            zomes: &self.zomes,
+            ui: &self.ui,
+        };
+        HashString::encode_from_str(&canonical_json(&content), Hash::SHA2256).to_string()
+    }
+
+    /// Make sure the package has a known format, comes with the wasm of exactly the zomes of
+    /// the manifest, and that the wasm and the package as a whole match their hashes.
+    pub fn verify(&self) -> Result<(), HolochainError> {
+        if self.format_version != PACKAGE_FORMAT_VERSION {
+            return Err(package_error(format!(
+                "Unsupported package format version {}",
+                self.format_version
+            )));
+        }
+        for zome_name in self.manifest.zomes.keys() {
+            if !self.zomes.contains_key(zome_name) {
+                return Err(package_error(format!(
+                    "Zome '{}' has no wasm in the package",
+                    zome_name
+                )));
+            }
+        }
+        for (zome_name, wasm) in self.zomes.iter() {
+            if !self.manifest.zomes.contains_key(zome_name) {
+                return Err(package_error(format!(
+                    "The package has wasm for the unknown zome '{}'",
+                    zome_name
+                )));
+            }
+            if !wasm.matches_hash() {
+                return Err(package_error(format!(
+                    "The wasm of zome '{}' does not match its hash {}",
+                    zome_name, wasm.hash
+                )));
+            }
+        }
+        if self.content_hash() != self.hash {
+            return Err(package_error(format!(
+                "The package does not match its hash {}",
+                self.hash
+            )));
+        }
+        Ok(())
+    }
+
+    /// The packaged dna, with the wasm of its zomes.
+    pub fn dna(&self) -> Dna {
+        let mut dna = self.manifest.clone();
+        for (zome_name, zome) in dna.zomes.iter_mut() {
+            if let Some(wasm) = self.zomes.get(zome_name) {
+                zome.code = DnaWasm {
+                    code: wasm.code.clone(),
+                };
+            }
+        }
+        dna
+    }
+}
+
+/// Serializes to json with the keys of all objects sorted,
+/// so equal values always give the same string.
+pub(crate) fn canonical_json<S: Serialize>(value: &S) -> String {
+    fn sorted(value: Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                let mut sorted_map = Map::new();
+                for (key, value) in entries {
+                    sorted_map.insert(key, sorted(value));
+                }
+                Value::Object(sorted_map)
+            }
+            Value::Array(values) => Value::Array(values.into_iter().map(sorted).collect()),
+            value => value,
+        }
+    }
+    // maps with non-string keys are the only thing that fails, and dnas don't have them
+    let value = serde_json::to_value(value).expect("value should serialize to json");
+    sorted(value).to_string()
+}
+
+/// Assembles a package from a source directory, which holds:
+///  - the manifest `<name>.dna.json`
+///  - a folder for every zome, named like the zome, with its compiled `<zome>.wasm`
+///    or a single other `.wasm` file (zomes with code in the manifest don't need one)
+///  - optionally a `ui` folder with the UI assets
+///
+/// # Examples
+///
+/// ```no_run
+/// use holochain_dna::package::PackageBuilder;
+///
+/// let package = PackageBuilder::new("my_app").build().expect("could not build package");
+/// package.save("my_app.hcpkg").expect("could not save package");
+/// ```
+#[derive(Clone, Debug)]
+pub struct PackageBuilder {
+    source_dir: PathBuf,
+    ui_dir: Option<PathBuf>,
+}
+
+impl PackageBuilder {
+    pub fn new<P: AsRef<Path>>(source_dir: P) -> Self {
+        let source_dir = source_dir.as_ref().to_path_buf();
+        PackageBuilder {
+            ui_dir: Some(source_dir.join(UI_DIR)),
+            source_dir,
+        }
+    }
+
+    /// Take the UI assets from the given directory instead.
+    pub fn with_ui_dir<P: AsRef<Path>>(mut self, ui_dir: P) -> Self {
+        self.ui_dir = Some(ui_dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Don't bundle any UI assets.
+    pub fn without_ui(mut self) -> Self {
+        self.ui_dir = None;
+        self
+    }
+
+    pub fn build(&self) -> Result<DnaPackage, HolochainError> {
+        let manifest_file = self.manifest_file()?;
+        let mut dna = Dna::from_json_str(&fs::read_to_string(&manifest_file)?).map_err(|e| {
+            package_error(format!(
+                "Could not parse {}: {}",
+                manifest_file.display(),
+                e
+            ))
+        })?;
+
+        for (zome_name, zome) in dna.zomes.iter_mut() {
+            let zome_dir = self.source_dir.join(zome_name);
+            if zome_dir.is_dir() {
+                zome.code = DnaWasm {
+                    code: fs::read(find_wasm(&zome_dir, zome_name)?)?,
+                };
+            } else if zome.code.code.is_empty() {
+                return Err(package_error(format!(
+                    "Zome '{}' has neither a folder with its wasm nor code in the manifest",
+                    zome_name
+                )));
+            }
+        }
+
+        let mut ui = BTreeMap::new();
+        if let Some(ref ui_dir) = self.ui_dir {
+            if ui_dir.is_dir() {
+                collect_ui_assets(ui_dir, "", &mut ui)?;
+            }
+        }
+        Ok(DnaPackage::from_dna(&dna, ui))
+    }
+
+    /// The one file in the source directory with a name ending in .dna.json
+    fn manifest_file(&self) -> Result<PathBuf, HolochainError> {
+        let mut manifests = Vec::new();
+        for entry in fs::read_dir(&self.source_dir)? {
+            let path = entry?.path();
+            let is_manifest = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.ends_with(MANIFEST_SUFFIX))
+                .unwrap_or(false);
+            if is_manifest && path.is_file() {
+                manifests.push(path);
+            }
+        }
+        match manifests.len() {
+            1 => Ok(manifests.remove(0)),
+            0 => Err(package_error(format!(
+                "No *{} manifest in {}",
+                MANIFEST_SUFFIX,
+                self.source_dir.display()
+            ))),
+            _ => Err(package_error(format!(
+                "More than one *{} manifest in {}",
+                MANIFEST_SUFFIX,
+                self.source_dir.display()
+            ))),
+        }
+    }
+}
+
+/// The wasm file of a zome folder: `<zome>.wasm`, or the only .wasm file in it.
+fn find_wasm(zome_dir: &Path, zome_name: &str) -> Result<PathBuf, HolochainError> {
+    let named = zome_dir.join(format!("{}.wasm", zome_name));
+    if named.is_file() {
+        return Ok(named);
+    }
+    let mut wasm_files = Vec::new();
+    for entry in fs::read_dir(zome_dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().map(|ext| ext == "wasm").unwrap_or(false) {
+            wasm_files.push(path);
+        }
+    }
+    if wasm_files.len() == 1 {
+        Ok(wasm_files.remove(0))
+    } else {
+        Err(package_error(format!(
+            "Expected {}.wasm or a single .wasm file in {}",
+            zome_name,
+            zome_dir.display()
+        )))
+    }
+}
+
+/// Adds all files below dir to the assets, keyed by their path below the UI directory.
+fn collect_ui_assets(
+    dir: &Path,
+    prefix: &str,
+    assets: &mut BTreeMap<String, UiAsset>,
+) -> Result<(), HolochainError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| package_error(format!("Invalid file name {}", path.display())))?
+            .to_string();
+        let key = format!("{}{}", prefix, name);
+        if path.is_dir() {
+            collect_ui_assets(&path, &format!("{}/", key), assets)?;
+        } else {
+            assets.insert(
+                key,
+                UiAsset {
+                    content: fs::read(&path)?,
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    extern crate tempfile;
+
+    use self::tempfile::tempdir;
+    use super::*;
+    use zome::Zome;
+
+    fn test_dna_with_zomes() -> Dna {
+        let mut dna = Dna::new();
+        dna.name = "test".to_string();
+        for (name, code) in vec![("blog", vec![0, 1, 2, 3]), ("chat", vec![4, 5])] {
+            let mut zome = Zome::default();
+            zome.code = DnaWasm { code };
+            dna.zomes.insert(name.to_string(), zome);
+        }
+        dna
+    }
+
+    /// writes the manifest of the test dna, without code, plus zome folders and a UI
+    fn write_source_dir(dir: &Path) {
+        let dna = DnaPackage::from_dna(&test_dna_with_zomes(), BTreeMap::new()).manifest;
+        fs::write(dir.join("test.dna.json"), dna.to_json()).unwrap();
+        fs::create_dir_all(dir.join("blog")).unwrap();
+        fs::write(dir.join("blog/blog.wasm"), vec![0, 1, 2, 3]).unwrap();
+        fs::create_dir_all(dir.join("chat")).unwrap();
+        fs::write(dir.join("chat/anything.wasm"), vec![4, 5]).unwrap();
+        fs::create_dir_all(dir.join("ui/css")).unwrap();
+        fs::write(dir.join("ui/index.html"), "<html></html>").unwrap();
+        fs::write(dir.join("ui/css/main.css"), "body {}").unwrap();
+    }
+
+    #[test]
+    fn package_round_trip() {
+        let dna = test_dna_with_zomes();
+        let package = DnaPackage::from_dna(&dna, BTreeMap::new());
+        assert!(package.manifest.zomes["blog"].code.code.is_empty());
+        assert_eq!(package.zomes["blog"].code, vec![0, 1, 2, 3]);
+        assert!(package.verify().is_ok());
+
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("test.hcpkg");
+        package.save(&file).unwrap();
+        let loaded = Dna::from_package_file(&file).unwrap();
+        assert_eq!(loaded.name, dna.name);
+        assert_eq!(loaded.zomes["blog"].code.code, vec![0, 1, 2, 3]);
+        assert_eq!(loaded.zomes["chat"].code.code, vec![4, 5]);
+    }
+
+    #[test]
+    fn builds_packages_from_source_dirs() {
+        let dir = tempdir().unwrap();
+        write_source_dir(dir.path());
+
+        let package = PackageBuilder::new(dir.path()).build().unwrap();
+        assert!(package.verify().is_ok());
+        assert_eq!(package.zomes["blog"].code, vec![0, 1, 2, 3]);
+        assert_eq!(package.zomes["chat"].code, vec![4, 5]);
+        assert_eq!(
+            package.ui.keys().collect::<Vec<_>>(),
+            vec!["css/main.css", "index.html"]
+        );
+        assert_eq!(package.ui["index.html"].content, b"<html></html>".to_vec());
+
+        let package = PackageBuilder::new(dir.path())
+            .without_ui()
+            .build()
+            .unwrap();
+        assert!(package.ui.is_empty());
+
+        fs::remove_file(dir.path().join("blog/blog.wasm")).unwrap();
+        assert!(PackageBuilder::new(dir.path()).build().is_err());
+    }
+
+    #[test]
+    fn verifies_hashes() {
+        let package = DnaPackage::from_dna(&test_dna_with_zomes(), BTreeMap::new());
+        let package = DnaPackage::from_json_str(&package.to_json().unwrap()).unwrap();
+        assert!(package.verify().is_ok());
+
+        let mut tampered = package.clone();
+        tampered.zomes.get_mut("blog").unwrap().code = vec![6, 6, 6];
+        assert_eq!(
+            tampered.verify(),
+            Err(package_error(format!(
+                "The wasm of zome 'blog' does not match its hash {}",
+                package.zomes["blog"].hash
+            )))
+        );
+
+        // fixing up the wasm hash still breaks the package hash
+        tampered
+            .zomes
+            .insert("blog".to_string(), PackagedWasm::new(vec![6, 6, 6]));
+        assert_eq!(
+            tampered.verify(),
+            Err(package_error(format!(
+                "The package does not match its hash {}",
+                package.hash
+            )))
+        );
+
+        let mut incomplete = package.clone();
+        incomplete.zomes.remove("chat");
+        assert_eq!(
+            incomplete.verify(),
+            Err(package_error("Zome 'chat' has no wasm in the package"))
+        );
+    }
+
+    #[test]
+    fn canonical_json_sorts_keys() {
+        assert_eq!(
+            canonical_json(&json!({"b": 1, "a": {"d": [{"f": 2, "e": 3}], "c": 4}})),
+            r#"{"a":{"c":4,"d":[{"e":3,"f":2}]},"b":1}"#
+        );
+    }
+}
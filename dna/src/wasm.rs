@@ -9,8 +9,8 @@ use serde::{
     ser::Serializer,
 };
 
-/// Private helper for converting binary WebAssembly into base64 serialized string.
-fn _vec_u8_to_b64_str<S>(data: &[u8], s: S) -> Result<S::Ok, S::Error>
+/// Crate helper for converting binary WebAssembly into base64 serialized string.
+pub(crate) fn _vec_u8_to_b64_str<S>(data: &[u8], s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -18,8 +18,8 @@ where
     s.serialize_str(&b64)
 }
 
-/// Private helper for converting base64 string into binary WebAssembly.
-fn _b64_str_to_vec_u8<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
+/// Crate helper for converting base64 string into binary WebAssembly.
+pub(crate) fn _b64_str_to_vec_u8<'de, D>(d: D) -> Result<Vec<u8>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -76,3 +76,145 @@ impl DnaWasm {
         Default::default()
     }
 }
+
+/// Magic number and version every binary WebAssembly module starts with.
+const WASM_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+/// Id of the section listing the exports of a module.
+const EXPORT_SECTION_ID: u8 = 7;
+
+/// External kind of exported functions.
+const FUNCTION_EXTERNAL_KIND: u8 = 0;
+
+/// Reads the sections of binary WebAssembly as far as the dna needs to know about them.
+struct WasmReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WasmReader<'a> {
+    fn is_done(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| format!("unexpected end of wasm at byte {}", self.position))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn slice(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() - self.position {
+            return Err(format!("unexpected end of wasm at byte {}", self.position));
+        }
+        let slice = &self.bytes[self.position..self.position + len];
+        self.position += len;
+        Ok(slice)
+    }
+
+    /// unsigned LEB128, which may be padded to 5 bytes
+    fn var_u32(&mut self) -> Result<u32, String> {
+        let mut result: u32 = 0;
+        for shift in 0..5 {
+            let byte = self.byte()?;
+            result |= u32::from(byte & 0x7f) << (shift * 7);
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(format!("invalid integer before byte {}", self.position))
+    }
+}
+
+impl DnaWasm {
+    /// Names of the functions the wasm exports, in the order they are declared.
+    /// Errors with a description of the problem if the code is no binary WebAssembly.
+    pub fn exported_functions(&self) -> Result<Vec<String>, String> {
+        let mut reader = WasmReader {
+            bytes: &self.code,
+            position: 0,
+        };
+        if reader.slice(WASM_PREAMBLE.len()).ok() != Some(&WASM_PREAMBLE[..]) {
+            return Err("not a WebAssembly binary".to_string());
+        }
+
+        let mut functions = Vec::new();
+        while !reader.is_done() {
+            let section_id = reader.byte()?;
+            let section_size = reader.var_u32()? as usize;
+            let section = reader.slice(section_size)?;
+            if section_id != EXPORT_SECTION_ID {
+                continue;
+            }
+            let mut section = WasmReader {
+                bytes: section,
+                position: 0,
+            };
+            for _ in 0..section.var_u32()? {
+                let name_len = section.var_u32()? as usize;
+                let name = String::from_utf8(section.slice(name_len)?.to_vec())
+                    .map_err(|_| "export name is not valid UTF-8".to_string())?;
+                let kind = section.byte()?;
+                section.var_u32()?;
+                if kind == FUNCTION_EXTERNAL_KIND {
+                    functions.push(name);
+                }
+            }
+        }
+        Ok(functions)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// a module with a memory and the exports "main" (function) and "memory" (memory),
+    /// as wat2wasm writes it with padded integers
+    pub fn test_wasm() -> Vec<u8> {
+        let mut code = WASM_PREAMBLE.to_vec();
+        // type section: () -> ()
+        code.extend_from_slice(&[0x01, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        // function section: one function of type 0
+        code.extend_from_slice(&[0x03, 0x02, 0x01, 0x00]);
+        // memory section: one memory, 1 page
+        code.extend_from_slice(&[0x05, 0x03, 0x01, 0x00, 0x01]);
+        // export section, with a padded size
+        code.extend_from_slice(&[0x07, 0x91, 0x80, 0x80, 0x80, 0x00, 0x02]);
+        code.extend_from_slice(&[0x04, b'm', b'a', b'i', b'n', 0x00, 0x00]);
+        code.extend_from_slice(&[0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00]);
+        // code section: an empty body
+        code.extend_from_slice(&[0x0a, 0x04, 0x01, 0x02, 0x00, 0x0b]);
+        code
+    }
+
+    #[test]
+    fn reads_exported_functions() {
+        let wasm = DnaWasm { code: test_wasm() };
+        assert_eq!(wasm.exported_functions(), Ok(vec!["main".to_string()]));
+
+        let wasm = DnaWasm {
+            code: WASM_PREAMBLE.to_vec(),
+        };
+        assert_eq!(wasm.exported_functions(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn fails_on_invalid_wasm() {
+        let wasm = DnaWasm {
+            code: vec![0, 1, 2, 3],
+        };
+        assert_eq!(
+            wasm.exported_functions(),
+            Err("not a WebAssembly binary".to_string())
+        );
+
+        let mut code = test_wasm();
+        code.truncate(30);
+        let wasm = DnaWasm { code };
+        assert!(wasm.exported_functions().is_err());
+    }
+}
@@ -41,8 +41,22 @@ impl ReservedCapabilityNames {
             ReservedCapabilityNames::MissingNo => "",
         }
     }
+
+    /// Names of the callbacks core calls through the capability, which are the only functions
+    /// it may declare.
+    /// Keep in line with the callbacks in holochain_core::nucleus::ribosome::callback
+    pub fn callbacks(&self) -> &'static [&'static str] {
+        match *self {
+            ReservedCapabilityNames::LifeCycle => &["genesis"],
+            ReservedCapabilityNames::Communication => &["receive"],
+            ReservedCapabilityNames::MissingNo => &[],
+        }
+    }
 }
 
+/// Prefix of reserved capability names, which apps can't use for capabilities of their own.
+pub const RESERVED_CAPABILITY_PREFIX: &str = "hc_";
+
 //--------------------------------------------------------------------------------------------------
 // CapabilityType
 //--------------------------------------------------------------------------------------------------
@@ -136,6 +150,39 @@ impl FnDeclaration {
     }
 }
 
+/// Represents a zome "signals" object.
+/// Where a function declaration exposes an entry point into the zome,
+/// a signal declaration exposes an exit point that clients can listen to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
+pub struct SignalDeclaration {
+    /// The name of this signal declaration.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// The arguments the signal sends to its listeners.
+    #[serde(default)]
+    pub arguments: Vec<FnParameter>,
+}
+
+impl Default for SignalDeclaration {
+    /// Defaults for a "signals" object.
+    fn default() -> Self {
+        SignalDeclaration {
+            name: String::new(),
+            description: String::new(),
+            arguments: Vec::new(),
+        }
+    }
+}
+
+impl SignalDeclaration {
+    /// Allow sane defaults for `SignalDeclaration::new()`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
 /// Represents an individual object in the "zome" "capabilities" array.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
 pub struct Capability {
@@ -146,6 +193,10 @@ pub struct Capability {
     /// "fn_declarations" array
     #[serde(default)]
     pub functions: Vec<FnDeclaration>,
+
+    /// "signals" array
+    #[serde(default)]
+    pub signals: Vec<SignalDeclaration>,
 }
 
 impl Default for Capability {
@@ -154,6 +205,7 @@ impl Default for Capability {
         Capability {
             cap_type: CapabilityType::new(),
             functions: Vec::new(),
+            signals: Vec::new(),
         }
     }
 }
@@ -163,6 +215,13 @@ impl Capability {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Return the declaration of the signal with the given name, if the Capability declares it.
+    pub fn get_signal(&self, signal_name: &str) -> Option<&SignalDeclaration> {
+        self.signals
+            .iter()
+            .find(|signal_declaration| signal_declaration.name == signal_name)
+    }
 }
 
 #[cfg(test)]
@@ -197,6 +256,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_capabilities_callbacks() {
+        assert_eq!(ReservedCapabilityNames::LifeCycle.callbacks(), &["genesis"]);
+        assert_eq!(
+            ReservedCapabilityNames::Communication.callbacks(),
+            &["receive"]
+        );
+        assert!(ReservedCapabilityNames::MissingNo.callbacks().is_empty());
+    }
+
     #[test]
     fn build_and_compare() {
         let fixture: Capability = serde_json::from_str(
@@ -235,4 +304,39 @@ mod tests {
 
         assert_eq!(fixture, cap);
     }
+
+    #[test]
+    fn build_and_compare_signals() {
+        let fixture: Capability = serde_json::from_str(
+            r#"{
+                "capability": {
+                    "membrane": "public"
+                },
+                "signals": [
+                    {
+                        "name": "Post",
+                        "description": "signal emitted when a post is committed",
+                        "arguments": [
+                            {
+                                "name": "hash",
+                                "type": "hash"
+                            }
+                        ]
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let mut cap = Capability::new();
+        cap.cap_type.membrane = Membrane::Public;
+        let mut signal = SignalDeclaration::new();
+        signal.name = String::from("Post");
+        signal.description = String::from("signal emitted when a post is committed");
+        signal.arguments.push(FnParameter::new("hash", "hash"));
+        cap.signals.push(signal.clone());
+
+        assert_eq!(fixture, cap);
+        assert_eq!(cap.get_signal("Post"), Some(&signal));
+        assert_eq!(cap.get_signal("Comment"), None);
+    }
 }
@@ -1,5 +1,7 @@
 //! File holding all the structs for handling entry types defined by DNA.
 
+use zome::schema::EntrySchema;
+
 /// Enum for Zome EntryType "sharing" property.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Hash)]
 pub enum Sharing {
@@ -106,6 +108,10 @@ pub struct EntryTypeDef {
     /// An array of link definitions for links pointing to entries of this type
     #[serde(default)]
     pub linked_from: Vec<LinkedFrom>,
+
+    /// The JSON Schema the content of entries of this type has to match, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<EntrySchema>,
 }
 
 impl Default for EntryTypeDef {
@@ -116,6 +122,7 @@ impl Default for EntryTypeDef {
             sharing: Sharing::Public,
             links_to: Vec::new(),
             linked_from: Vec::new(),
+            schema: None,
         }
     }
 }
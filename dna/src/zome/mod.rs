@@ -2,6 +2,7 @@
 
 pub mod capabilities;
 pub mod entry_types;
+pub mod schema;
 
 use std::collections::HashMap;
 use wasm::DnaWasm;
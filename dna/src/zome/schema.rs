@@ -0,0 +1,462 @@
+//! File holding the JSON Schema entry types can declare for their content.
+//!
+//! Core checks entries against the schema of their type before running any validation
+//! callback, so simple structural checks don't need to spin up the ribosome.
+//! The schema is a subset of JSON Schema (draft 7) supporting these keywords:
+//!  - `type`, `enum`, `const`
+//!  - `minLength`, `maxLength`
+//!  - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`
+//!  - `items`, `minItems`, `maxItems`
+//!  - `properties`, `required`, `additionalProperties`
+//!  - `allOf`, `anyOf`, `oneOf`, `not`
+//!
+//! Like in JSON Schema, other keywords get ignored.
+
+use serde_json::{self, Map, Value};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// The JSON Schema of an entry type.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EntrySchema(pub Value);
+
+impl Hash for EntrySchema {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_string().hash(state);
+    }
+}
+
+/// Where and why a value doesn't match a schema.
+/// Both paths are JSON pointers, into the value and into the schema respectively.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SchemaViolation {
+    pub instance_path: String,
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}' {} (schema path '{}')",
+            self.instance_path, self.message, self.schema_path
+        )
+    }
+}
+
+impl EntrySchema {
+    /// Schemas are objects or booleans.
+    pub fn is_valid(&self) -> bool {
+        self.0.is_object() || self.0.is_boolean()
+    }
+
+    /// Check the value against the schema, failing with the first violation found.
+    pub fn check(&self, value: &Value) -> Result<(), SchemaViolation> {
+        check_value(&self.0, value, "", "")
+    }
+
+    /// Check the JSON serialization of a value against the schema.
+    pub fn check_str(&self, json: &str) -> Result<(), SchemaViolation> {
+        let value = serde_json::from_str(json).map_err(|e| SchemaViolation {
+            instance_path: String::new(),
+            schema_path: String::new(),
+            message: format!("is not JSON: {}", e),
+        })?;
+        self.check(&value)
+    }
+}
+
+/// Escape a key or index for use in a JSON pointer.
+fn pointer(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, type_name: &str) -> bool {
+    match (type_name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_))
+        | ("array", Value::Array(_))
+        | ("object", Value::Object(_)) => true,
+        ("integer", Value::Number(number)) => {
+            number.is_i64()
+                || number.is_u64()
+                || number
+                    .as_f64()
+                    .map_or(false, |n| n.fract().abs() < ::std::f64::EPSILON)
+        }
+        _ => false,
+    }
+}
+
+fn check_value(
+    schema: &Value,
+    value: &Value,
+    instance_path: &str,
+    schema_path: &str,
+) -> Result<(), SchemaViolation> {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => {
+            return Err(SchemaViolation {
+                instance_path: instance_path.to_string(),
+                schema_path: schema_path.to_string(),
+                message: "is not allowed".to_string(),
+            })
+        }
+        // true, and invalid schemas, which dna validation reports
+        _ => return Ok(()),
+    };
+    let violation = |keyword: &str, message: String| SchemaViolation {
+        instance_path: instance_path.to_string(),
+        schema_path: pointer(schema_path, keyword),
+        message,
+    };
+
+    if let Some(types) = schema.get("type") {
+        let matches = match types {
+            Value::String(expected) => has_type(value, expected),
+            Value::Array(expected) => expected
+                .iter()
+                .filter_map(Value::as_str)
+                .any(|expected| has_type(value, expected)),
+            _ => true,
+        };
+        if !matches {
+            return Err(violation(
+                "type",
+                format!("should be of type {}, not {}", types, type_name(value)),
+            ));
+        }
+    }
+    if let Some(options) = schema.get("enum") {
+        if options
+            .as_array()
+            .map_or(false, |options| !options.contains(value))
+        {
+            return Err(violation("enum", format!("should be one of {}", options)));
+        }
+    }
+    if let Some(constant) = schema.get("const") {
+        if constant != value {
+            return Err(violation("const", format!("should be {}", constant)));
+        }
+    }
+
+    match value {
+        Value::String(string) => {
+            let length = string.chars().count() as f64;
+            check_bound(schema, "minLength", length, &violation, |min| length >= min)?;
+            check_bound(schema, "maxLength", length, &violation, |max| length <= max)?;
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            check_bound(schema, "minimum", number, &violation, |min| number >= min)?;
+            check_bound(schema, "maximum", number, &violation, |max| number <= max)?;
+            check_bound(schema, "exclusiveMinimum", number, &violation, |min| {
+                number > min
+            })?;
+            check_bound(schema, "exclusiveMaximum", number, &violation, |max| {
+                number < max
+            })?;
+        }
+        Value::Array(items) => {
+            let count = items.len() as f64;
+            check_bound(schema, "minItems", count, &violation, |min| count >= min)?;
+            check_bound(schema, "maxItems", count, &violation, |max| count <= max)?;
+            match schema.get("items") {
+                // a tuple, with a schema per position
+                Some(Value::Array(item_schemas)) => {
+                    for (index, (item, item_schema)) in items.iter().zip(item_schemas).enumerate() {
+                        check_value(
+                            item_schema,
+                            item,
+                            &pointer(instance_path, &index.to_string()),
+                            &pointer(&pointer(schema_path, "items"), &index.to_string()),
+                        )?;
+                    }
+                }
+                Some(item_schema) => {
+                    for (index, item) in items.iter().enumerate() {
+                        check_value(
+                            item_schema,
+                            item,
+                            &pointer(instance_path, &index.to_string()),
+                            &pointer(schema_path, "items"),
+                        )?;
+                    }
+                }
+                None => {}
+            }
+        }
+        Value::Object(object) => {
+            check_object(schema, object, instance_path, schema_path, &violation)?
+        }
+        _ => {}
+    }
+
+    if let Some(Value::Array(sub_schemas)) = schema.get("allOf") {
+        for (index, sub_schema) in sub_schemas.iter().enumerate() {
+            check_value(
+                sub_schema,
+                value,
+                instance_path,
+                &pointer(&pointer(schema_path, "allOf"), &index.to_string()),
+            )?;
+        }
+    }
+    if let Some(Value::Array(sub_schemas)) = schema.get("anyOf") {
+        if !sub_schemas
+            .iter()
+            .any(|sub_schema| check_value(sub_schema, value, "", "").is_ok())
+        {
+            return Err(violation(
+                "anyOf",
+                "should match at least one of the schemas".to_string(),
+            ));
+        }
+    }
+    if let Some(Value::Array(sub_schemas)) = schema.get("oneOf") {
+        let matching = sub_schemas
+            .iter()
+            .filter(|sub_schema| check_value(sub_schema, value, "", "").is_ok())
+            .count();
+        if matching != 1 {
+            return Err(violation(
+                "oneOf",
+                format!("should match exactly one of the schemas, not {}", matching),
+            ));
+        }
+    }
+    if let Some(sub_schema) = schema.get("not") {
+        if check_value(sub_schema, value, "", "").is_ok() {
+            return Err(violation("not", "should not match the schema".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Fails if the schema has the numeric keyword and the value doesn't satisfy the bound it sets.
+fn check_bound<V, F>(
+    schema: &Map<String, Value>,
+    keyword: &str,
+    actual: f64,
+    violation: &V,
+    satisfies: F,
+) -> Result<(), SchemaViolation>
+where
+    V: Fn(&str, String) -> SchemaViolation,
+    F: Fn(f64) -> bool,
+{
+    match schema.get(keyword).and_then(Value::as_f64) {
+        Some(bound) if !satisfies(bound) => Err(violation(
+            keyword,
+            format!("should satisfy {} {}, but is {}", keyword, bound, actual),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn check_object<V>(
+    schema: &Map<String, Value>,
+    object: &Map<String, Value>,
+    instance_path: &str,
+    schema_path: &str,
+    violation: &V,
+) -> Result<(), SchemaViolation>
+where
+    V: Fn(&str, String) -> SchemaViolation,
+{
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                return Err(violation(
+                    "required",
+                    format!("should have the property '{}'", key),
+                ));
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, property) in object {
+        let property_path = pointer(instance_path, key);
+        match properties.and_then(|properties| properties.get(key)) {
+            Some(property_schema) => check_value(
+                property_schema,
+                property,
+                &property_path,
+                &pointer(&pointer(schema_path, "properties"), key),
+            )?,
+            None => {
+                if let Some(additional_schema) = schema.get("additionalProperties") {
+                    check_value(
+                        additional_schema,
+                        property,
+                        &property_path,
+                        &pointer(schema_path, "additionalProperties"),
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    pub fn test_schema() -> EntrySchema {
+        EntrySchema(json!({
+            "type": "object",
+            "required": ["title", "tags"],
+            "properties": {
+                "title": {"type": "string", "minLength": 1, "maxLength": 20},
+                "stars": {"type": "integer", "minimum": 0, "exclusiveMaximum": 6},
+                "tags": {"type": "array", "items": {"enum": ["news", "rust"]}},
+                "a/b": {"type": "null"}
+            },
+            "additionalProperties": false
+        }))
+    }
+
+    fn violation(instance_path: &str, schema_path: &str, message: &str) -> SchemaViolation {
+        SchemaViolation {
+            instance_path: instance_path.to_string(),
+            schema_path: schema_path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn accepts_matching_values() {
+        let schema = test_schema();
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "stars": 5, "tags": ["rust"], "a/b": null})),
+            Ok(())
+        );
+        assert_eq!(schema.check_str(r#"{"title": "hi", "tags": []}"#), Ok(()));
+        assert_eq!(EntrySchema(json!(true)).check(&json!([1, "two"])), Ok(()));
+    }
+
+    #[test]
+    fn reports_where_values_fail() {
+        let schema = test_schema();
+        assert_eq!(
+            schema.check(&json!("hi")),
+            Err(violation(
+                "",
+                "/type",
+                "should be of type \"object\", not string"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi"})),
+            Err(violation(
+                "",
+                "/required",
+                "should have the property 'tags'"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "", "tags": []})),
+            Err(violation(
+                "/title",
+                "/properties/title/minLength",
+                "should satisfy minLength 1, but is 0"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "stars": 6, "tags": []})),
+            Err(violation(
+                "/stars",
+                "/properties/stars/exclusiveMaximum",
+                "should satisfy exclusiveMaximum 6, but is 6"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "stars": 2.5, "tags": []})),
+            Err(violation(
+                "/stars",
+                "/properties/stars/type",
+                "should be of type \"integer\", not number"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "tags": ["rust", "go"]})),
+            Err(violation(
+                "/tags/1",
+                "/properties/tags/items/enum",
+                "should be one of [\"news\",\"rust\"]"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "tags": [], "a/b": 1})),
+            Err(violation(
+                "/a~1b",
+                "/properties/a~1b/type",
+                "should be of type \"null\", not number"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!({"title": "hi", "tags": [], "extra": 1})),
+            Err(violation(
+                "/extra",
+                "/additionalProperties",
+                "is not allowed"
+            ))
+        );
+        assert_eq!(
+            schema.check_str("{").unwrap_err().schema_path,
+            "".to_string()
+        );
+    }
+
+    #[test]
+    fn combines_schemas() {
+        let schema = EntrySchema(json!({
+            "anyOf": [{"type": "string"}, {"type": "integer"}],
+            "not": {"const": 0}
+        }));
+        assert_eq!(schema.check(&json!("x")), Ok(()));
+        assert_eq!(schema.check(&json!(1)), Ok(()));
+        assert_eq!(
+            schema.check(&json!(true)),
+            Err(violation(
+                "",
+                "/anyOf",
+                "should match at least one of the schemas"
+            ))
+        );
+        assert_eq!(
+            schema.check(&json!(0)),
+            Err(violation("", "/not", "should not match the schema"))
+        );
+
+        let schema = EntrySchema(json!({"oneOf": [{"type": "number"}, {"type": "integer"}]}));
+        assert_eq!(schema.check(&json!(1.5)), Ok(()));
+        assert_eq!(
+            schema.check(&json!(1)),
+            Err(violation(
+                "",
+                "/oneOf",
+                "should match exactly one of the schemas, not 2"
+            ))
+        );
+    }
+}
@@ -4,14 +4,16 @@ pub use holochain_wasm_utils::api_serialization::validation::*;
 use holochain_wasm_utils::{
     api_serialization::{
         commit::{CommitEntryArgs, CommitEntryResult},
+        emit_signal::{EmitSignalArgs, EmitSignalResult},
         get_entry::{GetEntryArgs, GetEntryOptions, GetEntryResult},
         get_links::{GetLinksArgs, GetLinksResult},
         link_entries::{LinkEntriesArgs, LinkEntriesResult},
+        property::{PropertyArgs, PropertyResult},
     },
     holochain_core_types::hash::HashString,
-    memory_allocation::*,
     memory_serialization::*,
 };
+use serde::Serialize;
 use serde_json;
 
 //--------------------------------------------------------------------------------------------------
@@ -137,21 +139,25 @@ pub enum BundleOnClose {
 // API FUNCTIONS
 //--------------------------------------------------------------------------------------------------
 
+/// Serializes the input of a Zome API function into a buffer the Ribosome can read.
+/// The buffer has to be kept until the Zome API function returned.
+fn store_input<T: Serialize>(input: T) -> ZomeApiResult<(MultiPageBuffer, u64)> {
+    let buffer_of_input = MultiPageBuffer::from_json(input)
+        .map_err(|err_code| ZomeApiError::Internal(err_code.to_string()))?;
+    let encoded_allocation_of_input = buffer_of_input
+        .allocation()
+        .map_err(|err_code| ZomeApiError::Internal(err_code.to_string()))?
+        .encode();
+    Ok((buffer_of_input, encoded_allocation_of_input))
+}
+
 /// Prints a string through the stdout of the running service, and also
 /// writes that string to the logger in the execution context
 pub fn debug(msg: &str) -> ZomeApiResult<()> {
-    let mut mem_stack = unsafe { G_MEM_STACK.unwrap() };
-    let maybe_allocation_of_input = store_as_json(&mut mem_stack, msg);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(ZomeApiError::Internal(err_code.to_string()));
-    }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(msg)?;
     unsafe {
-        hc_debug(allocation_of_input.encode());
+        hc_debug(encoded_allocation_of_input);
     }
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("should be able to deallocate input that has been allocated on memory stack");
     Ok(())
 }
 
@@ -172,40 +178,26 @@ pub fn commit_entry(
     entry_type_name: &str,
     entry_value: serde_json::Value,
 ) -> ZomeApiResult<HashString> {
-    let mut mem_stack: SinglePageStack;
-    unsafe {
-        mem_stack = G_MEM_STACK.unwrap();
-    }
-
     // Put args in struct and serialize into memory
     let input = CommitEntryArgs {
         entry_type_name: entry_type_name.to_string(),
         entry_value: entry_value.to_string(),
     };
-    let maybe_allocation_of_input = store_as_json(&mut mem_stack, input);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(ZomeApiError::Internal(err_code.to_string()));
-    }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
 
     // Call WASMI-able commit
-    let encoded_allocation_of_result: u32;
+    let encoded_allocation_of_result: u64;
     unsafe {
-        encoded_allocation_of_result = hc_commit_entry(allocation_of_input.encode() as u32);
+        encoded_allocation_of_result = hc_commit_entry(encoded_allocation_of_input);
     }
     // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result = load_json(encoded_allocation_of_result as u32);
+    let result = load_json_multi_page(encoded_allocation_of_result);
 
     if let Err(err_str) = result {
         return Err(ZomeApiError::Internal(err_str));
     }
     let output: CommitEntryResult = result.unwrap();
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
-
     if output.validation_failure.len() > 0 {
         Err(ZomeApiError::ValidationFailed(output.validation_failure))
     } else {
@@ -216,36 +208,22 @@ pub fn commit_entry(
 /// Retrieves an entry from the local chain or the DHT, by looking it up using
 /// its address.
 pub fn get_entry(address: HashString, _options: GetEntryOptions) -> ZomeApiResult<GetEntryResult> {
-    let mut mem_stack: SinglePageStack;
-    unsafe {
-        mem_stack = G_MEM_STACK.unwrap();
-    }
-
     // Put args in struct and serialize into memory
     let input = GetEntryArgs { address: address };
-    let maybe_allocation_of_input = store_as_json(&mut mem_stack, input);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(ZomeApiError::Internal(err_code.to_string()));
-    }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
 
     // Call WASMI-able get_entry
-    let encoded_allocation_of_result: u32;
+    let encoded_allocation_of_result: u64;
     unsafe {
-        encoded_allocation_of_result = hc_get_entry(allocation_of_input.encode() as u32);
+        encoded_allocation_of_result = hc_get_entry(encoded_allocation_of_input);
     }
     // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result = load_json(encoded_allocation_of_result as u32);
+    let result = load_json_multi_page(encoded_allocation_of_result);
     if let Err(err_str) = result {
         return Err(ZomeApiError::Internal(err_str));
     }
     let result: GetEntryResult = result.unwrap();
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
-
     Ok(result)
 }
 
@@ -257,8 +235,6 @@ pub fn link_entries<S: Into<String>>(
     target: &HashString,
     tag: S,
 ) -> Result<(), ZomeApiError> {
-    let mut mem_stack = unsafe { G_MEM_STACK.unwrap() };
-
     // Put args in struct and serialize into memory
     let input = LinkEntriesArgs {
         base: base.clone(),
@@ -266,20 +242,38 @@ pub fn link_entries<S: Into<String>>(
         tag: tag.into(),
     };
 
-    let allocation_of_input = store_as_json(&mut mem_stack, input)
-        .map_err(|err_code| ZomeApiError::Internal(err_code.to_string()))?;
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
 
-    let encoded_allocation_of_result: u32 =
-        unsafe { hc_link_entries(allocation_of_input.encode() as u32) };
+    let encoded_allocation_of_result: u64 = unsafe { hc_link_entries(encoded_allocation_of_input) };
 
     // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result: LinkEntriesResult = load_json(encoded_allocation_of_result as u32)
+    let result: LinkEntriesResult = load_json_multi_page(encoded_allocation_of_result)
         .map_err(|err_str| ZomeApiError::Internal(err_str))?;
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
+    if result.ok {
+        Ok(())
+    } else {
+        Err(ZomeApiError::Internal(result.error))
+    }
+}
+
+/// Emits a signal that is declared in a capability of the calling zome in the DNA.
+/// Only clients that are listening to that signal through the container receive it,
+/// together with the given arguments.
+pub fn emit_signal<S: Into<String>>(name: S, arguments: serde_json::Value) -> ZomeApiResult<()> {
+    // Put args in struct and serialize into memory
+    let input = EmitSignalArgs {
+        name: name.into(),
+        arguments,
+    };
+
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
+
+    let encoded_allocation_of_result: u64 = unsafe { hc_emit_signal(encoded_allocation_of_input) };
+
+    // Deserialize complex result stored in memory and check for ERROR in encoding
+    let result: EmitSignalResult = load_json_multi_page(encoded_allocation_of_result)
+        .map_err(|err_str| ZomeApiError::Internal(err_str))?;
 
     if result.ok {
         Ok(())
@@ -288,13 +282,28 @@ pub fn link_entries<S: Into<String>>(
     }
 }
 
-/// Not Yet Available
-// Returns a DNA property, which are defined by the DNA developer.
-// They are custom values that are defined in the DNA file
-// that can be used in the zome code for defining configurable behaviors.
-// (e.g. Name, Language, Description, Author, etc.).
-pub fn property<S: Into<String>>(_name: S) -> ZomeApiResult<String> {
-    Err(ZomeApiError::FunctionNotImplemented)
+/// Returns a DNA property, which are defined by the DNA developer.
+/// They are custom values that are defined in the "properties" object of the DNA file
+/// that can be used in the zome code for defining configurable behaviors.
+/// (e.g. Name, Language, Description, Author, etc.).
+/// Fails if the DNA doesn't set the property.
+pub fn property<S: Into<String>>(name: S) -> ZomeApiResult<serde_json::Value> {
+    // Put args in struct and serialize into memory
+    let input = PropertyArgs { key: name.into() };
+
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
+
+    let encoded_allocation_of_result: u64 = unsafe { hc_property(encoded_allocation_of_input) };
+
+    // Deserialize complex result stored in memory and check for ERROR in encoding
+    let result: PropertyResult = load_json_multi_page(encoded_allocation_of_result)
+        .map_err(|err_str| ZomeApiError::Internal(err_str))?;
+
+    if result.ok {
+        Ok(result.value)
+    } else {
+        Err(ZomeApiError::Internal(result.error))
+    }
 }
 
 /// Not Yet Available
@@ -344,29 +353,20 @@ pub fn remove_entry<S: Into<String>>(_entry: HashString, _message: S) -> ZomeApi
 /// used to describe the relationship between the `base` and other entries you wish to lookup. Returns a list of addresses of other
 /// entries which matched as being linked by the given `tag`. Links are created in the first place using the Zome API function `link_entries`.
 pub fn get_links<S: Into<String>>(base: &HashString, tag: S) -> ZomeApiResult<GetLinksResult> {
-    let mut mem_stack = unsafe { G_MEM_STACK.unwrap() };
-
     // Put args in struct and serialize into memory
     let input = GetLinksArgs {
         entry_address: base.clone(),
         tag: tag.into(),
     };
 
-    let allocation_of_input = store_as_json(&mut mem_stack, input)
-        .map_err(|err_code| ZomeApiError::Internal(err_code.to_string()))?;
+    let (_buffer_of_input, encoded_allocation_of_input) = store_input(input)?;
 
-    let encoded_allocation_of_result: u32 =
-        unsafe { hc_get_links(allocation_of_input.encode() as u32) };
+    let encoded_allocation_of_result: u64 = unsafe { hc_get_links(encoded_allocation_of_input) };
 
     // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result: GetLinksResult = load_json(encoded_allocation_of_result as u32)
+    let result: GetLinksResult = load_json_multi_page(encoded_allocation_of_result)
         .map_err(|err_str| ZomeApiError::Internal(err_str))?;
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
-
     if result.ok {
         Ok(result)
     } else {
@@ -1,17 +1,15 @@
-use globals::G_MEM_STACK;
 pub use holochain_wasm_utils::api_serialization::validation::*;
-use holochain_wasm_utils::{memory_allocation::*, memory_serialization::*};
+use holochain_wasm_utils::{
+    holochain_core_types::error::RibosomeReturnCode,
+    memory_allocation::encode_multi_page_return_code, memory_serialization::MultiPageBuffer,
+};
 use serde;
 
-/// Init global memory stack
-pub fn init_global_memory(encoded_allocation_of_input: u32) {
-    unsafe {
-        G_MEM_STACK =
-            Some(SinglePageStack::from_encoded_allocation(encoded_allocation_of_input).unwrap());
-    }
-}
-
 /// Serialize output as json in WASM memory
-pub fn store_and_return_output<T: serde::Serialize>(output: T) -> u32 {
-    unsafe { return store_json_into_encoded_allocation(&mut G_MEM_STACK.unwrap(), output) as u32 }
+/// and hand it over to the Ribosome as the result of the zome function
+pub fn store_and_return_output<T: serde::Serialize>(output: T) -> u64 {
+    match MultiPageBuffer::from_json(output).and_then(|buffer| buffer.leak()) {
+        Ok(encoded_allocation) => encoded_allocation,
+        Err(err_code) => encode_multi_page_return_code(RibosomeReturnCode::Failure(err_code)),
+    }
 }
@@ -2,10 +2,12 @@
 //! which initializes the Zome API Globals with the values it receives from the Ribosome.
 //! It is automatically called at startup of each Zome function call.
 
-use holochain_wasm_utils::{api_serialization::ZomeApiGlobals, memory_serialization::load_json};
+use holochain_wasm_utils::{
+    api_serialization::ZomeApiGlobals, memory_serialization::load_json_multi_page,
+};
 
 extern "C" {
-    fn hc_init_globals(encoded_allocation_of_input: u32) -> u32;
+    fn hc_init_globals(encoded_allocation_of_input: u64) -> u64;
 }
 
 // HC INIT GLOBALS - Secret Api Function
@@ -14,7 +16,7 @@ pub(crate) fn init_globals() -> ZomeApiGlobals {
     // Call WASMI-able init_globals
     let encoded_allocation_of_result = unsafe { hc_init_globals(0) };
     // Deserialize complex result stored in memory
-    let result = load_json(encoded_allocation_of_result as u32);
+    let result = load_json_multi_page(encoded_allocation_of_result);
     if result.is_err() {
         panic!("ZomeApiGlobals should deserialize properly");
     }
@@ -2,10 +2,13 @@
 macro_rules! load_json {
     ($encoded_allocation_of_input:ident) => {{
         let maybe_input =
-            ::hdk::holochain_wasm_utils::memory_serialization::load_json($encoded_allocation_of_input);
+            ::hdk::holochain_wasm_utils::memory_serialization::load_json_multi_page($encoded_allocation_of_input);
         if let Err(_) = maybe_input {
-            return ::hdk::holochain_wasm_utils::holochain_core_types::error::RibosomeErrorCode::ArgumentDeserializationFailed
-                as u32;
+            return ::hdk::holochain_wasm_utils::memory_allocation::encode_multi_page_return_code(
+                ::hdk::holochain_wasm_utils::holochain_core_types::error::RibosomeReturnCode::Failure(
+                    ::hdk::holochain_wasm_utils::holochain_core_types::error::RibosomeErrorCode::ArgumentDeserializationFailed,
+                ),
+            );
         }
         maybe_input
     }};
@@ -20,12 +23,11 @@ macro_rules! load_json {
 /// # extern crate serde;
 /// # extern crate serde_json;
 /// # #[macro_use] extern crate serde_derive;
-/// # use hdk::globals::G_MEM_STACK;
 /// # use holochain_wasm_utils::holochain_core_types::error::RibosomeReturnCode;
 ///
 /// # // Adding empty hc_init_globals() so that the cfg(test) build can link.
 /// # #[no_mangle]
-/// # pub fn hc_init_globals(_: u32) -> u32 { 0 }
+/// # pub fn hc_init_globals(_: u64) -> u64 { 0 }
 ///
 /// # fn main() {
 /// #[derive(Serialize)]
@@ -52,9 +54,7 @@ macro_rules! zome_functions {
 
         $(
             #[no_mangle]
-            pub extern "C" fn $func_name(encoded_allocation_of_input: u32) -> u32 {
-
-                ::hdk::global_fns::init_global_memory(encoded_allocation_of_input);
+            pub extern "C" fn $func_name(encoded_allocation_of_input: u64) -> u64 {
 
                 // Macro'd InputStruct
                 #[derive(Deserialize)]
@@ -91,9 +91,7 @@ macro_rules! validations {
 
         $(
             #[no_mangle]
-            pub extern "C" fn $func_name(encoded_allocation_of_input: u32) -> u32 {
-
-                ::hdk::global_fns::init_global_memory(encoded_allocation_of_input);
+            pub extern "C" fn $func_name(encoded_allocation_of_input: u64) -> u64 {
 
                 // Macro'd InputStruct
                 #[derive(Deserialize)]
@@ -1,6 +1,8 @@
 use entry_definition::ValidatingEntryType;
 use holochain_wasm_utils::{
-    holochain_core_types::error::RibosomeErrorCode, memory_serialization::load_string,
+    holochain_core_types::error::{RibosomeErrorCode, RibosomeReturnCode},
+    memory_allocation::encode_multi_page_return_code,
+    memory_serialization::load_string_multi_page,
 };
 
 trait Ribosome {
@@ -32,20 +34,19 @@ extern "C" {
 
 #[no_mangle]
 pub extern "C" fn __hdk_get_validation_package_for_entry_type(
-    encoded_allocation_of_input: u32,
-) -> u32 {
-    ::global_fns::init_global_memory(encoded_allocation_of_input);
-
+    encoded_allocation_of_input: u64,
+) -> u64 {
     let mut zd = ZomeDefinition::new();
     unsafe {
         zome_setup(&mut zd);
     }
 
     // Deserialize input
-    let maybe_name = load_string(encoded_allocation_of_input);
+    let maybe_name = load_string_multi_page(encoded_allocation_of_input);
     if maybe_name.is_err() {
-        return ::holochain_wasm_utils::holochain_core_types::error::RibosomeErrorCode::ArgumentDeserializationFailed
-            as u32;
+        return encode_multi_page_return_code(RibosomeReturnCode::Failure(
+            RibosomeErrorCode::ArgumentDeserializationFailed,
+        ));
     }
     let name: String = maybe_name.unwrap();
 
@@ -54,7 +55,9 @@ pub extern "C" fn __hdk_get_validation_package_for_entry_type(
         .into_iter()
         .find(|ref entry_type| entry_type.name == name)
     {
-        None => RibosomeErrorCode::CallbackFailed as u32,
+        None => encode_multi_page_return_code(RibosomeReturnCode::Failure(
+            RibosomeErrorCode::CallbackFailed,
+        )),
         Some(mut entry_type_definition) => {
             let package = (*entry_type_definition.package_creator)();
             ::global_fns::store_and_return_output(package)
@@ -9,11 +9,11 @@ extern crate serde_derive;
 extern crate boolinator;
 
 use boolinator::Boolinator;
-use hdk::{globals::G_MEM_STACK, error::ZomeApiError};
+use hdk::error::ZomeApiError;
 use holochain_wasm_utils::{
     memory_allocation::*, memory_serialization::*,
     holochain_core_types::{
-        error::RibosomeErrorCode,
+        error::{RibosomeErrorCode, RibosomeReturnCode},
         hash::HashString,
     },
 };
@@ -24,8 +24,7 @@ use hdk::holochain_dna::zome::entry_types::Sharing;
 use hdk::meta::ZomeDefinition;
 
 #[no_mangle]
-pub extern "C" fn check_global(encoded_allocation_of_input: u32) -> u32 {
-    hdk::global_fns::init_global_memory(encoded_allocation_of_input);
+pub extern "C" fn check_global(_encoded_allocation_of_input: u64) -> u64 {
     #[allow(unused_must_use)]
     {
         hdk::debug(&hdk::DNA_NAME);
@@ -44,23 +43,20 @@ struct CommitOutputStruct {
 }
 
 #[no_mangle]
-pub extern "C" fn check_commit_entry(encoded_allocation_of_input: u32) -> u32 {
+pub extern "C" fn check_commit_entry(encoded_allocation_of_input: u64) -> u64 {
     #[derive(Deserialize, Default)]
     struct CommitInputStruct {
         entry_type_name: String,
         entry_content: String,
     }
 
-    unsafe {
-        G_MEM_STACK =
-            Some(SinglePageStack::from_encoded_allocation(encoded_allocation_of_input).unwrap());
-    }
-
     // Deserialize and check for an encoded error
-    let result = load_json(encoded_allocation_of_input as u32);
+    let result = load_json_multi_page(encoded_allocation_of_input);
     if let Err(err_str) = result {
         hdk::debug(&format!("ERROR: {:?}", err_str)).expect("debug() must work");
-        return RibosomeErrorCode::ArgumentDeserializationFailed as u32;
+        return encode_multi_page_return_code(RibosomeReturnCode::Failure(
+            RibosomeErrorCode::ArgumentDeserializationFailed,
+        ));
     }
 
     let input: CommitInputStruct = result.unwrap();
@@ -72,14 +68,12 @@ pub extern "C" fn check_commit_entry(encoded_allocation_of_input: u32) -> u32 {
         Ok(hash_str) => CommitOutputStruct {
             address: hash_str.to_string(),
         },
-        Err(ZomeApiError::Internal(err_str)) => unsafe {
-            return store_json_into_encoded_allocation(&mut G_MEM_STACK.unwrap(), err_str) as u32;
+        Err(ZomeApiError::Internal(err_str)) => {
+            return hdk::global_fns::store_and_return_output(err_str);
         },
         Err(_) => unreachable!(),
     };
-    unsafe {
-        return store_json_into_encoded_allocation(&mut G_MEM_STACK.unwrap(), res_obj) as u32;
-    }
+    hdk::global_fns::store_and_return_output(res_obj)
 }
 
 //
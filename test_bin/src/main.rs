@@ -1,19 +1,16 @@
-extern crate holochain_agent;
-extern crate holochain_cas_implementations;
-extern crate holochain_core;
-extern crate holochain_core_api;
+extern crate holochain_container;
 extern crate holochain_dna;
 extern crate tempfile;
 
-use holochain_agent::Agent;
-use holochain_cas_implementations::{cas::file::FilesystemStorage, eav::file::EavFileStorage};
-use holochain_core::{context::Context, logger::SimpleLogger, persister::SimplePersister};
-use holochain_core_api::*;
-use holochain_dna::Dna;
-use std::{
-    env,
-    sync::{Arc, Mutex},
+use holochain_container::{
+    config::{
+        AgentConfiguration, Configuration, DnaConfiguration, InstanceConfiguration,
+        StorageConfiguration,
+    },
+    container::Container,
 };
+use holochain_dna::Dna;
+use std::{env, fs};
 
 use tempfile::tempdir;
 
@@ -40,29 +37,54 @@ fn main() {
         usage();
     }
 
-    //let dna = holochain_dna::from_package_file("mydna.hcpkg");
+    //let dna = Dna::from_package_file("mydna.hcpkg");
     let dna = Dna::new();
-    let agent = Agent::from(identity.to_string());
-    let context = Context::new(
-        agent,
-        Arc::new(Mutex::new(SimpleLogger {})),
-        Arc::new(Mutex::new(SimplePersister::new())),
-        FilesystemStorage::new(tempdir.path().to_str().unwrap()).unwrap(),
-        EavFileStorage::new(tempdir.path().to_str().unwrap().to_string()).unwrap(),
-    ).expect("context is supposed to be created");
-    let mut hc = Holochain::new(dna, Arc::new(context)).unwrap();
+    let dna_file = tempdir.path().join("test_bin.dna.json");
+    fs::write(&dna_file, dna.to_json()).expect("couldn't write the DNA file");
+
+    // the container builds the context of the instance out of this configuration
+    let config = Configuration {
+        agents: vec![AgentConfiguration {
+            id: "agent".to_string(),
+            name: identity.to_string(),
+        }],
+        dnas: vec![DnaConfiguration {
+            id: "dna".to_string(),
+            file: dna_file.to_str().unwrap().to_string(),
+            hash: None,
+        }],
+        instances: vec![InstanceConfiguration {
+            id: "test_bin".to_string(),
+            dna: "dna".to_string(),
+            agent: "agent".to_string(),
+            storage: StorageConfiguration::File {
+                path: tempdir.path().join("storage").to_str().unwrap().to_string(),
+            },
+            limits: Default::default(),
+        }],
+        interfaces: Vec::new(),
+    };
+    let mut container = Container::from_config(config);
+    container
+        .load_config()
+        .expect("couldn't create the holochain instance");
     println!("Created a new instance with identity: {}", identity);
 
     // start up the holochain instance
-    hc.start().expect("couldn't start the holochain instance");
+    container
+        .start_all_instances()
+        .expect("couldn't start the holochain instance");
     println!("Started the holochain instance..");
 
     // call a function in the zome code
-    //hc.call("some_fn");
+    //container.instance("test_bin").unwrap().lock().unwrap().call("some_fn");
 
     // get the state
     {
-        let state = hc.state().unwrap();
+        let hc = container
+            .instance("test_bin")
+            .expect("the instance should exist");
+        let state = hc.lock().unwrap().state().unwrap();
         println!("Agent State: {:?}", state.agent());
 
         // do some other stuff with the state here
@@ -70,6 +92,8 @@ fn main() {
     }
 
     // stop the holochain instance
-    hc.stop().expect("couldn't stop the holochain instance");
+    container
+        .stop_all_instances()
+        .expect("couldn't stop the holochain instance");
     println!("Stopped the holochain instance..");
 }
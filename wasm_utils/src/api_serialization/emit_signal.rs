@@ -0,0 +1,13 @@
+use serde_json;
+
+#[derive(Deserialize, Default, Debug, Serialize)]
+pub struct EmitSignalArgs {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Deserialize, Default, Debug, Serialize)]
+pub struct EmitSignalResult {
+    pub ok: bool,
+    pub error: String,
+}
@@ -9,9 +9,11 @@
 /// For the case of HDK-rust we can use the exact same types by
 /// importing this module.
 pub mod commit;
+pub mod emit_signal;
 pub mod get_entry;
 pub mod get_links;
 pub mod link_entries;
+pub mod property;
 pub mod validation;
 mod zome_api_globals;
 
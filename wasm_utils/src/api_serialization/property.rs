@@ -0,0 +1,13 @@
+use serde_json;
+
+#[derive(Deserialize, Default, Debug, Serialize)]
+pub struct PropertyArgs {
+    pub key: String,
+}
+
+#[derive(Deserialize, Default, Debug, Serialize)]
+pub struct PropertyResult {
+    pub ok: bool,
+    pub value: serde_json::Value,
+    pub error: String,
+}
@@ -35,6 +35,7 @@ macro_rules! ribosome_error_report {
             description: $s.to_string(),
             file_name: file!().to_string(),
             line: line!().to_string(),
+            column: column!().to_string(),
         }
     };
 }
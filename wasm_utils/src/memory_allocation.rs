@@ -26,6 +26,28 @@ pub fn u32_merge_bits(high: u16, low: u16) -> u32 {
     (u32::from(high) << 16) | u32::from(low)
 }
 
+pub const U32_MAX: u64 = <u32>::max_value() as u64;
+
+/// returns the u32 high bits from a u64
+pub fn u64_high_bits(i: u64) -> u32 {
+    (i >> 32) as u32
+}
+
+/// returns the u32 low bits from a u64 by doing a lossy cast
+pub fn u64_low_bits(i: u64) -> u32 {
+    (i as u32)
+}
+
+/// splits the high and low bits of u64 into a tuple of u32, for destructuring convenience
+pub fn u64_split_bits(i: u64) -> (u32, u32) {
+    (u64_high_bits(i), u64_low_bits(i))
+}
+
+/// merges 2x u32 into a single u64
+pub fn u64_merge_bits(high: u32, low: u32) -> u64 {
+    (u64::from(high) << 32) | u64::from(low)
+}
+
 pub fn decode_encoded_allocation(
     encoded_allocation: u32,
 ) -> Result<SinglePageAllocation, RibosomeReturnCode> {
@@ -41,6 +63,34 @@ pub fn decode_encoded_allocation(
     }
 }
 
+pub fn decode_encoded_multi_page_allocation(
+    encoded_allocation: u64,
+) -> Result<MultiPageAllocation, RibosomeReturnCode> {
+    let (offset, length) = u64_split_bits(encoded_allocation);
+    // zero length allocation = RibosomeReturnCode
+    if length == 0 {
+        if u64::from(offset) > u64::from(U16_MAX) {
+            return Err(RibosomeReturnCode::Failure(RibosomeErrorCode::Unspecified));
+        }
+        return Err(RibosomeReturnCode::from_offset(offset as u16));
+    }
+    let res = MultiPageAllocation::new(offset, length);
+    match res {
+        Ok(alloc) => Ok(alloc),
+        Err(err_code) => Err(RibosomeReturnCode::Failure(err_code)),
+    }
+}
+
+/// Encodes a return code the way the multi-page ABI passes it around:
+/// the code in the 'offset' and a zero 'length'.
+pub fn encode_multi_page_return_code(return_code: RibosomeReturnCode) -> u64 {
+    match return_code {
+        RibosomeReturnCode::Success => 0,
+        // error codes already hold their offset in the upper 16 bits of a u32
+        RibosomeReturnCode::Failure(err_code) => u64::from(err_code as u32) << 16,
+    }
+}
+
 //--------------------------------------------------------------------------------------------------
 // Single Page Memory Allocation
 //--------------------------------------------------------------------------------------------------
@@ -146,6 +196,67 @@ impl SinglePageStack {
     }
 }
 
+//--------------------------------------------------------------------------------------------------
+// Multi Page Memory Allocation
+//--------------------------------------------------------------------------------------------------
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+/// MultiPageAllocation is a memory allocation anywhere in the 4GiB a WASM memory can address.
+/// Arguments and results of zomes built against the multi-page ABI are passed around as
+/// MultiPageAllocations encoded in a u64, instead of SinglePageAllocations encoded in a u32.
+pub struct MultiPageAllocation {
+    offset: u32,
+    length: u32,
+}
+
+impl MultiPageAllocation {
+    pub fn new(offset: u32, length: u32) -> Result<Self, RibosomeErrorCode> {
+        if u64::from(offset) + u64::from(length) > U32_MAX {
+            return Err(RibosomeErrorCode::OutOfMemory);
+        }
+        if length == 0 {
+            if offset == 0 {
+                return Err(RibosomeErrorCode::ZeroSizedAllocation);
+            }
+            return Err(RibosomeErrorCode::NotAnAllocation);
+        }
+        Ok(MultiPageAllocation { offset, length })
+    }
+
+    /// An Encoded Allocation is a u64 where 'offset' is first 32-bits and 'length' last 32-bits
+    /// A valid allocation must not have a length of zero
+    /// An Encoded Allocation with an offset but no length is actually an encoding of an ErrorCode
+    pub fn from_encoded_allocation(encoded_allocation: u64) -> Result<Self, RibosomeErrorCode> {
+        let maybe_allocation = decode_encoded_multi_page_allocation(encoded_allocation);
+        match maybe_allocation {
+            Err(_) => Err(RibosomeErrorCode::NotAnAllocation),
+            Ok(allocation) => Ok(allocation),
+        }
+    }
+
+    /// returns a single u64 value encoding both the u32 offset and length values
+    pub fn encode(self) -> u64 {
+        u64_merge_bits(self.offset, self.length)
+    }
+
+    // getters
+    pub fn offset(self) -> u32 {
+        self.offset
+    }
+    pub fn length(self) -> u32 {
+        self.length
+    }
+}
+
+impl From<SinglePageAllocation> for MultiPageAllocation {
+    fn from(allocation: SinglePageAllocation) -> Self {
+        MultiPageAllocation {
+            offset: u32::from(allocation.offset()),
+            length: u32::from(allocation.length()),
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -353,4 +464,74 @@ pub mod tests {
         );
     }
 
+    #[test]
+    /// tests that we can split a u64 into a tuple of high/low bits and merge it back
+    fn u64_split_and_merge_bits() {
+        let i = 0xAAAA_AAAA_5555_5555;
+        assert_eq!((0xAAAA_AAAA, 0x5555_5555), super::u64_split_bits(i));
+        assert_eq!(0xAAAA_AAAA, super::u64_high_bits(i));
+        assert_eq!(0x5555_5555, super::u64_low_bits(i));
+        assert_eq!(i, super::u64_merge_bits(0xAAAA_AAAA, 0x5555_5555));
+    }
+
+    #[test]
+    /// tests that a MultiPageAllocation can be bigger than a page and round trips its encoding
+    fn can_multi_page_allocation_encode() {
+        let allocation = MultiPageAllocation::new(3 * 65536, 5 * 65536).unwrap();
+        assert_eq!(3 * 65536, allocation.offset());
+        assert_eq!(5 * 65536, allocation.length());
+        assert_eq!(
+            allocation,
+            MultiPageAllocation::from_encoded_allocation(allocation.encode()).unwrap()
+        );
+        assert_eq!(
+            MultiPageAllocation::new(1, 20).unwrap(),
+            MultiPageAllocation::from(SinglePageAllocation::new(1, 20).unwrap())
+        );
+    }
+
+    #[test]
+    fn can_multi_page_allocation_new_fail() {
+        assert_eq!(
+            RibosomeErrorCode::ZeroSizedAllocation,
+            MultiPageAllocation::new(0, 0).err().unwrap()
+        );
+        assert_eq!(
+            RibosomeErrorCode::NotAnAllocation,
+            MultiPageAllocation::new(1, 0).err().unwrap()
+        );
+        assert_eq!(
+            RibosomeErrorCode::OutOfMemory,
+            MultiPageAllocation::new(<u32>::max_value(), 1)
+                .err()
+                .unwrap()
+        );
+        assert!(MultiPageAllocation::new(0, <u32>::max_value()).is_ok());
+    }
+
+    #[test]
+    /// tests that return codes round trip through the multi-page encoding
+    fn can_decode_encoded_multi_page_return_code() {
+        assert_eq!(
+            0,
+            encode_multi_page_return_code(RibosomeReturnCode::Success)
+        );
+        assert_eq!(
+            RibosomeReturnCode::Success,
+            decode_encoded_multi_page_allocation(0).unwrap_err(),
+        );
+        let encoded = encode_multi_page_return_code(RibosomeReturnCode::Failure(
+            RibosomeErrorCode::OutOfMemory,
+        ));
+        assert_eq!(3_u64 << 32, encoded);
+        assert_eq!(
+            RibosomeReturnCode::Failure(RibosomeErrorCode::OutOfMemory),
+            decode_encoded_multi_page_allocation(encoded).unwrap_err(),
+        );
+        assert_eq!(
+            // nonsense offset = generic error
+            RibosomeReturnCode::Failure(RibosomeErrorCode::Unspecified),
+            decode_encoded_multi_page_allocation(0xFFFF_FFFF_0000_0000).unwrap_err(),
+        );
+    }
 }
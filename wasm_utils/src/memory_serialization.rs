@@ -1,10 +1,11 @@
 use holochain_core_types::error::{RibosomeErrorCode, RibosomeErrorReport};
 use memory_allocation::{
-    decode_encoded_allocation, SinglePageAllocation, SinglePageStack, U16_MAX,
+    decode_encoded_allocation, decode_encoded_multi_page_allocation, MultiPageAllocation,
+    SinglePageAllocation, SinglePageStack, U16_MAX, U32_MAX,
 };
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::{ffi::CStr, os::raw::c_char, slice};
+use std::{ffi::CStr, mem, os::raw::c_char, slice, str};
 
 //-------------------------------------------------------------------------------------------------
 // Raw
@@ -106,7 +107,10 @@ pub fn load_json<'s, T: Deserialize<'s>>(encoded_allocation: u32) -> Result<T, S
 #[allow(unknown_lints)]
 #[allow(not_unsafe_ptr_arg_deref)]
 pub fn load_json_from_raw<'s, T: Deserialize<'s>>(ptr_data: *mut c_char) -> Result<T, String> {
-    let stored_str = load_str_from_raw(ptr_data);
+    load_json_from_str(load_str_from_raw(ptr_data))
+}
+
+fn load_json_from_str<'s, T: Deserialize<'s>>(stored_str: &'s str) -> Result<T, String> {
     let maybe_obj: Result<T, serde_json::Error> = serde_json::from_str(stored_str);
     match maybe_obj {
         Ok(obj) => Ok(obj),
@@ -121,3 +125,81 @@ pub fn load_json_from_raw<'s, T: Deserialize<'s>>(ptr_data: *mut c_char) -> Resu
         }
     }
 }
+
+//-------------------------------------------------------------------------------------------------
+// Multi-page
+//-------------------------------------------------------------------------------------------------
+
+/// Bytes a zome built against the multi-page ABI hands over to the Ribosome.
+/// They live on the heap of the zome, so the Ribosome can read them wherever they are,
+/// as long as the buffer isn't dropped.
+pub struct MultiPageBuffer {
+    bytes: Vec<u8>,
+}
+
+impl MultiPageBuffer {
+    pub fn new(bytes: Vec<u8>) -> Result<Self, RibosomeErrorCode> {
+        if bytes.is_empty() {
+            return Err(RibosomeErrorCode::ZeroSizedAllocation);
+        }
+        if bytes.len() as u64 > U32_MAX {
+            return Err(RibosomeErrorCode::OutOfMemory);
+        }
+        Ok(MultiPageBuffer { bytes })
+    }
+
+    /// Serialize a data struct as a json string into a new buffer.
+    pub fn from_json<T: Serialize>(internal: T) -> Result<Self, RibosomeErrorCode> {
+        let json_bytes = serde_json::to_vec(&internal)
+            .map_err(|_| RibosomeErrorCode::ResponseSerializationFailed)?;
+        MultiPageBuffer::new(json_bytes)
+    }
+
+    /// The allocation of the buffer in wasm memory.
+    /// Only meaningful inside a wasm module, where pointers are 32 bits wide.
+    pub fn allocation(&self) -> Result<MultiPageAllocation, RibosomeErrorCode> {
+        MultiPageAllocation::new(self.bytes.as_ptr() as usize as u32, self.bytes.len() as u32)
+    }
+
+    /// Hands the buffer over for good and returns its encoded allocation,
+    /// e.g. to return it as the result of a zome function.
+    pub fn leak(self) -> Result<u64, RibosomeErrorCode> {
+        let encoded_allocation = self.allocation()?.encode();
+        mem::forget(self);
+        Ok(encoded_allocation)
+    }
+}
+
+/// Get the bytes of a MultiPageAllocation, without the string terminate character
+/// the Ribosome appends to what it stores.
+fn load_bytes_from_multi_page<'a>(allocation: MultiPageAllocation) -> &'a [u8] {
+    let bytes = unsafe {
+        slice::from_raw_parts(
+            allocation.offset() as usize as *const u8,
+            allocation.length() as usize,
+        )
+    };
+    match bytes.split_last() {
+        Some((&0, content)) => content,
+        _ => bytes,
+    }
+}
+
+fn load_str_from_multi_page<'a>(encoded_allocation: u64) -> Result<&'a str, String> {
+    let allocation = decode_encoded_multi_page_allocation(encoded_allocation)
+        .map_err(|code| code.to_string())?;
+    str::from_utf8(load_bytes_from_multi_page(allocation))
+        .map_err(|_| RibosomeErrorCode::ArgumentDeserializationFailed.to_string())
+}
+
+/// Retrieve a stored string from an encoded multi-page allocation.
+/// Return error string if encoded_allocation is invalid.
+pub fn load_string_multi_page(encoded_allocation: u64) -> Result<String, String> {
+    load_str_from_multi_page(encoded_allocation).map(|s| s.to_string())
+}
+
+/// Retrieve a stored data struct from an encoded multi-page allocation.
+/// Return error string if encoded_allocation is invalid.
+pub fn load_json_multi_page<'s, T: Deserialize<'s>>(encoded_allocation: u64) -> Result<T, String> {
+    load_json_from_str(load_str_from_multi_page(encoded_allocation)?)
+}